@@ -0,0 +1,525 @@
+use crate::ec::EcLevel;
+use crate::eci::Charset;
+use crate::encoding::Encoding;
+use crate::error::QrError;
+use crate::fnc1::{fnc1_charset, Fnc1};
+use crate::mask::MaskSelection;
+use crate::preprocessor::Preprocessor;
+use crate::qrcode::QrCode;
+use crate::version::Version;
+use std::ops::RangeInclusive;
+
+/// Builds a [`QrCode`] from text or raw bytes.
+///
+/// ```
+/// use qrcode::{EcLevel, MaskSelection, QrCodeBuilder, Version};
+///
+/// let qrcode = QrCodeBuilder::new("HELLO WORLD")
+///     .ec_level(EcLevel::M)
+///     .mask(MaskSelection::Auto)
+///     .min_version(3)
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(qrcode.version(), Version::Normal(3));
+/// ```
+pub struct QrCodeBuilder {
+    payload: Payload,
+    encoding: Option<Encoding>,
+    charset: Option<Charset>,
+    fnc1: Option<Fnc1>,
+    ec_level: EcLevel,
+    boost_ec: bool,
+    mask: MaskSelection,
+    min_version: Version,
+    max_version: Version,
+    micro: bool,
+    rmqr: bool,
+}
+
+enum Payload {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl QrCodeBuilder {
+    pub fn new(data: &str) -> QrCodeBuilder {
+        Self::with_payload(Payload::Text(data.to_string()))
+    }
+
+    /// Encodes `data` as is in byte mode, without any ECI header. The encoding, charset and
+    /// FNC1 options do not apply.
+    pub fn binary(data: &[u8]) -> QrCodeBuilder {
+        Self::with_payload(Payload::Binary(data.to_vec()))
+    }
+
+    fn with_payload(payload: Payload) -> QrCodeBuilder {
+        QrCodeBuilder {
+            payload,
+            encoding: None,
+            charset: None,
+            fnc1: None,
+            ec_level: EcLevel::M,
+            boost_ec: false,
+            mask: MaskSelection::Auto,
+            min_version: Version::Normal(1),
+            max_version: Version::Normal(40),
+            micro: false,
+            rmqr: false,
+        }
+    }
+
+    /// Encodes the whole data in a single mode. By default the data is split into the
+    /// shortest mix of modes.
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = Some(encoding);
+        self
+    }
+
+    /// Transcodes byte mode data to `charset`, announced with an ECI header. By default the
+    /// data is ISO-8859-1, or UTF-8 if it has characters outside of ISO-8859-1 and kanji.
+    pub fn charset(mut self, charset: Charset) -> Self {
+        self.charset = Some(charset);
+        self
+    }
+
+    /// Marks the data as GS1 ([`Fnc1::First`]) or industry specific ([`Fnc1::Second`]) with an
+    /// FNC1 mode indicator. Fields are separated with [`crate::fnc1::GS`], and byte mode data
+    /// uses the default charset: building fails if [`Self::charset`] sets another one.
+    pub fn fnc1(mut self, fnc1: Fnc1) -> Self {
+        self.fnc1 = Some(fnc1);
+        self
+    }
+
+    /// Minimum ec level. See [`Self::boost_ec`] to use a higher one when possible.
+    pub fn ec_level(mut self, ec_level: EcLevel) -> Self {
+        self.ec_level = ec_level;
+        self
+    }
+
+    /// Raises the ec level as far as the data still fits in the chosen version, so the symbol
+    /// does not grow.
+    pub fn boost_ec(mut self, boost_ec: bool) -> Self {
+        self.boost_ec = boost_ec;
+        self
+    }
+
+    pub fn mask(mut self, mask: MaskSelection) -> Self {
+        self.mask = mask;
+        self
+    }
+
+    /// Exact version to use, for a fixed symbol size. Same as setting both the smallest and the
+    /// largest version.
+    pub fn version(self, version: u8) -> Self {
+        self.min_version(version).max_version(version)
+    }
+
+    /// Exact Micro QR version, M1 to M4.
+    pub fn micro_version(mut self, version: u8) -> Self {
+        self.min_version = Version::Micro(version);
+        self.max_version = Version::Micro(version);
+        self
+    }
+
+    /// Exact rMQR version, from R7x43 to R17x139.
+    pub fn rmqr_version(mut self, height: u8, width: u8) -> Self {
+        self.min_version = Version::Rmqr(height, width);
+        self.max_version = Version::Rmqr(height, width);
+        self
+    }
+
+    /// Smallest version to use, even if the data fits in a smaller one.
+    pub fn min_version(mut self, min_version: u8) -> Self {
+        self.min_version = Version::Normal(min_version);
+        self
+    }
+
+    /// Largest version to use. Data that does not fit in it is rejected.
+    pub fn max_version(mut self, max_version: u8) -> Self {
+        self.max_version = Version::Normal(max_version);
+        self
+    }
+
+    /// Tries the Micro QR versions M1 to M4 before version 1, unless the smallest version is
+    /// above 1. They only hold a few characters, without ECI nor ec level H.
+    pub fn micro(mut self, micro: bool) -> Self {
+        self.micro = micro;
+        self
+    }
+
+    /// Uses a rectangular Micro QR code (rMQR) instead of a square symbol, with the lowest
+    /// height holding the data and then the narrowest width, unless an exact rMQR version is
+    /// set. The smallest and largest versions do not apply, and the ec level is M or H.
+    pub fn rmqr(mut self, rmqr: bool) -> Self {
+        self.rmqr = rmqr;
+        self
+    }
+
+    /// Versions to pick from, following the Micro QR and rMQR options.
+    fn versions(&self) -> RangeInclusive<Version> {
+        match self.min_version {
+            Version::Normal(_) if self.rmqr => Version::Rmqr(7, 43)..=Version::Rmqr(17, 139),
+            Version::Normal(1) if self.micro => Version::Micro(1)..=self.max_version,
+            min_version => min_version..=self.max_version,
+        }
+    }
+
+    pub fn build(&self) -> Result<QrCode, QrError> {
+        self.preprocessor()?.generate_qrcode()
+    }
+
+    /// Version the built QR code takes, without laying out its modules.
+    pub fn smallest_version(&self) -> Result<Version, QrError> {
+        Ok(self.preprocessor()?.version())
+    }
+
+    fn preprocessor(&self) -> Result<Preprocessor, QrError> {
+        let versions = self.versions();
+        match (&self.payload, self.fnc1) {
+            (Payload::Text(_), Some(_)) if self.charset.is_some() => Err(fnc1_charset()),
+            (Payload::Text(data), Some(fnc1)) => Preprocessor::fnc1(
+                data,
+                fnc1,
+                self.encoding,
+                self.ec_level,
+                self.boost_ec,
+                self.mask,
+                versions,
+            ),
+            (Payload::Text(data), None) => Preprocessor::encode(
+                data,
+                self.encoding,
+                self.charset,
+                self.ec_level,
+                self.boost_ec,
+                self.mask,
+                versions,
+            ),
+            (Payload::Binary(data), _) => {
+                Preprocessor::binary(data, self.ec_level, self.boost_ec, self.mask, versions)
+            }
+        }
+    }
+
+    /// Builds a Structured Append sequence of up to 16 symbols of the same version, or a single
+    /// symbol when the data fits in one. Read them back with [`crate::decoder::reassemble`].
+    ///
+    /// Only regular QR codes link into sequences, so the Micro QR and rMQR options are
+    /// rejected, and the ec level is not boosted.
+    pub fn build_structured_append(&self) -> Result<Vec<QrCode>, QrError> {
+        let versions = self.versions();
+        let preprocessors = match &self.payload {
+            Payload::Text(data) => Preprocessor::structured_append(
+                data,
+                self.encoding,
+                self.charset,
+                self.fnc1,
+                self.ec_level,
+                self.mask,
+                versions,
+            )?,
+            Payload::Binary(data) => {
+                Preprocessor::structured_append_binary(data, self.ec_level, self.mask, versions)?
+            }
+        };
+
+        preprocessors
+            .iter()
+            .map(Preprocessor::generate_qrcode)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::decode;
+    use crate::mask::MaskPattern;
+    use crate::segment::Segment;
+
+    #[test]
+    fn build_uses_defaults() {
+        let qrcode = QrCodeBuilder::new("HELLO WORLD").build().unwrap();
+        assert_eq!(qrcode.version(), Version::Normal(1));
+        assert_eq!(qrcode.ec_level(), EcLevel::M);
+        assert!(qrcode.mask_scores().is_some());
+        assert_eq!(decode(&qrcode).unwrap().text().unwrap(), "HELLO WORLD");
+    }
+
+    #[test]
+    fn build_applies_options() {
+        let qrcode = QrCodeBuilder::new("12345")
+            .encoding(Encoding::Byte)
+            .ec_level(EcLevel::H)
+            .mask(MaskSelection::Fixed(MaskPattern::Diagonal))
+            .min_version(5)
+            .build()
+            .unwrap();
+
+        assert_eq!(qrcode.version(), Version::Normal(5));
+        assert_eq!(qrcode.ec_level(), EcLevel::H);
+        assert_eq!(qrcode.mask_pattern(), MaskPattern::Diagonal);
+
+        let decoded = decode(&qrcode).unwrap();
+        assert_eq!(decoded.segments[0].encoding, Encoding::Byte);
+        assert_eq!(decoded.text().unwrap(), "12345");
+    }
+
+    #[test]
+    fn build_falls_back_to_utf8() {
+        let qrcode = QrCodeBuilder::new("HELLO 😀").build().unwrap();
+        let decoded = decode(&qrcode).unwrap();
+        assert_eq!(decoded.text().unwrap(), "HELLO 😀");
+        assert!(decoded
+            .segments
+            .iter()
+            .any(|segment| segment.charset == Charset::Utf8));
+
+        // ISO-8859-1 is kept when it is enough
+        let decoded = decode(&QrCodeBuilder::new("café").build().unwrap()).unwrap();
+        assert_eq!(decoded.segments[0].charset, Charset::Iso8859_1);
+    }
+
+    #[test]
+    fn build_uses_requested_charset() {
+        let qrcode = QrCodeBuilder::new("Жук")
+            .charset(Charset::Iso8859_5)
+            .build()
+            .unwrap();
+        let decoded = decode(&qrcode).unwrap();
+        assert_eq!(decoded.segments[0].charset, Charset::Iso8859_5);
+        assert_eq!(decoded.text().unwrap(), "Жук");
+    }
+
+    #[test]
+    fn build_encodes_binary_data() {
+        let data: Vec<u8> = (0..=255).collect();
+        let qrcode = QrCodeBuilder::binary(&data).build().unwrap();
+
+        let decoded = decode(&qrcode).unwrap();
+        assert_eq!(decoded.segments, vec![Segment::binary(&data)]);
+        assert_eq!(decoded.bytes(), data);
+    }
+
+    #[test]
+    fn build_grows_version_to_fit() {
+        let data = "A".repeat(100);
+        let qrcode = QrCodeBuilder::new(&data)
+            .ec_level(EcLevel::H)
+            .build()
+            .unwrap();
+        assert_eq!(qrcode.version(), Version::Normal(8));
+    }
+
+    #[test]
+    fn build_rejects_bad_input() {
+        assert_eq!(
+            QrCodeBuilder::new("12a")
+                .encoding(Encoding::Numeric)
+                .build()
+                .err(),
+            Some(QrError::InvalidCharacter {
+                ch: 'a',
+                position: 2,
+                mode: Encoding::Numeric
+            })
+        );
+        assert_eq!(
+            QrCodeBuilder::new("abc").min_version(41).build().err(),
+            Some(QrError::InvalidVersion(Version::Normal(41)))
+        );
+    }
+
+    #[test]
+    fn build_respects_max_version() {
+        let data = "A".repeat(100);
+        assert_eq!(
+            QrCodeBuilder::new(&data)
+                .ec_level(EcLevel::H)
+                .max_version(7)
+                .build()
+                .err(),
+            Some(QrError::DataTooLong {
+                needed_bits: 4 + 9 + 50 * 11,
+                max_bits: 66 * 8,
+                version: Version::Normal(7),
+                ec_level: EcLevel::H
+            })
+        );
+        assert_eq!(
+            QrCodeBuilder::new(&data).max_version(41).build().err(),
+            Some(QrError::InvalidVersion(Version::Normal(41)))
+        );
+    }
+
+    #[test]
+    fn build_uses_exact_version() {
+        let qrcode = QrCodeBuilder::new("HELLO").version(4).build().unwrap();
+        assert_eq!(qrcode.version(), Version::Normal(4));
+
+        assert_eq!(
+            QrCodeBuilder::new(&"A".repeat(30)).version(1).build().err(),
+            Some(QrError::DataTooLong {
+                needed_bits: 4 + 9 + 15 * 11,
+                max_bits: 16 * 8,
+                version: Version::Normal(1),
+                ec_level: EcLevel::M
+            })
+        );
+    }
+
+    #[test]
+    fn build_boosts_ec_level_without_growing() {
+        let qrcode = QrCodeBuilder::new("HELLO WORLD")
+            .ec_level(EcLevel::L)
+            .boost_ec(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            (qrcode.version(), qrcode.ec_level()),
+            (Version::Normal(1), EcLevel::Q)
+        );
+        assert_eq!(decode(&qrcode).unwrap().text().unwrap(), "HELLO WORLD");
+
+        // A fixed version leaves more room to boost
+        let qrcode = QrCodeBuilder::new("HELLO WORLD")
+            .ec_level(EcLevel::L)
+            .boost_ec(true)
+            .version(2)
+            .build()
+            .unwrap();
+        assert_eq!(
+            (qrcode.version(), qrcode.ec_level()),
+            (Version::Normal(2), EcLevel::H)
+        );
+    }
+
+    #[test]
+    fn build_picks_micro_qr_when_allowed() {
+        let qrcode = QrCodeBuilder::new("12345").micro(true).build().unwrap();
+        assert_eq!(qrcode.version(), Version::Micro(2));
+        assert_eq!(qrcode.width(), 13);
+
+        let decoded = decode(&qrcode).unwrap();
+        assert_eq!(decoded.version, Version::Micro(2));
+        assert_eq!(decoded.text().unwrap(), "12345");
+
+        // Not below the smallest version
+        let qrcode = QrCodeBuilder::new("12345")
+            .micro(true)
+            .min_version(2)
+            .build()
+            .unwrap();
+        assert_eq!(qrcode.version(), Version::Normal(2));
+    }
+
+    #[test]
+    fn build_picks_the_lowest_rmqr_height() {
+        let qrcode = QrCodeBuilder::new("HELLO WORLD")
+            .rmqr(true)
+            .build()
+            .unwrap();
+        assert_eq!(qrcode.version(), Version::Rmqr(7, 59));
+        assert_eq!((qrcode.width(), qrcode.height()), (59, 7));
+        assert_eq!(decode(&qrcode).unwrap().text().unwrap(), "HELLO WORLD");
+
+        let qrcode = QrCodeBuilder::new("HELLO WORLD")
+            .rmqr_version(13, 27)
+            .ec_level(EcLevel::M)
+            .build()
+            .unwrap();
+        assert_eq!(qrcode.version(), Version::Rmqr(13, 27));
+        assert_eq!(decode(&qrcode).unwrap().text().unwrap(), "HELLO WORLD");
+
+        let result = QrCodeBuilder::new("HELLO")
+            .rmqr(true)
+            .ec_level(EcLevel::L)
+            .build();
+        assert!(matches!(result, Err(QrError::Unsupported(_))));
+    }
+
+    #[test]
+    fn build_uses_exact_micro_version() {
+        let qrcode = QrCodeBuilder::new("HELLO")
+            .micro_version(4)
+            .ec_level(EcLevel::L)
+            .boost_ec(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            (qrcode.version(), qrcode.ec_level()),
+            (Version::Micro(4), EcLevel::Q)
+        );
+        assert_eq!(decode(&qrcode).unwrap().text().unwrap(), "HELLO");
+
+        assert!(matches!(
+            QrCodeBuilder::new("HELLO")
+                .micro_version(2)
+                .mask(MaskSelection::Fixed(MaskPattern::Checkerboard))
+                .build(),
+            Err(QrError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn build_reports_needed_and_available_bits() {
+        // 4 + 14 header bits, 2666 groups of 3 digits and a group of 2
+        assert_eq!(
+            QrCodeBuilder::new(&"9".repeat(8000)).build().err(),
+            Some(QrError::DataTooLong {
+                needed_bits: 26685,
+                max_bits: 18672,
+                version: Version::Normal(40),
+                ec_level: EcLevel::M
+            })
+        );
+    }
+
+    #[test]
+    fn build_structured_append_links_symbols_of_one_version() {
+        let data = "9".repeat(8000);
+        let qrcodes = QrCodeBuilder::new(&data).build_structured_append().unwrap();
+        assert_eq!(qrcodes.len(), 2);
+        assert_eq!(qrcodes[0].version(), qrcodes[1].version());
+
+        let decoded: Vec<_> = qrcodes
+            .iter()
+            .map(|qrcode| decode(qrcode).unwrap())
+            .collect();
+        let segments = crate::decoder::reassemble(&decoded).unwrap();
+        let text: Option<String> = segments.iter().map(Segment::text).collect();
+        assert_eq!(text.unwrap(), data);
+
+        let result = QrCodeBuilder::new("HELLO")
+            .rmqr(true)
+            .build_structured_append();
+        assert!(matches!(result, Err(QrError::Unsupported(_))));
+    }
+
+    #[test]
+    fn build_marks_fnc1_data() {
+        let data = "01095011010209171719050810ABCD1234\u{1D}2110";
+        let qrcode = QrCodeBuilder::new(data).fnc1(Fnc1::First).build().unwrap();
+
+        let decoded = decode(&qrcode).unwrap();
+        assert_eq!(decoded.fnc1, Some(Fnc1::First));
+        assert_eq!(decoded.text().unwrap(), data);
+        assert!(decoded
+            .segments
+            .iter()
+            .any(|segment| segment.data.contains(&b'%')));
+    }
+
+    #[test]
+    fn build_rejects_a_charset_with_fnc1() {
+        let builder = QrCodeBuilder::new("10ABC")
+            .fnc1(Fnc1::First)
+            .charset(Charset::Iso8859_5);
+        assert!(matches!(builder.build(), Err(QrError::Unsupported(_))));
+        assert!(matches!(
+            builder.build_structured_append(),
+            Err(QrError::Unsupported(_))
+        ));
+    }
+}
@@ -0,0 +1,469 @@
+use crate::builder::QrCodeBuilder;
+use crate::ec::EcLevel;
+use crate::error::QrError;
+use crate::qrcode::QrCode;
+
+/// Payload format of a contact.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ContactFormat {
+    /// vCard 3.0, RFC 2426.
+    VCard3,
+    /// vCard 4.0, RFC 6350.
+    VCard4,
+    /// NTT DoCoMo MeCard, the most compact one. It has no title field, so the title goes to the
+    /// note.
+    MeCard,
+}
+
+impl ContactFormat {
+    pub const ALL: [ContactFormat; 3] = [
+        ContactFormat::VCard3,
+        ContactFormat::VCard4,
+        ContactFormat::MeCard,
+    ];
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PhoneType {
+    Cell,
+    Home,
+    Work,
+    Fax,
+}
+
+impl PhoneType {
+    fn name(self) -> &'static str {
+        match self {
+            PhoneType::Cell => "cell",
+            PhoneType::Home => "home",
+            PhoneType::Work => "work",
+            PhoneType::Fax => "fax",
+        }
+    }
+}
+
+/// Postal address, every part being optional.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Address {
+    pub street: String,
+    pub city: String,
+    pub region: String,
+    pub postal_code: String,
+    pub country: String,
+}
+
+impl Address {
+    fn parts(&self) -> [&str; 5] {
+        [
+            &self.street,
+            &self.city,
+            &self.region,
+            &self.postal_code,
+            &self.country,
+        ]
+    }
+}
+
+/// Business card payload, as a vCard or a MeCard.
+///
+/// ```
+/// use qrcode::contact::{ContactFormat, PhoneType};
+/// use qrcode::Contact;
+///
+/// let contact = Contact::new("Jane", "Doe")
+///     .phone("+41 44 668 18 00", PhoneType::Work)
+///     .email("jane@example.com");
+/// assert_eq!(
+///     contact.data(ContactFormat::MeCard).unwrap(),
+///     "MECARD:N:Doe,Jane;TEL:+41 44 668 18 00;EMAIL:jane@example.com;;"
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Contact {
+    given_name: String,
+    family_name: String,
+    phones: Vec<(String, PhoneType)>,
+    emails: Vec<String>,
+    organization: Option<String>,
+    title: Option<String>,
+    addresses: Vec<Address>,
+    urls: Vec<String>,
+}
+
+impl Contact {
+    pub fn new(given_name: &str, family_name: &str) -> Contact {
+        Contact {
+            given_name: given_name.to_string(),
+            family_name: family_name.to_string(),
+            ..Contact::default()
+        }
+    }
+
+    pub fn phone(mut self, number: &str, phone_type: PhoneType) -> Self {
+        self.phones.push((number.to_string(), phone_type));
+        self
+    }
+
+    pub fn email(mut self, email: &str) -> Self {
+        self.emails.push(email.to_string());
+        self
+    }
+
+    pub fn organization(mut self, organization: &str) -> Self {
+        self.organization = Some(organization.to_string());
+        self
+    }
+
+    /// Job title.
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    pub fn address(mut self, address: Address) -> Self {
+        self.addresses.push(address);
+        self
+    }
+
+    pub fn url(mut self, url: &str) -> Self {
+        self.urls.push(url.to_string());
+        self
+    }
+
+    /// Payload in the given format. vCard lines end with CRLF and are folded at 75 bytes.
+    pub fn data(&self, format: ContactFormat) -> Result<String, QrError> {
+        if self.given_name.is_empty() && self.family_name.is_empty() {
+            return Err(QrError::InvalidPayload(
+                "a contact needs a name".to_string(),
+            ));
+        }
+
+        Ok(match format {
+            ContactFormat::VCard3 | ContactFormat::VCard4 => self.vcard(format)?,
+            ContactFormat::MeCard => self.mecard(),
+        })
+    }
+
+    /// Builder of a QR code holding the payload in the given format.
+    pub fn builder(&self, format: ContactFormat) -> Result<QrCodeBuilder, QrError> {
+        Ok(QrCodeBuilder::new(&self.data(format)?))
+    }
+
+    /// QR code of at most `max_version` at `ec_level`, in the `preferred` format if it fits,
+    /// or else in the shortest other format that does. The format used is returned with it.
+    pub fn build_within(
+        &self,
+        preferred: ContactFormat,
+        ec_level: EcLevel,
+        max_version: u8,
+    ) -> Result<(ContactFormat, QrCode), QrError> {
+        let mut others = vec![];
+        for format in ContactFormat::ALL.into_iter().filter(|f| *f != preferred) {
+            others.push((self.data(format)?.len(), format));
+        }
+        others.sort_by_key(|(length, _)| *length);
+
+        let build = |format| {
+            self.builder(format)?
+                .ec_level(ec_level)
+                .max_version(max_version)
+                .build()
+        };
+        let error = match build(preferred) {
+            Ok(qrcode) => return Ok((preferred, qrcode)),
+            Err(error @ QrError::DataTooLong { .. }) => error,
+            Err(error) => return Err(error),
+        };
+
+        others
+            .into_iter()
+            .find_map(|(_, format)| build(format).ok().map(|qrcode| (format, qrcode)))
+            .ok_or(error)
+    }
+
+    fn full_name(&self) -> String {
+        [&self.given_name, &self.family_name]
+            .into_iter()
+            .filter(|name| !name.is_empty())
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn vcard(&self, format: ContactFormat) -> Result<String, QrError> {
+        let v4 = format == ContactFormat::VCard4;
+        let kind = |name: &str| match v4 {
+            true => name.to_string(),
+            false => name.to_uppercase(),
+        };
+
+        let mut lines = vec![
+            "BEGIN:VCARD".to_string(),
+            format!("VERSION:{}", if v4 { "4.0" } else { "3.0" }),
+            format!(
+                "N:{};{};;;",
+                escape_text(&self.family_name),
+                escape_text(&self.given_name)
+            ),
+            format!("FN:{}", escape_text(&self.full_name())),
+        ];
+        if let Some(organization) = &self.organization {
+            lines.push(format!("ORG:{}", escape_text(organization)));
+        }
+        if let Some(title) = &self.title {
+            lines.push(format!("TITLE:{}", escape_text(title)));
+        }
+        for (number, phone_type) in &self.phones {
+            lines.push(match v4 {
+                true => format!(
+                    "TEL;VALUE=uri;TYPE={}:{}",
+                    phone_type.name(),
+                    tel_uri(number)?
+                ),
+                false => format!(
+                    "TEL;TYPE={}:{}",
+                    kind(phone_type.name()),
+                    escape_text(number)
+                ),
+            });
+        }
+        for email in &self.emails {
+            lines.push(format!(
+                "EMAIL;TYPE={}:{}",
+                kind("internet"),
+                escape_text(email)
+            ));
+        }
+        for address in &self.addresses {
+            let parts: Vec<String> = address.parts().map(escape_text).to_vec();
+            lines.push(format!("ADR:;;{}", parts.join(";")));
+        }
+        for url in &self.urls {
+            lines.push(format!("URL:{}", url));
+        }
+        lines.push("END:VCARD".to_string());
+
+        Ok(lines.iter().map(|line| fold(line)).collect())
+    }
+
+    fn mecard(&self) -> String {
+        let mut fields = vec![format!(
+            "N:{},{}",
+            escape_mecard(&self.family_name),
+            escape_mecard(&self.given_name)
+        )];
+        for (number, _) in &self.phones {
+            fields.push(format!("TEL:{}", escape_mecard(number)));
+        }
+        for email in &self.emails {
+            fields.push(format!("EMAIL:{}", escape_mecard(email)));
+        }
+        for address in &self.addresses {
+            // PO box and extended address first, as in vCard
+            let parts: Vec<String> = address.parts().map(escape_mecard).to_vec();
+            fields.push(format!("ADR:,,{}", parts.join(",")));
+        }
+        for url in &self.urls {
+            fields.push(format!("URL:{}", escape_mecard(url)));
+        }
+        if let Some(organization) = &self.organization {
+            fields.push(format!("ORG:{}", escape_mecard(organization)));
+        }
+        if let Some(title) = &self.title {
+            fields.push(format!("NOTE:{}", escape_mecard(title)));
+        }
+
+        format!("MECARD:{};;", fields.join(";"))
+    }
+}
+
+/// RFC 3966 tel URI of a phone number: an optional leading `+` and the digits, the spaces,
+/// dots, slashes, dashes and parentheses between them becoming single dashes.
+fn tel_uri(number: &str) -> Result<String, QrError> {
+    let invalid = || QrError::InvalidPayload(format!("{:?} is not a valid phone number", number));
+    let mut uri = "tel:".to_string();
+    let mut separated = false;
+    for (i, c) in number.trim().chars().enumerate() {
+        match c {
+            '+' if i == 0 => uri.push(c),
+            '0'..='9' => {
+                if separated && !uri.ends_with([':', '+']) {
+                    uri.push('-');
+                }
+                separated = false;
+                uri.push(c);
+            }
+            ' ' | '.' | '/' | '-' | '(' | ')' => separated = true,
+            _ => return Err(invalid()),
+        }
+    }
+    match uri.ends_with(|c: char| c.is_ascii_digit()) {
+        true => Ok(uri),
+        false => Err(invalid()),
+    }
+}
+
+/// Text value escaped following RFC 6350, 3.4, the same as in iCalendar.
+pub(crate) fn escape_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' | ',' | ';' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn escape_mecard(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | ';' | ',' | ':' | '"') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Content line ended with CRLF and folded following RFC 6350, 3.2: lines of at most 75 bytes,
+/// the next ones starting with a space, without splitting a character.
+pub(crate) fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 2);
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::decode;
+
+    fn contact() -> Contact {
+        Contact::new("Jane", "Doe")
+            .phone("+41 44 668 18 00", PhoneType::Work)
+            .phone("+41 79 000 00 00", PhoneType::Cell)
+            .email("jane@example.com")
+            .organization("Doe, Sons; Co")
+            .title("CTO")
+            .address(Address {
+                street: "Bahnhofstrasse 1".to_string(),
+                city: "Zürich".to_string(),
+                postal_code: "8001".to_string(),
+                country: "Switzerland".to_string(),
+                ..Address::default()
+            })
+            .url("https://example.com")
+    }
+
+    #[test]
+    fn data_writes_vcard_3() {
+        assert_eq!(
+            contact().data(ContactFormat::VCard3).unwrap(),
+            "BEGIN:VCARD\r\n\
+             VERSION:3.0\r\n\
+             N:Doe;Jane;;;\r\n\
+             FN:Jane Doe\r\n\
+             ORG:Doe\\, Sons\\; Co\r\n\
+             TITLE:CTO\r\n\
+             TEL;TYPE=WORK:+41 44 668 18 00\r\n\
+             TEL;TYPE=CELL:+41 79 000 00 00\r\n\
+             EMAIL;TYPE=INTERNET:jane@example.com\r\n\
+             ADR:;;Bahnhofstrasse 1;Zürich;;8001;Switzerland\r\n\
+             URL:https://example.com\r\n\
+             END:VCARD\r\n"
+        );
+    }
+
+    #[test]
+    fn data_writes_vcard_4() {
+        let data = contact().data(ContactFormat::VCard4).unwrap();
+        assert!(data.starts_with("BEGIN:VCARD\r\nVERSION:4.0\r\n"));
+        assert!(data.contains("\r\nTEL;VALUE=uri;TYPE=work:tel:+41-44-668-18-00\r\n"));
+        assert!(data.contains("\r\nEMAIL;TYPE=internet:jane@example.com\r\n"));
+    }
+
+    #[test]
+    fn tel_uri_keeps_the_digits() {
+        assert_eq!(
+            tel_uri("+41 (0)44 / 668.18-00").unwrap(),
+            "tel:+41-0-44-668-18-00"
+        );
+        assert_eq!(tel_uri(" (044) 668 18 00 ").unwrap(), "tel:044-668-18-00");
+        for number in ["", "+", "() -", "044 668 18 00 ext. 12", "+41 44 +1"] {
+            assert!(tel_uri(number).is_err(), "{:?}", number);
+        }
+        assert!(matches!(
+            Contact::new("Jane", "Doe")
+                .phone("call me", PhoneType::Home)
+                .data(ContactFormat::VCard4),
+            Err(QrError::InvalidPayload(_))
+        ));
+    }
+
+    #[test]
+    fn data_writes_mecard() {
+        assert_eq!(
+            contact().data(ContactFormat::MeCard).unwrap(),
+            "MECARD:N:Doe,Jane;\
+             TEL:+41 44 668 18 00;TEL:+41 79 000 00 00;\
+             EMAIL:jane@example.com;\
+             ADR:,,Bahnhofstrasse 1,Zürich,,8001,Switzerland;\
+             URL:https\\://example.com;\
+             ORG:Doe\\, Sons\\; Co;\
+             NOTE:CTO;;"
+        );
+        assert!(matches!(
+            Contact::new("", "").data(ContactFormat::MeCard),
+            Err(QrError::InvalidPayload(_))
+        ));
+    }
+
+    #[test]
+    fn fold_keeps_characters_whole() {
+        let line = format!("NOTE:{}", "é".repeat(40));
+        let folded = fold(&line);
+        let lines: Vec<&str> = folded.trim_end().split("\r\n").collect();
+        // 5 + 35 * 2 bytes, then a space and the 5 characters left
+        assert_eq!(lines[0].len(), 75);
+        assert_eq!(lines[1], format!(" {}", "é".repeat(5)));
+        assert_eq!(escape_text("a\\b\nc"), "a\\\\b\\nc");
+    }
+
+    #[test]
+    fn build_within_falls_back_to_a_shorter_format() {
+        let contact = contact();
+        let (format, qrcode) = contact
+            .build_within(ContactFormat::VCard3, EcLevel::M, 40)
+            .unwrap();
+        assert_eq!(format, ContactFormat::VCard3);
+        let text = decode(&qrcode).unwrap().text().unwrap();
+        assert_eq!(text, contact.data(format).unwrap());
+
+        // The vCard takes version 17 at ec level H, the MeCard version 14
+        let (format, qrcode) = contact
+            .build_within(ContactFormat::VCard3, EcLevel::H, 14)
+            .unwrap();
+        assert_eq!(format, ContactFormat::MeCard);
+        assert_eq!(qrcode.version(), crate::version::Version::Normal(14));
+
+        assert!(matches!(
+            contact.build_within(ContactFormat::VCard3, EcLevel::H, 13),
+            Err(QrError::DataTooLong { .. })
+        ));
+    }
+}
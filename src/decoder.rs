@@ -0,0 +1,1042 @@
+use crate::bit::Bit;
+use crate::ec::{block_lengths, correct_block, deinterleave, EcLevel};
+use crate::eci::Charset;
+use crate::encoding::Encoding;
+use crate::error::QrError;
+use crate::fnc1::{payload_bytes, unescape_alphanumeric, Fnc1};
+use crate::mask::MaskPattern;
+use crate::qrcode::QrCode;
+use crate::segment::Segment;
+use crate::structured_append::{parity, StructuredAppend};
+use crate::tables::{
+    FORMAT_BITS, MICRO_FORMAT_BITS, RMQR_FORMAT_BITS, RMQR_FORMAT_MASKS, RMQR_SIZES, VERSION_BITS,
+};
+use crate::version::Version;
+
+const ALPHANUMERIC_CHARS: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+/// Content and parameters read back from a symbol.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Decoded {
+    pub version: Version,
+    pub ec_level: EcLevel,
+    pub mask_pattern: MaskPattern,
+    pub segments: Vec<Segment>,
+    /// Position of the symbol in a Structured Append sequence, if it is part of one.
+    pub structured_append: Option<StructuredAppend>,
+    /// FNC1 mode of GS1 and industry specific data, whose alphanumeric segments write the
+    /// separators as `%`.
+    pub fnc1: Option<Fnc1>,
+    /// Number of codewords fixed by the error correction.
+    pub errors_corrected: usize,
+}
+
+impl Decoded {
+    /// Payload bytes of every segment, kanji being Shift JIS double bytes. With FNC1, the
+    /// separators of alphanumeric segments are back to [`crate::fnc1::GS`].
+    pub fn bytes(&self) -> Vec<u8> {
+        self.segments
+            .iter()
+            .flat_map(|segment| payload_bytes(segment, self.fnc1.is_some()))
+            .collect()
+    }
+
+    /// Text of every segment, or `None` if byte mode data is not valid in its charset. With
+    /// FNC1, the separators of alphanumeric segments are back to [`crate::fnc1::GS`].
+    pub fn text(&self) -> Option<String> {
+        self.segments
+            .iter()
+            .map(|segment| match self.fnc1_alphanumeric(segment) {
+                true => String::from_utf8(unescape_alphanumeric(&segment.data)).ok(),
+                false => segment.text(),
+            })
+            .collect()
+    }
+
+    fn fnc1_alphanumeric(&self, segment: &Segment) -> bool {
+        self.fnc1.is_some() && segment.encoding == Encoding::Alphanumeric
+    }
+}
+
+pub fn decode(qrcode: &QrCode) -> Result<Decoded, QrError> {
+    let modules: Vec<bool> = qrcode.data.iter().map(Bit::value).collect();
+    match qrcode.version() {
+        Version::Rmqr(..) => {
+            decode_rmqr_modules(&modules, qrcode.width() as usize, qrcode.height() as usize)
+        }
+        _ => decode_modules(&modules, qrcode.width() as usize),
+    }
+}
+
+/// Decodes a square module grid stored row by row, `true` being a dark module. Sizes 11 to 17
+/// are Micro QR codes.
+pub fn decode_modules(modules: &[bool], size: usize) -> Result<Decoded, QrError> {
+    if modules.len() != size * size {
+        return Err(QrError::InvalidSymbol("Invalid size.".to_string()));
+    }
+
+    let get = |x: usize, y: usize| modules[x + size * y];
+
+    let (version, ec_level, mask_pattern) = match size {
+        11 | 13 | 15 | 17 => {
+            let number = ((size - 9) / 2) as u8;
+            let (symbol_number, ec_level, mask_pattern) = read_micro_format_information(&get)?;
+            if symbol_number != number {
+                return Err(QrError::InvalidSymbol(
+                    "Format information does not match the size.".to_string(),
+                ));
+            }
+            (Version::Micro(number), ec_level, mask_pattern)
+        }
+        21..=177 if (size - 17).is_multiple_of(4) => {
+            let number = ((size - 17) / 4) as u8;
+            let (ec_level, mask_pattern) = read_format_information(&get, size)?;
+            if number >= 7 && read_version_information(&get, size)? != number {
+                return Err(QrError::InvalidSymbol(
+                    "Version information does not match the size.".to_string(),
+                ));
+            }
+            (Version::Normal(number), ec_level, mask_pattern)
+        }
+        _ => return Err(QrError::InvalidSymbol("Invalid size.".to_string())),
+    };
+
+    read_data(&get, version, ec_level, mask_pattern)
+}
+
+/// Decodes an rMQR module grid of `width` by `height` modules stored row by row, `true` being
+/// a dark module.
+pub fn decode_rmqr_modules(
+    modules: &[bool],
+    width: usize,
+    height: usize,
+) -> Result<Decoded, QrError> {
+    let indicator = RMQR_SIZES
+        .iter()
+        .position(|size| (size.0 as usize, size.1 as usize) == (height, width));
+    let Some(indicator) = indicator.filter(|_| modules.len() == width * height) else {
+        return Err(QrError::InvalidSymbol("Invalid size.".to_string()));
+    };
+
+    let get = |x: usize, y: usize| modules[x + width * y];
+
+    let (read_indicator, ec_level) = read_rmqr_format_information(&get, width, height)?;
+    if read_indicator != indicator {
+        return Err(QrError::InvalidSymbol(
+            "Format information does not match the size.".to_string(),
+        ));
+    }
+
+    let version = Version::Rmqr(height as u8, width as u8);
+    read_data(&get, version, ec_level, MaskPattern::RMQR)
+}
+
+/// Unmasks the data modules, corrects the codewords and reads the segments.
+fn read_data(
+    get: &impl Fn(usize, usize) -> bool,
+    version: Version,
+    ec_level: EcLevel,
+    mask_pattern: MaskPattern,
+) -> Result<Decoded, QrError> {
+    // An empty symbol with the same parameters tells which modules hold data
+    let mut template = QrCode::new(version, ec_level, mask_pattern)?;
+    template.all_functional_patterns();
+
+    let mask_fn = mask_pattern.get_mask();
+    let bits: Vec<Bit> = template
+        .data_positions()
+        .iter()
+        .map(|&(x, y)| {
+            if get(x as usize, y as usize) ^ mask_fn(x, y) {
+                Bit::One(false)
+            } else {
+                Bit::Zero(false)
+            }
+        })
+        .collect();
+
+    let lengths = block_lengths(version, &ec_level);
+    let data_len: usize = lengths.iter().sum();
+    // The remainder bits do not form a full codeword, nor does the 4 bit last data codeword of
+    // M1 and M3
+    let (Some(data_bits), Some(ec_len)) = (
+        version.data_capacity(ec_level),
+        version.ec_codewords(ec_level),
+    ) else {
+        return Err(QrError::InvalidSymbol(format!(
+            "ec level {:?} is not available in version {}",
+            ec_level, version
+        )));
+    };
+    let ec_bits = &bits[data_bits..data_bits + ec_len * lengths.len() * 8];
+    let codewords = [Bit::bytes(&bits[..data_bits]), Bit::bytes(ec_bits)].concat();
+    let data_blocks = deinterleave(&codewords[..data_len], &lengths);
+    let ec_blocks = deinterleave(&codewords[data_len..], &vec![ec_len; lengths.len()]);
+
+    let mut data = Vec::with_capacity(data_len);
+    let mut errors_corrected = 0;
+
+    for (mut block, mut ec_block) in data_blocks.into_iter().zip(ec_blocks) {
+        let block_len = block.len();
+        block.append(&mut ec_block);
+
+        let (corrected, errors) = correct_block(&block, ec_len, &[])?;
+        data.extend_from_slice(&corrected[..block_len]);
+        errors_corrected += errors;
+    }
+
+    let content = read_segments(&data, version)?;
+    Ok(Decoded {
+        version,
+        ec_level,
+        mask_pattern,
+        segments: content.segments,
+        structured_append: content.structured_append,
+        fnc1: content.fnc1,
+        errors_corrected,
+    })
+}
+
+/// Joins the segments of a Structured Append sequence, given in any order, after checking that
+/// every symbol is there once and that the parity matches. A single symbol without header is
+/// returned as is.
+pub fn reassemble(symbols: &[Decoded]) -> Result<Vec<Segment>, QrError> {
+    let headers: Vec<_> = symbols
+        .iter()
+        .map(|symbol| symbol.structured_append)
+        .collect();
+    let first = match headers.as_slice() {
+        [None] => return Ok(symbols[0].segments.clone()),
+        [Some(first), ..] => *first,
+        _ => {
+            return Err(QrError::InvalidSymbol(
+                "Missing Structured Append header.".to_string(),
+            ))
+        }
+    };
+
+    let mut ordered = vec![None; first.total as usize];
+    for (symbol, header) in symbols.iter().zip(&headers) {
+        let Some(header) =
+            header.filter(|header| (header.total, header.parity) == (first.total, first.parity))
+        else {
+            return Err(QrError::InvalidSymbol(
+                "Symbols from different Structured Append sequences.".to_string(),
+            ));
+        };
+        match ordered.get_mut(header.index as usize) {
+            Some(slot @ None) => *slot = Some(symbol),
+            _ => {
+                return Err(QrError::InvalidSymbol(format!(
+                    "Duplicate or invalid Structured Append index: {}",
+                    header.index
+                )))
+            }
+        }
+    }
+
+    let segments: Vec<Segment> = ordered
+        .into_iter()
+        .map(|symbol| symbol.map(|symbol| symbol.segments.clone()))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| {
+            QrError::InvalidSymbol(format!("Missing symbols, expected {}.", first.total))
+        })?
+        .concat();
+
+    if parity(&segments, symbols[0].fnc1.is_some()) != first.parity {
+        return Err(QrError::InvalidSymbol(
+            "Structured Append parity does not match.".to_string(),
+        ));
+    }
+    Ok(segments)
+}
+
+/// Index of the closest codeword if at most 3 bits differ, the BCH codes correcting up to 3 errors.
+fn closest_codeword(codewords: &[u32], read: &[u32]) -> Option<usize> {
+    codewords
+        .iter()
+        .enumerate()
+        .flat_map(|(i, codeword)| {
+            read.iter()
+                .map(move |value| (i, (codeword ^ value).count_ones()))
+        })
+        .min_by_key(|(_, distance)| *distance)
+        .filter(|(_, distance)| *distance <= 3)
+        .map(|(i, _)| i)
+}
+
+fn to_u32(bits: impl Iterator<Item = bool>) -> u32 {
+    bits.fold(0, |acc, bit| (acc << 1) | bit as u32)
+}
+
+fn read_format_information(
+    get: &impl Fn(usize, usize) -> bool,
+    size: usize,
+) -> Result<(EcLevel, MaskPattern), QrError> {
+    // Most significant bit first, skipping the timing patterns
+    let top_left = (0..9)
+        .filter(|x| *x != 6)
+        .map(|x| get(x, 8))
+        .chain((0..8).rev().filter(|y| *y != 6).map(|y| get(8, y)));
+    let split = (0..7)
+        .map(|i| get(8, size - 1 - i))
+        .chain((size - 8..size).map(|x| get(x, 8)));
+
+    let read = [to_u32(top_left), to_u32(split)];
+
+    let index = closest_codeword(&FORMAT_BITS, &read)
+        .ok_or_else(|| QrError::InvalidSymbol("Unreadable format information.".to_string()))?;
+
+    Ok((EcLevel::ALL[index / 8], MaskPattern::ALL[index % 8]))
+}
+
+/// Reads the Micro QR format information: the version number, ec level and mask.
+fn read_micro_format_information(
+    get: &impl Fn(usize, usize) -> bool,
+) -> Result<(u8, EcLevel, MaskPattern), QrError> {
+    // Most significant bit first
+    let bits = (1..9)
+        .map(|x| get(x, 8))
+        .chain((1..8).rev().map(|y| get(8, y)));
+
+    let index = closest_codeword(&MICRO_FORMAT_BITS, &[to_u32(bits)])
+        .ok_or_else(|| QrError::InvalidSymbol("Unreadable format information.".to_string()))?;
+
+    // Indexed by symbol number
+    const SYMBOLS: [(u8, EcLevel); 8] = [
+        (1, EcLevel::L),
+        (2, EcLevel::L),
+        (2, EcLevel::M),
+        (3, EcLevel::L),
+        (3, EcLevel::M),
+        (4, EcLevel::L),
+        (4, EcLevel::M),
+        (4, EcLevel::Q),
+    ];
+
+    let (number, ec_level) = SYMBOLS[index / 4];
+    Ok((number, ec_level, MaskPattern::MICRO[index % 4]))
+}
+
+/// Reads the rMQR format information next to the finder pattern, or next to the sub-finder
+/// pattern: the version indicator and ec level.
+fn read_rmqr_format_information(
+    get: &impl Fn(usize, usize) -> bool,
+    width: usize,
+    height: usize,
+) -> Result<(usize, EcLevel), QrError> {
+    // Least significant bit first, in columns of 5 modules then a row or column of 3
+    let finder_side = (0..18).rev().map(|i| match i {
+        0..15 => get(8 + i / 5, 1 + i % 5),
+        _ => get(11, i - 14),
+    });
+    let sub_finder_side = (0..18).rev().map(|i| match i {
+        0..15 => get(width - 8 + i / 5, height - 6 + i % 5),
+        _ => get(width - 20 + i, height - 6),
+    });
+
+    let read = [
+        to_u32(finder_side) ^ RMQR_FORMAT_MASKS[0],
+        to_u32(sub_finder_side) ^ RMQR_FORMAT_MASKS[1],
+    ];
+
+    let index = closest_codeword(&RMQR_FORMAT_BITS, &read)
+        .ok_or_else(|| QrError::InvalidSymbol("Unreadable format information.".to_string()))?;
+
+    let ec_level = if index < 32 { EcLevel::M } else { EcLevel::H };
+    Ok((index % 32, ec_level))
+}
+
+fn read_version_information(
+    get: &impl Fn(usize, usize) -> bool,
+    size: usize,
+) -> Result<u8, QrError> {
+    // Least significant bit first
+    let bottom_left = (0..18).rev().map(|i| get(i / 3, size - 11 + i % 3));
+    let top_right = (0..18).rev().map(|i| get(size - 11 + i % 3, i / 3));
+
+    let read = [to_u32(bottom_left), to_u32(top_right)];
+
+    closest_codeword(&VERSION_BITS, &read)
+        .map(|index| index as u8 + 7)
+        .ok_or_else(|| QrError::InvalidSymbol("Unreadable version information.".to_string()))
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl BitReader<'_> {
+    fn remaining(&self) -> usize {
+        self.data.len() * 8 - self.position
+    }
+
+    fn read(&mut self, n_bits: u8) -> Result<u32, QrError> {
+        if self.remaining() < n_bits as usize {
+            return Err(QrError::InvalidSymbol(
+                "Unexpected end of data.".to_string(),
+            ));
+        }
+
+        let mut value = 0;
+        for _ in 0..n_bits {
+            let bit = (self.data[self.position / 8] >> (7 - self.position % 8)) & 1;
+            value = (value << 1) | bit as u32;
+            self.position += 1;
+        }
+        Ok(value)
+    }
+
+    fn peek(&self, n_bits: usize) -> Result<u32, QrError> {
+        BitReader {
+            data: self.data,
+            position: self.position,
+        }
+        .read(n_bits as u8)
+    }
+}
+
+/// Segments of a symbol, with the Structured Append and FNC1 headers preceding them.
+struct Content {
+    segments: Vec<Segment>,
+    structured_append: Option<StructuredAppend>,
+    fnc1: Option<Fnc1>,
+}
+
+fn read_segments(data: &[u8], version: Version) -> Result<Content, QrError> {
+    let mut reader = BitReader { data, position: 0 };
+    let mut segments = vec![];
+    let mut structured_append = None;
+    let mut fnc1 = None;
+    let mut charset = Charset::Iso8859_1;
+
+    let (mode_bits, terminator_bits) = (version.mode_bits()?, version.terminator_bits()?);
+
+    // The terminator can be shortened or left out when the symbol is full
+    while reader.remaining() >= terminator_bits {
+        if reader.peek(terminator_bits)? == 0 {
+            break;
+        }
+
+        if version.is_micro() {
+            let mode = reader.read(mode_bits as u8)?;
+            let encoding = Encoding::ALL.get(mode as usize).copied().ok_or_else(|| {
+                QrError::InvalidSymbol(format!("Unsupported mode indicator: {:b}", mode))
+            })?;
+            segments.push(read_segment(&mut reader, encoding, version, charset)?);
+            continue;
+        }
+
+        // rMQR mode indicators are 3 bits wide, counting from 1 in the Micro QR order
+        let encoding = match (version.is_rmqr(), reader.read(mode_bits as u8)?) {
+            (false, 0b0111) | (true, 0b111) => {
+                let eci = read_eci_designator(&mut reader)?;
+                charset = Charset::from_eci(eci)
+                    .ok_or_else(|| QrError::InvalidSymbol(format!("Unsupported ECI: {}", eci)))?;
+                continue;
+            }
+            (false, 0b0011) => {
+                let sequence = reader.read(8)?;
+                structured_append = Some(StructuredAppend {
+                    index: (sequence >> 4) as u8,
+                    total: (sequence & 0xF) as u8 + 1,
+                    parity: reader.read(8)? as u8,
+                });
+                continue;
+            }
+            (false, 0b0101) | (true, 0b101) => {
+                fnc1 = Some(Fnc1::First);
+                continue;
+            }
+            (false, 0b1001) | (true, 0b110) => {
+                fnc1 = Some(Fnc1::Second(reader.read(8)? as u8));
+                continue;
+            }
+            (false, 0b0001) => Encoding::Numeric,
+            (false, 0b0010) => Encoding::Alphanumeric,
+            (false, 0b0100) => Encoding::Byte,
+            (false, 0b1000) => Encoding::Kanji,
+            (true, mode @ 1..=4) => Encoding::ALL[mode as usize - 1],
+            (_, mode) => {
+                return Err(QrError::InvalidSymbol(format!(
+                    "Unsupported mode indicator: {:0width$b}",
+                    mode,
+                    width = mode_bits
+                )))
+            }
+        };
+
+        segments.push(read_segment(&mut reader, encoding, version, charset)?);
+    }
+
+    Ok(Content {
+        segments,
+        structured_append,
+        fnc1,
+    })
+}
+
+/// Reads the character count indicator and data of a segment, after its mode indicator.
+fn read_segment(
+    reader: &mut BitReader,
+    encoding: Encoding,
+    version: Version,
+    charset: Charset,
+) -> Result<Segment, QrError> {
+    let count_bits = encoding
+        .char_count_bits(version)
+        .map_err(|error| match error {
+            QrError::Unsupported(reason) => QrError::InvalidSymbol(reason),
+            error => error,
+        })?;
+    let count = reader.read(count_bits)? as usize;
+
+    let data = match encoding {
+        Encoding::Numeric => read_numeric(reader, count)?.into_bytes(),
+        Encoding::Alphanumeric => read_alphanumeric(reader, count)?.into_bytes(),
+        Encoding::Byte => read_byte(reader, count)?,
+        Encoding::Kanji => read_kanji(reader, count)?,
+    };
+
+    Ok(Segment {
+        encoding,
+        data,
+        charset: match encoding {
+            Encoding::Byte => charset,
+            _ => Charset::Iso8859_1,
+        },
+    })
+}
+
+fn read_numeric(reader: &mut BitReader, count: usize) -> Result<String, QrError> {
+    let mut data = String::with_capacity(count);
+    let mut remaining = count;
+
+    while remaining > 0 {
+        let digits = remaining.min(3);
+        let value = reader.read(digits as u8 * 3 + 1)?;
+        if value >= 10u32.pow(digits as u32) {
+            return Err(QrError::InvalidSymbol(format!(
+                "Invalid numeric value: {}",
+                value
+            )));
+        }
+        data.push_str(&format!("{:0width$}", value, width = digits));
+        remaining -= digits;
+    }
+
+    Ok(data)
+}
+
+fn read_alphanumeric(reader: &mut BitReader, count: usize) -> Result<String, QrError> {
+    let char_at = |value: u32| {
+        ALPHANUMERIC_CHARS
+            .get(value as usize)
+            .map(|c| *c as char)
+            .ok_or_else(|| QrError::InvalidSymbol(format!("Invalid alphanumeric value: {}", value)))
+    };
+
+    let mut data = String::with_capacity(count);
+    for _ in 0..count / 2 {
+        let value = reader.read(11)?;
+        data.push(char_at(value / 45)?);
+        data.push(char_at(value % 45)?);
+    }
+    if count % 2 == 1 {
+        data.push(char_at(reader.read(6)?)?);
+    }
+
+    Ok(data)
+}
+
+/// Reads the 1, 2 or 3 byte designator following an ECI mode indicator.
+fn read_eci_designator(reader: &mut BitReader) -> Result<u32, QrError> {
+    let first = reader.read(8)?;
+    match (first as u8).leading_ones() {
+        0 => Ok(first),
+        1 => Ok((first & 0x3F) << 8 | reader.read(8)?),
+        2 => Ok((first & 0x1F) << 16 | reader.read(16)?),
+        _ => Err(QrError::InvalidSymbol(
+            "Invalid ECI designator.".to_string(),
+        )),
+    }
+}
+
+fn read_byte(reader: &mut BitReader, count: usize) -> Result<Vec<u8>, QrError> {
+    (0..count)
+        .map(|_| reader.read(8).map(|byte| byte as u8))
+        .collect()
+}
+
+/// Reads `count` kanji back into Shift JIS double bytes.
+fn read_kanji(reader: &mut BitReader, count: usize) -> Result<Vec<u8>, QrError> {
+    let mut bytes = Vec::with_capacity(count * 2);
+    for _ in 0..count {
+        let value = reader.read(13)?;
+        let mut code = ((value / 0xC0) << 8) | (value % 0xC0);
+        code += if code < 0x1F00 { 0x8140 } else { 0xC140 };
+        bytes.extend_from_slice(&(code as u16).to_be_bytes());
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mask::MaskSelection;
+    use crate::preprocessor::Preprocessor;
+
+    fn generate(data: &str, encoding: Encoding, ec_level: EcLevel) -> QrCode {
+        Preprocessor::new(data, encoding, ec_level, MaskSelection::Auto)
+            .unwrap()
+            .generate_qrcode()
+            .unwrap()
+    }
+
+    #[test]
+    fn decode_reads_every_mode() {
+        let inputs = [
+            ("0123456789012", Encoding::Numeric),
+            ("HELLO WORLD $%*+-./:", Encoding::Alphanumeric),
+            ("Hello, wörld!", Encoding::Byte),
+            ("点茗漢字", Encoding::Kanji),
+        ];
+
+        for (data, encoding) in inputs {
+            let decoded = decode(&generate(data, encoding, EcLevel::M)).unwrap();
+            assert_eq!(
+                decoded.segments,
+                vec![Segment::new(encoding, data).unwrap()]
+            );
+        }
+    }
+
+    #[test]
+    fn decode_applies_eci_charset() {
+        let inputs = [
+            ("Привет, мир 😀", Charset::Utf8),
+            ("Жук", Charset::Windows1251),
+            ("Ωμέγα", Charset::Iso8859_7),
+        ];
+
+        for (data, charset) in inputs {
+            let qrcode = Preprocessor::encode(
+                data,
+                Some(Encoding::Byte),
+                Some(charset),
+                EcLevel::M,
+                false,
+                MaskSelection::Auto,
+                Version::Normal(1)..=Version::Normal(40),
+            )
+            .unwrap()
+            .generate_qrcode()
+            .unwrap();
+
+            let decoded = decode(&qrcode).unwrap();
+            assert_eq!(
+                decoded.segments,
+                vec![Segment::with_charset(Encoding::Byte, data, charset).unwrap()]
+            );
+        }
+    }
+
+    #[test]
+    fn decode_returns_bytes_that_are_not_text() {
+        // Not valid UTF-8 once announced as such
+        let segments = [Segment {
+            charset: Charset::Utf8,
+            ..Segment::binary(&[0xC3, 0x28, 0xFF])
+        }];
+        let qrcode = Preprocessor::from_segments(
+            &segments,
+            Version::Normal(1),
+            EcLevel::M,
+            MaskSelection::Auto,
+        )
+        .unwrap()
+        .generate_qrcode()
+        .unwrap();
+
+        let decoded = decode(&qrcode).unwrap();
+        assert_eq!(decoded.bytes(), vec![0xC3, 0x28, 0xFF]);
+        assert_eq!(decoded.text(), None);
+    }
+
+    #[test]
+    fn decode_reports_symbol_parameters() {
+        let qrcode = Preprocessor::new(
+            "HELLO WORLD",
+            Encoding::Alphanumeric,
+            EcLevel::Q,
+            MaskSelection::Fixed(MaskPattern::Fields),
+        )
+        .unwrap()
+        .generate_qrcode()
+        .unwrap();
+
+        let decoded = decode(&qrcode).unwrap();
+        assert_eq!(decoded.version, Version::Normal(1));
+        assert_eq!(decoded.ec_level, EcLevel::Q);
+        assert_eq!(decoded.mask_pattern, MaskPattern::Fields);
+    }
+
+    #[test]
+    fn decode_reads_version_information() {
+        let data = "https://example.com ".repeat(20);
+        for ec_level in EcLevel::ALL {
+            let qrcode = generate(&data, Encoding::Byte, ec_level);
+            assert!(qrcode.version() >= Version::Normal(7));
+
+            let decoded = decode(&qrcode).unwrap();
+            assert_eq!(decoded.version, qrcode.version());
+            assert_eq!(decoded.text().unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn decode_reads_mixed_segments() {
+        let data = "ORDER 000123456789 - café";
+        let qrcode = Preprocessor::segmented(data, EcLevel::L, MaskSelection::Auto)
+            .unwrap()
+            .generate_qrcode()
+            .unwrap();
+
+        let decoded = decode(&qrcode).unwrap();
+        assert_eq!(decoded.segments.len(), 3);
+        assert_eq!(decoded.text().unwrap(), data);
+    }
+
+    #[test]
+    fn decode_reads_micro_qr_codes() {
+        let inputs = [
+            ("12345", Encoding::Numeric),
+            ("A1B2", Encoding::Alphanumeric),
+            ("qr", Encoding::Byte),
+            ("点", Encoding::Kanji),
+        ];
+
+        for version in (1..=4).map(Version::Micro) {
+            for ec_level in EcLevel::ALL {
+                for mask in MaskPattern::MICRO {
+                    for (data, encoding) in inputs {
+                        let segments = [Segment::new(encoding, data).unwrap()];
+                        let mask_selection = MaskSelection::Fixed(mask);
+                        let Ok(preprocessor) = Preprocessor::from_segments(
+                            &segments,
+                            version,
+                            ec_level,
+                            mask_selection,
+                        ) else {
+                            // The mode or the ec level is not available in this version
+                            continue;
+                        };
+
+                        let qrcode = preprocessor.generate_qrcode().unwrap();
+                        let decoded = decode(&qrcode).unwrap();
+                        assert_eq!(decoded.version, version);
+                        assert_eq!(decoded.ec_level, ec_level);
+                        assert_eq!(decoded.mask_pattern, mask);
+                        assert_eq!(decoded.segments, segments);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn decode_modules_corrects_micro_qr_codes() {
+        let segments = [Segment::new(Encoding::Alphanumeric, "MICRO QR").unwrap()];
+        let qrcode = Preprocessor::from_segments(
+            &segments,
+            Version::Micro(4),
+            EcLevel::Q,
+            MaskSelection::Auto,
+        )
+        .unwrap()
+        .generate_qrcode()
+        .unwrap();
+        let size = qrcode.width() as usize;
+        let mut modules: Vec<bool> = qrcode.data.iter().map(Bit::value).collect();
+
+        // A few modules of the first data codewords, in the bottom right corner
+        for (x, y) in [(16, 16), (15, 15), (16, 12)] {
+            modules[x + size * y] = !modules[x + size * y];
+        }
+
+        let decoded = decode_modules(&modules, size).unwrap();
+        assert_eq!(decoded.segments, segments);
+        assert!(decoded.errors_corrected > 0);
+    }
+
+    #[test]
+    fn decode_modules_corrects_format_information() {
+        let qrcode = generate("HELLO", Encoding::Alphanumeric, EcLevel::H);
+        let size = qrcode.width() as usize;
+        let mut modules: Vec<bool> = qrcode.data.iter().map(Bit::value).collect();
+
+        for x in [0, 2, 4] {
+            modules[x + size * 8] = !modules[x + size * 8];
+        }
+
+        let decoded = decode_modules(&modules, size).unwrap();
+        assert_eq!(decoded.ec_level, EcLevel::H);
+        assert_eq!(decoded.text().unwrap(), "HELLO");
+    }
+
+    #[test]
+    fn decode_modules_corrects_damaged_data() {
+        let data = "https://example.com/some/longer/path";
+        let qrcode = generate(data, Encoding::Byte, EcLevel::H);
+        let size = qrcode.width() as usize;
+        let mut modules: Vec<bool> = qrcode.data.iter().map(Bit::value).collect();
+
+        // Flip a square in the data area, away from the function patterns
+        for y in 12..16 {
+            for x in 12..16 {
+                modules[x + size * y] = !modules[x + size * y];
+            }
+        }
+
+        let decoded = decode_modules(&modules, size).unwrap();
+        assert_eq!(decoded.text().unwrap(), data);
+        assert!(decoded.errors_corrected > 0);
+    }
+
+    #[test]
+    fn decode_modules_rejects_unreadable_data() {
+        let qrcode = generate("HELLO WORLD", Encoding::Alphanumeric, EcLevel::L);
+        let size = qrcode.width() as usize;
+        let mut modules: Vec<bool> = qrcode.data.iter().map(Bit::value).collect();
+
+        for y in 9..size {
+            for x in 9..size {
+                modules[x + size * y] = !modules[x + size * y];
+            }
+        }
+
+        assert!(decode_modules(&modules, size).is_err());
+    }
+
+    #[test]
+    fn decode_modules_rejects_invalid_size() {
+        assert!(decode_modules(&[false; 400], 20).is_err());
+        assert!(decode_modules(&[false; 100], 21).is_err());
+        assert!(decode_modules(&[false; 144], 12).is_err());
+        assert!(decode_rmqr_modules(&[false; 7 * 27], 27, 7).is_err());
+        assert!(decode_rmqr_modules(&[false; 100], 43, 7).is_err());
+    }
+
+    #[test]
+    fn decode_reads_rmqr_codes() {
+        let numeric = Segment::new(Encoding::Numeric, "12345").unwrap();
+        let utf8 = Segment::with_charset(Encoding::Byte, "é", Charset::Utf8).unwrap();
+
+        for version in Version::all().filter(Version::is_rmqr) {
+            for ec_level in [EcLevel::M, EcLevel::H] {
+                for segments in [vec![numeric.clone()], vec![numeric.clone(), utf8.clone()]] {
+                    let mask = MaskSelection::Auto;
+                    let preprocessor =
+                        match Preprocessor::from_segments(&segments, version, ec_level, mask) {
+                            Ok(preprocessor) => preprocessor,
+                            // The ECI header does not fit in the smallest versions
+                            Err(QrError::DataTooLong { .. }) => continue,
+                            Err(error) => panic!("{}", error),
+                        };
+
+                    let decoded = decode(&preprocessor.generate_qrcode().unwrap()).unwrap();
+                    assert_eq!(decoded.version, version);
+                    assert_eq!(decoded.ec_level, ec_level);
+                    assert_eq!(decoded.mask_pattern, MaskPattern::RMQR);
+                    assert_eq!(decoded.segments, segments);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn decode_rmqr_modules_corrects_errors() {
+        let segments = [Segment::new(Encoding::Alphanumeric, "CABLE 42").unwrap()];
+        let version = Version::Rmqr(9, 59);
+        let qrcode =
+            Preprocessor::from_segments(&segments, version, EcLevel::H, MaskSelection::Auto)
+                .unwrap()
+                .generate_qrcode()
+                .unwrap();
+        let mut modules: Vec<bool> = qrcode.data.iter().map(Bit::value).collect();
+
+        // The format information next to the finder pattern, read again from the other side
+        for (x, y) in [(8, 1), (9, 2), (10, 3), (11, 1)] {
+            modules[x + 59 * y] = !modules[x + 59 * y];
+        }
+        // First data codewords, left of the sub-finder pattern
+        for (x, y) in [(56, 1), (55, 2), (54, 1)] {
+            modules[x + 59 * y] = !modules[x + 59 * y];
+        }
+
+        let decoded = decode_rmqr_modules(&modules, 59, 9).unwrap();
+        assert_eq!(decoded.segments, segments);
+        assert!(decoded.errors_corrected > 0);
+    }
+
+    fn sequence(data: &str, versions: std::ops::RangeInclusive<Version>) -> Vec<Decoded> {
+        Preprocessor::structured_append(
+            data,
+            None,
+            None,
+            None,
+            EcLevel::M,
+            MaskSelection::Auto,
+            versions,
+        )
+        .unwrap()
+        .iter()
+        .map(|preprocessor| decode(&preprocessor.generate_qrcode().unwrap()).unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn reassemble_joins_structured_append_symbols_in_any_order() {
+        let data = "Structured Append 0123456789 ".repeat(3);
+        let mut symbols = sequence(&data, Version::Normal(1)..=Version::Normal(2));
+        // 87 characters, version 2-M holding about 24 bytes after the headers
+        assert_eq!(symbols.len(), 4);
+
+        let parity = symbols[0].structured_append.unwrap().parity;
+        for (index, symbol) in symbols.iter().enumerate() {
+            assert_eq!(
+                symbol.structured_append,
+                Some(StructuredAppend {
+                    index: index as u8,
+                    total: 4,
+                    parity
+                })
+            );
+        }
+
+        symbols.reverse();
+        let segments = reassemble(&symbols).unwrap();
+        let text: Option<String> = segments.iter().map(Segment::text).collect();
+        assert_eq!(text.unwrap(), data);
+
+        // A lone symbol has no header
+        let single = sequence("HELLO", Version::Normal(1)..=Version::Normal(2));
+        assert_eq!(single[0].structured_append, None);
+        assert_eq!(reassemble(&single).unwrap(), single[0].segments);
+    }
+
+    #[test]
+    fn reassemble_rejects_incomplete_or_mixed_sequences() {
+        let versions = Version::Normal(1)..=Version::Normal(1);
+        // 15 characters and the 20 bit header fill 116 of the 128 bits of version 1-M
+        let mut symbols = sequence(&"A".repeat(45), versions.clone());
+        assert_eq!(symbols.len(), 3);
+
+        let missing = &symbols[..2];
+        assert!(matches!(
+            reassemble(missing),
+            Err(QrError::InvalidSymbol(_))
+        ));
+
+        let duplicate = [&symbols[..2], &symbols[1..2]].concat();
+        assert!(matches!(
+            reassemble(&duplicate),
+            Err(QrError::InvalidSymbol(_))
+        ));
+
+        let other = sequence(&"B".repeat(45), versions);
+        let mixed = [&symbols[..2], &other[2..]].concat();
+        assert!(matches!(reassemble(&mixed), Err(QrError::InvalidSymbol(_))));
+
+        symbols[1].segments[0].data[0] = b'B';
+        assert_eq!(
+            reassemble(&symbols),
+            Err(QrError::InvalidSymbol(
+                "Structured Append parity does not match.".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn reassemble_checks_the_parity_of_fnc1_data() {
+        let data = "10ABC%\u{1D}21XYZ".repeat(9);
+        let symbols: Vec<Decoded> = Preprocessor::structured_append(
+            &data,
+            Some(Encoding::Alphanumeric),
+            None,
+            Some(Fnc1::First),
+            EcLevel::M,
+            MaskSelection::Auto,
+            Version::Normal(1)..=Version::Normal(1),
+        )
+        .unwrap()
+        .iter()
+        .map(|preprocessor| decode(&preprocessor.generate_qrcode().unwrap()).unwrap())
+        .collect();
+        assert!(symbols.len() > 1);
+
+        // The parity is that of the input, not of the escaped segments
+        let parity = data.bytes().fold(0, |parity, byte| parity ^ byte);
+        assert_eq!(symbols[0].structured_append.unwrap().parity, parity);
+        let segments = reassemble(&symbols).unwrap();
+        let bytes: Vec<u8> = segments
+            .iter()
+            .flat_map(|segment| payload_bytes(segment, true))
+            .collect();
+        assert_eq!(bytes, data.as_bytes());
+    }
+
+    #[test]
+    fn decode_reads_fnc1_data() {
+        let data = "01095011010209171719050810ABCD1234\u{1D}2110%";
+        let cases = [
+            (Fnc1::First, None, Version::Normal(1)..=Version::Normal(40)),
+            (
+                Fnc1::Second(37),
+                None,
+                Version::Normal(1)..=Version::Normal(40),
+            ),
+            (
+                Fnc1::First,
+                Some(Encoding::Alphanumeric),
+                Version::Normal(1)..=Version::Normal(40),
+            ),
+            (
+                Fnc1::First,
+                Some(Encoding::Byte),
+                Version::Normal(1)..=Version::Normal(40),
+            ),
+            (
+                Fnc1::First,
+                None,
+                Version::Rmqr(7, 43)..=Version::Rmqr(17, 139),
+            ),
+            (
+                Fnc1::Second(197),
+                None,
+                Version::Rmqr(7, 43)..=Version::Rmqr(17, 139),
+            ),
+        ];
+
+        for (fnc1, encoding, versions) in cases {
+            let mask = MaskSelection::Auto;
+            let qrcode =
+                Preprocessor::fnc1(data, fnc1, encoding, EcLevel::M, false, mask, versions)
+                    .unwrap()
+                    .generate_qrcode()
+                    .unwrap();
+
+            let decoded = decode(&qrcode).unwrap();
+            assert_eq!(decoded.fnc1, Some(fnc1));
+            assert_eq!(decoded.text().unwrap(), data, "{:?} {:?}", fnc1, encoding);
+            assert_eq!(decoded.bytes(), data.as_bytes());
+        }
+
+        // Without FNC1, % is a literal character
+        let qrcode = generate("10ABC%", Encoding::Alphanumeric, EcLevel::M);
+        assert_eq!(decode(&qrcode).unwrap().text().unwrap(), "10ABC%");
+    }
+}
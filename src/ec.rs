@@ -1,6 +1,8 @@
-use crate::tables::{DATA_BYTES_PER_BLOCK, EXP_TABLE, GENERATOR_POLYNOMIALS, LOG_TABLE};
+use crate::error::QrError;
+use crate::tables::{EXP_TABLE, GENERATOR_POLYNOMIALS, LOG_TABLE};
+use crate::version::Version;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum EcLevel {
     H,
     Q,
@@ -9,6 +11,9 @@ pub enum EcLevel {
 }
 
 impl EcLevel {
+    /// Every level, indexed by ordinal.
+    pub const ALL: [EcLevel; 4] = [EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H];
+
     pub fn ordinal(&self) -> u8 {
         match self {
             EcLevel::L => 0,
@@ -20,8 +25,8 @@ impl EcLevel {
 }
 
 pub fn codewords(
-    data: &Vec<u8>,
-    version: u8,
+    data: &[u8],
+    version: Version,
     ec_level: &EcLevel,
     cw_per_block: usize,
 ) -> (Vec<u8>, Vec<u8>) {
@@ -31,7 +36,7 @@ pub fn codewords(
         .iter()
         .map(|block| {
             create_ec_for_block(
-                Vec::from(block.clone()),
+                block.clone(),
                 cw_per_block,
                 GENERATOR_POLYNOMIALS[cw_per_block],
             )
@@ -41,10 +46,13 @@ pub fn codewords(
     (interleave(blocks), interleave(ec_blocks))
 }
 
-pub fn groups(data: &Vec<u8>, version: u8, ec_level: &EcLevel) -> Vec<Vec<u8>> {
-    let ec_level = ec_level.ordinal();
-    let (block_1_size, block_1_count, block_2_size, block_2_count) =
-        DATA_BYTES_PER_BLOCK[(version - 1) as usize][ec_level as usize];
+pub fn groups(data: &[u8], version: Version, ec_level: &EcLevel) -> Vec<Vec<u8>> {
+    // Micro QR codes have a single block
+    let Some((block_1_size, block_1_count, block_2_size, block_2_count)) =
+        version.blocks(*ec_level)
+    else {
+        return vec![data.to_vec()];
+    };
 
     let group_1_size = block_1_count * block_1_size;
 
@@ -106,6 +114,200 @@ pub fn interleave(blocks: Vec<Vec<u8>>) -> Vec<u8> {
     result
 }
 
+fn gf_mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        0
+    } else {
+        EXP_TABLE[(usize::from(LOG_TABLE[a as usize]) + usize::from(LOG_TABLE[b as usize])) % 255]
+    }
+}
+
+fn gf_inverse(a: u8) -> u8 {
+    EXP_TABLE[255 - usize::from(LOG_TABLE[a as usize])]
+}
+
+/// 2<sup>n</sup> in GF(256), for any n.
+fn gf_exp(n: isize) -> u8 {
+    EXP_TABLE[n.rem_euclid(255) as usize]
+}
+
+/// Evaluates a polynomial whose coefficients are stored from the lowest degree.
+fn poly_eval(poly: &[u8], x: u8) -> u8 {
+    poly.iter()
+        .rev()
+        .fold(0, |acc, coeff| gf_mul(acc, x) ^ coeff)
+}
+
+fn poly_mul(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut res = vec![0; a.len() + b.len() - 1];
+    for (i, &coeff_a) in a.iter().enumerate() {
+        for (j, &coeff_b) in b.iter().enumerate() {
+            res[i + j] ^= gf_mul(coeff_a, coeff_b);
+        }
+    }
+    res
+}
+
+/// Corrects a block made of data codewords followed by `ec_size` ec codewords.
+///
+/// `erasures` are the indices of codewords known to be unreadable. Up to `e` errors and `f`
+/// erasures can be corrected as long as `2e + f <= ec_size`. Returns the corrected block and
+/// the number of codewords that were changed.
+pub fn correct_block(
+    block: &[u8],
+    ec_size: usize,
+    erasures: &[usize],
+) -> Result<(Vec<u8>, usize), QrError> {
+    let n = block.len();
+    if ec_size >= n || erasures.len() > ec_size || erasures.iter().any(|i| *i >= n) {
+        return Err(QrError::Uncorrectable);
+    }
+
+    // The codeword at index i is the coefficient of x^(n - 1 - i), evaluated at the roots
+    // 2^0 .. 2^(ec_size - 1) of the generator polynomial
+    let syndromes: Vec<u8> = (0..ec_size)
+        .map(|i| {
+            block
+                .iter()
+                .fold(0, |acc, cw| gf_mul(acc, gf_exp(i as isize)) ^ cw)
+        })
+        .collect();
+
+    if syndromes.iter().all(|s| *s == 0) {
+        return Ok((block.to_vec(), 0));
+    }
+
+    let locator_of = |i: usize| gf_exp((n - 1 - i) as isize);
+
+    // Berlekamp-Massey, starting from the erasure locator
+    let mut locator = vec![1u8];
+    for &i in erasures {
+        locator = poly_mul(&locator, &[1, locator_of(i)]);
+    }
+    let mut previous = locator.clone();
+    let mut length = erasures.len();
+
+    for k in erasures.len()..ec_size {
+        let discrepancy = (0..=length.min(k))
+            .filter(|j| *j < locator.len())
+            .fold(0, |acc, j| acc ^ gf_mul(locator[j], syndromes[k - j]));
+
+        previous.insert(0, 0);
+
+        if discrepancy != 0 {
+            let mut next = locator.clone();
+            next.resize(next.len().max(previous.len()), 0);
+            for (coeff, prev) in next.iter_mut().zip(&previous) {
+                *coeff ^= gf_mul(discrepancy, *prev);
+            }
+
+            if 2 * length <= k + erasures.len() {
+                let inverse = gf_inverse(discrepancy);
+                previous = locator.iter().map(|c| gf_mul(*c, inverse)).collect();
+                length = k + 1 + erasures.len() - length;
+            }
+
+            locator = next;
+        }
+    }
+
+    while locator.len() > 1 && locator[locator.len() - 1] == 0 {
+        locator.pop();
+    }
+
+    let errors = length - erasures.len();
+    if locator.len() - 1 != length || 2 * errors + erasures.len() > ec_size {
+        return Err(QrError::Uncorrectable);
+    }
+
+    // Chien search
+    let positions: Vec<usize> = (0..n)
+        .filter(|i| poly_eval(&locator, gf_exp(-((n - 1 - i) as isize))) == 0)
+        .collect();
+
+    if positions.len() != length {
+        return Err(QrError::Uncorrectable);
+    }
+
+    // Forney
+    let mut evaluator = poly_mul(&syndromes, &locator);
+    evaluator.truncate(ec_size);
+
+    let derivative: Vec<u8> = locator
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, coeff)| if i % 2 == 1 { *coeff } else { 0 })
+        .collect();
+
+    let mut corrected = block.to_vec();
+    let mut changed = 0;
+
+    for &i in &positions {
+        let x = locator_of(i);
+        let x_inverse = gf_inverse(x);
+        let denominator = poly_eval(&derivative, x_inverse);
+        if denominator == 0 {
+            return Err(QrError::Uncorrectable);
+        }
+
+        let magnitude = gf_mul(
+            gf_mul(x, poly_eval(&evaluator, x_inverse)),
+            gf_inverse(denominator),
+        );
+
+        if magnitude != 0 {
+            corrected[i] ^= magnitude;
+            changed += 1;
+        }
+    }
+
+    // The errors may have turned the block into a different, but wrong, codeword
+    let remaining = (0..ec_size).any(|i| {
+        corrected
+            .iter()
+            .fold(0, |acc, cw| gf_mul(acc, gf_exp(i as isize)) ^ cw)
+            != 0
+    });
+    if remaining {
+        return Err(QrError::Uncorrectable);
+    }
+
+    Ok((corrected, changed))
+}
+
+/// Splits interleaved codewords back into blocks of the given lengths.
+pub fn deinterleave(data: &[u8], lengths: &[usize]) -> Vec<Vec<u8>> {
+    let mut blocks: Vec<Vec<u8>> = lengths.iter().map(|len| Vec::with_capacity(*len)).collect();
+    let mut data = data.iter();
+    let max_len = lengths.iter().copied().max().unwrap_or(0);
+    for i in 0..max_len {
+        for (block, len) in blocks.iter_mut().zip(lengths) {
+            if i < *len {
+                if let Some(byte) = data.next() {
+                    block.push(*byte);
+                }
+            }
+        }
+    }
+    blocks
+}
+
+/// Lengths of the data blocks for a version and ec level. The 4 bit last data codeword of M1
+/// and M3 counts as a full byte.
+pub fn block_lengths(version: Version, ec_level: &EcLevel) -> Vec<usize> {
+    let Some((block_1_size, block_1_count, block_2_size, block_2_count)) =
+        version.blocks(*ec_level)
+    else {
+        let data_bits = version.data_capacity(*ec_level).unwrap_or(0);
+        return vec![data_bits.div_ceil(8)];
+    };
+
+    let mut lengths = vec![block_1_size; block_1_count];
+    lengths.append(&mut vec![block_2_size; block_2_count]);
+    lengths
+}
+
 #[cfg(test)]
 mod interleave_tests {
     use super::*;
@@ -137,12 +339,43 @@ mod interleave_tests {
         let result = interleave(blocks);
         assert_eq!(result, vec![1, 2, 3]);
     }
+
+    #[test]
+    fn deinterleave_reverses_interleave() {
+        let blocks = vec![vec![1, 2], vec![3, 4], vec![5, 6, 7], vec![8, 9, 10]];
+        let result = deinterleave(&interleave(blocks.clone()), &[2, 2, 3, 3]);
+        assert_eq!(result, blocks);
+    }
+
+    #[test]
+    fn block_lengths_lists_both_groups() {
+        assert_eq!(block_lengths(Version::Normal(1), &EcLevel::M), vec![16]);
+        assert_eq!(
+            block_lengths(Version::Normal(5), &EcLevel::Q),
+            vec![15, 15, 16, 16]
+        );
+        assert_eq!(block_lengths(Version::Micro(3), &EcLevel::L), vec![11]);
+        assert_eq!(
+            block_lengths(Version::Rmqr(13, 139), &EcLevel::M),
+            vec![35, 35, 36]
+        );
+    }
 }
 
 #[cfg(test)]
 mod ec_tests {
     use super::*;
 
+    #[test]
+    fn codewords_of_micro_qr_use_a_single_block() {
+        // "01234567" in M2-L, ISO/IEC 18004 Annex I
+        let data = [0x40, 0x18, 0xAC, 0xC3, 0x00];
+        let (data_codewords, ec_codewords) = codewords(&data, Version::Micro(2), &EcLevel::L, 5);
+
+        assert_eq!(data_codewords, data);
+        assert_eq!(ec_codewords, vec![0x86, 0x0D, 0x22, 0xAE, 0x30]);
+    }
+
     #[test]
     fn create_ec_for_block_works_simple() {
         let block = vec![1, 2, 3];
@@ -165,3 +398,84 @@ mod ec_tests {
         ]));
     }
 }
+
+#[cfg(test)]
+mod correction_tests {
+    use super::*;
+
+    fn encoded_block() -> Vec<u8> {
+        let data = vec![
+            32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236, 17, 236, 17,
+        ];
+        let mut block = data.clone();
+        block.append(&mut create_ec_for_block(
+            data,
+            10,
+            GENERATOR_POLYNOMIALS[10],
+        ));
+        block
+    }
+
+    #[test]
+    fn correct_block_leaves_valid_block_untouched() {
+        let block = encoded_block();
+        assert_eq!(correct_block(&block, 10, &[]), Ok((block, 0)));
+    }
+
+    #[test]
+    fn correct_block_fixes_up_to_half_ec_size_errors() {
+        let block = encoded_block();
+        let mut damaged = block.clone();
+        for (i, position) in [0, 3, 11, 17, 25].iter().enumerate() {
+            damaged[*position] ^= 0x5A + i as u8;
+        }
+
+        assert_eq!(correct_block(&damaged, 10, &[]), Ok((block, 5)));
+    }
+
+    #[test]
+    fn correct_block_fixes_up_to_ec_size_erasures() {
+        let block = encoded_block();
+        let erasures: Vec<usize> = (4..14).collect();
+        let mut damaged = block.clone();
+        for position in &erasures {
+            damaged[*position] = 0;
+        }
+
+        let (corrected, _) = correct_block(&damaged, 10, &erasures).unwrap();
+        assert_eq!(corrected, block);
+    }
+
+    #[test]
+    fn correct_block_fixes_errors_and_erasures() {
+        let block = encoded_block();
+        let mut damaged = block.clone();
+        damaged[1] ^= 0xFF;
+        damaged[2] ^= 0x01;
+        damaged[20] = 0;
+        damaged[21] = 0;
+        damaged[22] = 0;
+
+        let (corrected, _) = correct_block(&damaged, 10, &[20, 21, 22, 23]).unwrap();
+        assert_eq!(corrected, block);
+    }
+
+    #[test]
+    fn correct_block_reports_uncorrectable_blocks() {
+        let mut damaged = encoded_block();
+        for codeword in damaged.iter_mut().take(8) {
+            *codeword ^= 0x33;
+        }
+
+        assert_eq!(
+            correct_block(&damaged, 10, &[]),
+            Err(QrError::Uncorrectable)
+        );
+    }
+
+    #[test]
+    fn correct_block_rejects_too_many_erasures() {
+        let erasures: Vec<usize> = (0..11).collect();
+        assert!(correct_block(&encoded_block(), 10, &erasures).is_err());
+    }
+}
@@ -0,0 +1,248 @@
+use crate::bit::Bit;
+use crate::encoding::Encoding;
+use crate::error::QrError;
+use crate::version::Version;
+use encoding_rs::{
+    ISO_8859_10, ISO_8859_13, ISO_8859_14, ISO_8859_15, ISO_8859_16, ISO_8859_2, ISO_8859_3,
+    ISO_8859_4, ISO_8859_5, ISO_8859_6, ISO_8859_7, ISO_8859_8, SHIFT_JIS, UTF_8, WINDOWS_1250,
+    WINDOWS_1251, WINDOWS_1252, WINDOWS_1256,
+};
+
+/// Character set of the byte mode data, announced with an Extended Channel Interpretation
+/// (ECI) header when it is not the default ISO-8859-1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Charset {
+    Iso8859_1,
+    Iso8859_2,
+    Iso8859_3,
+    Iso8859_4,
+    Iso8859_5,
+    Iso8859_6,
+    Iso8859_7,
+    Iso8859_8,
+    Iso8859_10,
+    Iso8859_13,
+    Iso8859_14,
+    Iso8859_15,
+    Iso8859_16,
+    ShiftJis,
+    Windows1250,
+    Windows1251,
+    Windows1252,
+    Windows1256,
+    Utf8,
+}
+
+impl Charset {
+    pub const ALL: [Charset; 19] = [
+        Charset::Iso8859_1,
+        Charset::Iso8859_2,
+        Charset::Iso8859_3,
+        Charset::Iso8859_4,
+        Charset::Iso8859_5,
+        Charset::Iso8859_6,
+        Charset::Iso8859_7,
+        Charset::Iso8859_8,
+        Charset::Iso8859_10,
+        Charset::Iso8859_13,
+        Charset::Iso8859_14,
+        Charset::Iso8859_15,
+        Charset::Iso8859_16,
+        Charset::ShiftJis,
+        Charset::Windows1250,
+        Charset::Windows1251,
+        Charset::Windows1252,
+        Charset::Windows1256,
+        Charset::Utf8,
+    ];
+
+    /// ECI assignment number (AIM ECI specification).
+    pub fn eci(&self) -> u32 {
+        match self {
+            Charset::Iso8859_1 => 3,
+            Charset::Iso8859_2 => 4,
+            Charset::Iso8859_3 => 5,
+            Charset::Iso8859_4 => 6,
+            Charset::Iso8859_5 => 7,
+            Charset::Iso8859_6 => 8,
+            Charset::Iso8859_7 => 9,
+            Charset::Iso8859_8 => 10,
+            Charset::Iso8859_10 => 12,
+            Charset::Iso8859_13 => 15,
+            Charset::Iso8859_14 => 16,
+            Charset::Iso8859_15 => 17,
+            Charset::Iso8859_16 => 18,
+            Charset::ShiftJis => 20,
+            Charset::Windows1250 => 21,
+            Charset::Windows1251 => 22,
+            Charset::Windows1252 => 23,
+            Charset::Windows1256 => 24,
+            Charset::Utf8 => 26,
+        }
+    }
+
+    /// Charset of an ECI assignment number. ECI 1 is the legacy number of ISO-8859-1.
+    pub fn from_eci(eci: u32) -> Option<Charset> {
+        match eci {
+            1 => Some(Charset::Iso8859_1),
+            _ => Self::ALL.into_iter().find(|charset| charset.eci() == eci),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Charset::Iso8859_1 => "ISO-8859-1",
+            _ => self.encoding().name(),
+        }
+    }
+
+    /// Charset with the given name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Charset> {
+        Self::ALL
+            .into_iter()
+            .find(|charset| charset.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The encoding_rs encoding, except for ISO-8859-1 which encoding_rs treats as
+    /// Windows-1252.
+    fn encoding(&self) -> &'static encoding_rs::Encoding {
+        match self {
+            Charset::Iso8859_1 => unreachable!("ISO-8859-1 is encoded by hand"),
+            Charset::Iso8859_2 => ISO_8859_2,
+            Charset::Iso8859_3 => ISO_8859_3,
+            Charset::Iso8859_4 => ISO_8859_4,
+            Charset::Iso8859_5 => ISO_8859_5,
+            Charset::Iso8859_6 => ISO_8859_6,
+            Charset::Iso8859_7 => ISO_8859_7,
+            Charset::Iso8859_8 => ISO_8859_8,
+            Charset::Iso8859_10 => ISO_8859_10,
+            Charset::Iso8859_13 => ISO_8859_13,
+            Charset::Iso8859_14 => ISO_8859_14,
+            Charset::Iso8859_15 => ISO_8859_15,
+            Charset::Iso8859_16 => ISO_8859_16,
+            Charset::ShiftJis => SHIFT_JIS,
+            Charset::Windows1250 => WINDOWS_1250,
+            Charset::Windows1251 => WINDOWS_1251,
+            Charset::Windows1252 => WINDOWS_1252,
+            Charset::Windows1256 => WINDOWS_1256,
+            Charset::Utf8 => UTF_8,
+        }
+    }
+
+    /// Bytes of `c` in this charset, if it has it.
+    pub fn encode_char(&self, c: char) -> Option<Vec<u8>> {
+        if *self == Charset::Iso8859_1 {
+            return u8::try_from(c).ok().map(|byte| vec![byte]);
+        }
+
+        let mut buffer = [0u8; 4];
+        let (bytes, _, unmappable) = self.encoding().encode(c.encode_utf8(&mut buffer));
+        (!unmappable).then(|| bytes.into_owned())
+    }
+
+    pub fn encode(&self, data: &str) -> Result<Vec<u8>, QrError> {
+        let mut bytes = Vec::with_capacity(data.len());
+        for (position, ch) in data.chars().enumerate() {
+            let encoded = self.encode_char(ch).ok_or(QrError::InvalidCharacter {
+                ch,
+                position,
+                mode: Encoding::Byte,
+            })?;
+            bytes.extend_from_slice(&encoded);
+        }
+        Ok(bytes)
+    }
+
+    /// Text of `bytes` in this charset, or `None` if they are malformed.
+    pub fn decode(&self, bytes: &[u8]) -> Option<String> {
+        if *self == Charset::Iso8859_1 {
+            // Its code points match the first 256 of Unicode
+            return Some(bytes.iter().map(|byte| *byte as char).collect());
+        }
+
+        self.encoding()
+            .decode_without_bom_handling_and_without_replacement(bytes)
+            .map(|text| text.into_owned())
+    }
+
+    /// Mode indicator 0111 (111 in rMQR) followed by the 1, 2 or 3 byte ECI designator.
+    pub fn header(&self, version: Version) -> Vec<Bit> {
+        let eci = self.eci();
+        let mut bits = match version {
+            Version::Rmqr(..) => Bit::from(0b111, 3, false, true),
+            _ => Bit::from(0b0111, 4, false, true),
+        };
+
+        match eci {
+            0..=127 => bits.append(&mut Bit::from(eci, 8, false, true)),
+            128..=16383 => bits.append(&mut Bit::from(0b10 << 14 | eci, 16, false, true)),
+            _ => bits.append(&mut Bit::from(0b110 << 21 | eci, 24, false, true)),
+        }
+
+        bits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_u32(bits: &[Bit]) -> u32 {
+        bits.iter()
+            .fold(0, |acc, bit| (acc << 1) | bit.value() as u32)
+    }
+
+    #[test]
+    fn header_writes_mode_and_designator() {
+        let header = Charset::Utf8.header(Version::Normal(1));
+        assert_eq!(header.len(), 12);
+        assert_eq!(to_u32(&header), 0b0111_0001_1010);
+
+        let header = Charset::Utf8.header(Version::Rmqr(7, 43));
+        assert_eq!(to_u32(&header), 0b111_0001_1010);
+    }
+
+    #[test]
+    fn encode_transcodes_to_the_charset() {
+        assert_eq!(Charset::Utf8.encode("é😀").unwrap().len(), 6);
+        assert_eq!(Charset::Iso8859_1.encode("é"), Ok(vec![0xE9]));
+        assert_eq!(Charset::Iso8859_7.encode("Ωμέγα").unwrap()[0], 0xD9);
+        assert_eq!(
+            Charset::Windows1251.encode("Жук"),
+            Ok(vec![0xC6, 0xF3, 0xEA])
+        );
+        assert_eq!(Charset::ShiftJis.encode("点"), Ok(vec![0x93, 0x5F]));
+    }
+
+    #[test]
+    fn encode_reports_missing_characters() {
+        assert_eq!(
+            Charset::Iso8859_5.encode("Жé"),
+            Err(QrError::InvalidCharacter {
+                ch: 'é',
+                position: 1,
+                mode: Encoding::Byte
+            })
+        );
+    }
+
+    #[test]
+    fn decode_reverses_encode() {
+        for charset in Charset::ALL {
+            let text = "QR 123";
+            let bytes = charset.encode(text).unwrap();
+            assert_eq!(charset.decode(&bytes).as_deref(), Some(text));
+        }
+        assert_eq!(Charset::Utf8.decode(&[0xFF]), None);
+    }
+
+    #[test]
+    fn eci_numbers_round_trip() {
+        for charset in Charset::ALL {
+            assert_eq!(Charset::from_eci(charset.eci()), Some(charset));
+            assert_eq!(Charset::from_name(charset.name()), Some(charset));
+        }
+        assert_eq!(Charset::from_eci(1), Some(Charset::Iso8859_1));
+        assert_eq!(Charset::from_name("utf-8"), Some(Charset::Utf8));
+    }
+}
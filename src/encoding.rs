@@ -1,8 +1,17 @@
 use crate::bit::Bit;
+use crate::error::QrError;
+use crate::tables::{
+    ALPHANUMERIC_CHAR_COUNT, ALPHANUMERIC_MICRO_CHAR_COUNT, ALPHANUMERIC_RMQR_CHAR_COUNT,
+    BYTE_CHAR_COUNT, BYTE_MICRO_CHAR_COUNT, BYTE_RMQR_CHAR_COUNT, KANJI_CHAR_COUNT,
+    KANJI_MICRO_CHAR_COUNT, KANJI_RMQR_CHAR_COUNT, NUMERIC_CHAR_COUNT, NUMERIC_MICRO_CHAR_COUNT,
+    NUMERIC_RMQR_CHAR_COUNT,
+};
+use crate::version::Version;
+use encoding_rs::SHIFT_JIS;
 
 pub fn to_bits_str(data: &str) -> Vec<Bit> {
     data.chars()
-        .map(|c| {
+        .flat_map(|c| {
             let mut bits = vec![];
             for i in 0..8 {
                 let bit = (c as u8 >> i) & 1;
@@ -15,13 +24,12 @@ pub fn to_bits_str(data: &str) -> Vec<Bit> {
             bits.reverse();
             bits
         })
-        .flatten()
         .collect()
 }
 
 pub fn to_bits_array(data: &[u8]) -> Vec<Bit> {
     data.iter()
-        .map(|c| {
+        .flat_map(|c| {
             let mut bits = vec![];
             for i in 0..8 {
                 let bit = (c >> i) & 1u8;
@@ -34,11 +42,10 @@ pub fn to_bits_array(data: &[u8]) -> Vec<Bit> {
             bits.reverse();
             bits
         })
-        .flatten()
         .collect()
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Encoding {
     Numeric,
     Alphanumeric,
@@ -47,6 +54,26 @@ pub enum Encoding {
 }
 
 impl Encoding {
+    /// Every mode, in the order of their Micro QR mode indicator values (rMQR ones start at 1).
+    pub const ALL: [Encoding; 4] = [
+        Encoding::Numeric,
+        Encoding::Alphanumeric,
+        Encoding::Byte,
+        Encoding::Kanji,
+    ];
+
+    /// Mode indicator in the given version, 0 to 3 bits wide in Micro QR codes and 3 bits wide
+    /// in rMQR.
+    pub fn mode_indicator(&self, version: Version) -> Result<Vec<Bit>, QrError> {
+        let position = Self::ALL.iter().position(|mode| mode == self).unwrap() as u32;
+        let mode_bits = version.mode_bits()? as u8;
+        Ok(match version {
+            Version::Micro(_) => Bit::from(position, mode_bits, false, true),
+            Version::Normal(_) => self.mod_indicator(),
+            Version::Rmqr(..) => Bit::from(position + 1, mode_bits, false, true),
+        })
+    }
+
     pub fn mod_indicator(&self) -> Vec<Bit> {
         match self {
             Encoding::Numeric => {
@@ -84,97 +111,288 @@ impl Encoding {
         }
     }
 
-    pub fn encode(&self, data: &str) -> Result<Vec<Bit>, String> {
+    pub fn encode(&self, data: &str) -> Result<Vec<Bit>, QrError> {
+        self.encode_bytes(&self.to_bytes(data)?)
+    }
+
+    /// Encodes payload bytes as produced by [`Encoding::to_bytes`].
+    pub fn encode_bytes(&self, data: &[u8]) -> Result<Vec<Bit>, QrError> {
         match self {
-            Encoding::Numeric => Encoding::encode_numeric(data),
-            Encoding::Alphanumeric => Encoding::encode_alphanumeric(data),
-            Encoding::Byte => Encoding::encode_byte(data),
-            Encoding::Kanji => {
-                unimplemented!()
-            }
+            Encoding::Numeric => self.encode_numeric(data),
+            Encoding::Alphanumeric => self.encode_alphanumeric(data),
+            Encoding::Byte => Ok(Bit::bits(data, data.len() * 8)),
+            Encoding::Kanji => self.encode_kanji(data),
         }
     }
 
-    fn encode_alphanumeric(data: &str) -> Result<Vec<Bit>, String> {
-        let pairs = data
-            .chars()
-            .map(|c| Self::alphanumeric_value(c))
-            .collect::<Result<Vec<u16>, String>>();
-
-        match pairs {
-            Ok(vec) => Ok(vec
-                .chunks(2)
-                .flat_map(|pair| {
-                    if pair.len() == 2 {
-                        let value = pair[0] * 45 + pair[1];
-                        Bit::from(value as u32, 11, false, true)
-                    } else {
-                        let value = pair[0];
-                        Bit::from(value as u32, 6, false, true)
-                    }
-                })
-                .collect()),
-            Err(msg) => Err(msg),
+    /// Width of the character count indicator for the given version. Fails if the version
+    /// does not exist or the mode is not available in that Micro QR version.
+    pub fn char_count_bits(&self, version: Version) -> Result<u8, QrError> {
+        version.check()?;
+
+        let bits = match version {
+            Version::Micro(number) => {
+                let index = number as usize - 1;
+                match self {
+                    Encoding::Numeric => NUMERIC_MICRO_CHAR_COUNT[index],
+                    Encoding::Alphanumeric => ALPHANUMERIC_MICRO_CHAR_COUNT[index],
+                    Encoding::Byte => BYTE_MICRO_CHAR_COUNT[index],
+                    Encoding::Kanji => KANJI_MICRO_CHAR_COUNT[index],
+                }
+            }
+            Version::Normal(number) => {
+                let index = match number {
+                    1..=9 => 0,
+                    10..=26 => 1,
+                    _ => 2,
+                };
+                match self {
+                    Encoding::Numeric => NUMERIC_CHAR_COUNT[index],
+                    Encoding::Alphanumeric => ALPHANUMERIC_CHAR_COUNT[index],
+                    Encoding::Byte => BYTE_CHAR_COUNT[index],
+                    Encoding::Kanji => KANJI_CHAR_COUNT[index],
+                }
+            }
+            Version::Rmqr(..) => {
+                let index = version
+                    .rmqr_indicator()
+                    .ok_or(QrError::InvalidVersion(version))? as usize;
+                match self {
+                    Encoding::Numeric => NUMERIC_RMQR_CHAR_COUNT[index],
+                    Encoding::Alphanumeric => ALPHANUMERIC_RMQR_CHAR_COUNT[index],
+                    Encoding::Byte => BYTE_RMQR_CHAR_COUNT[index],
+                    Encoding::Kanji => KANJI_RMQR_CHAR_COUNT[index],
+                }
+            }
+        };
+
+        match bits {
+            0 => Err(QrError::Unsupported(format!(
+                "{:?} mode is not available in version {}",
+                self, version
+            ))),
+            bits => Ok(bits),
         }
     }
 
-    fn alphanumeric_value(c: char) -> Result<u16, String> {
-        match c {
-            '0'..='9' => Ok(c as u16 - '0' as u16),
-            'A'..='Z' => Ok(c as u16 - 'A' as u16 + 10),
-            ' ' => Ok(36),
-            '$' => Ok(37),
-            '%' => Ok(38),
-            '*' => Ok(39),
-            '+' => Ok(40),
-            '-' => Ok(41),
-            '.' => Ok(42),
-            '/' => Ok(43),
-            ':' => Ok(44),
-            _ => Err(format!("Invalid character: {}", c)),
-        }
+    pub fn can_encode(&self, c: char) -> bool {
+        self.char_bytes(c).is_some()
     }
 
-    fn encode_byte(data: &str) -> Result<Vec<Bit>, String> {
-        let bytes: Result<Vec<u8>, String> =
-            data.chars().map(|c| Self::char_to_iso_8859_1(c)).collect();
+    /// Number of characters written in the character count indicator.
+    ///
+    /// Every mode encodes one unit per character: a digit, an alphanumeric character, an
+    /// ISO-8859-1 byte or a Shift JIS double byte.
+    pub fn char_count(&self, data: &str) -> usize {
+        data.chars().count()
+    }
 
-        match bytes {
-            Ok(vec) => Ok(vec
-                .iter()
-                .flat_map(|byte| Bit::from(*byte as u32, 8, false, true))
-                .collect()),
-            Err(msg) => Err(msg),
+    /// Number of characters in payload bytes, two bytes making one kanji.
+    pub fn byte_char_count(&self, data: &[u8]) -> usize {
+        match self {
+            Encoding::Kanji => data.len() / 2,
+            _ => data.len(),
         }
     }
 
-    fn char_to_iso_8859_1(c: char) -> Result<u8, String> {
-        if (c as u32) <= 0xFF {
-            Ok(c as u8)
-        } else {
-            Err(format!("Invalid character: {}", c))
+    /// Payload bytes of `data`: ASCII for the numeric and alphanumeric modes, ISO-8859-1 for
+    /// byte mode and Shift JIS for kanji mode.
+    pub fn to_bytes(&self, data: &str) -> Result<Vec<u8>, QrError> {
+        let mut bytes = Vec::with_capacity(data.len());
+        for (position, ch) in data.chars().enumerate() {
+            let encoded = self.char_bytes(ch).ok_or(QrError::InvalidCharacter {
+                ch,
+                position,
+                mode: *self,
+            })?;
+            bytes.extend_from_slice(&encoded);
         }
+        Ok(bytes)
     }
 
-    fn encode_numeric(data: &str) -> Result<Vec<Bit>, String> {
-        let mut bits = vec![];
-        let mut i = 0;
-        while i < data.len() {
-            let mut value = 0;
-            for j in 0..3 {
-                if i + j < data.len() {
-                    let digit = data.chars().nth(i + j).unwrap();
-                    if digit < '0' || digit > '9' {
-                        return Err(format!("Invalid character: {}", digit));
-                    }
-                    value = value * 10 + (digit as u32 - '0' as u32);
+    /// Payload bytes of a single character, if this mode can encode it.
+    pub fn char_bytes(&self, c: char) -> Option<Vec<u8>> {
+        match self {
+            Encoding::Numeric => c.is_ascii_digit().then(|| vec![c as u8]),
+            Encoding::Alphanumeric => Self::alphanumeric_value(c).map(|_| vec![c as u8]),
+            Encoding::Byte => u8::try_from(c).ok().map(|byte| vec![byte]),
+            Encoding::Kanji => Self::char_to_shift_jis(c)
+                .filter(|code| Self::kanji_code_value(*code).is_some())
+                .map(|code| code.to_be_bytes().to_vec()),
+        }
+    }
+
+    /// Maps every payload byte with `value`, failing on the first one this mode cannot encode.
+    fn values<T>(&self, data: &[u8], value: fn(char) -> Option<T>) -> Result<Vec<T>, QrError> {
+        data.iter()
+            .enumerate()
+            .map(|(position, byte)| {
+                let ch = *byte as char;
+                value(ch).ok_or(QrError::InvalidCharacter {
+                    ch,
+                    position,
+                    mode: *self,
+                })
+            })
+            .collect()
+    }
+
+    fn encode_alphanumeric(&self, data: &[u8]) -> Result<Vec<Bit>, QrError> {
+        let values = self.values(data, Self::alphanumeric_value)?;
+
+        Ok(values
+            .chunks(2)
+            .flat_map(|pair| {
+                if pair.len() == 2 {
+                    let value = pair[0] * 45 + pair[1];
+                    Bit::from(value as u32, 11, false, true)
                 } else {
-                    break;
+                    let value = pair[0];
+                    Bit::from(value as u32, 6, false, true)
                 }
-            }
-            bits.append(&mut Bit::from(value, 10, false, true));
-            i += 3;
+            })
+            .collect())
+    }
+
+    fn alphanumeric_value(c: char) -> Option<u16> {
+        match c {
+            '0'..='9' => Some(c as u16 - '0' as u16),
+            'A'..='Z' => Some(c as u16 - 'A' as u16 + 10),
+            ' ' => Some(36),
+            '$' => Some(37),
+            '%' => Some(38),
+            '*' => Some(39),
+            '+' => Some(40),
+            '-' => Some(41),
+            '.' => Some(42),
+            '/' => Some(43),
+            ':' => Some(44),
+            _ => None,
         }
-        Ok(bits)
+    }
+
+    fn encode_kanji(&self, data: &[u8]) -> Result<Vec<Bit>, QrError> {
+        let values = data
+            .chunks(2)
+            .enumerate()
+            .map(|(position, pair)| {
+                let value = match pair {
+                    &[high, low] => Self::kanji_code_value(u16::from_be_bytes([high, low])),
+                    _ => None,
+                };
+                value.ok_or_else(|| QrError::InvalidCharacter {
+                    ch: SHIFT_JIS
+                        .decode_without_bom_handling(pair)
+                        .0
+                        .chars()
+                        .next()
+                        .unwrap_or(char::REPLACEMENT_CHARACTER),
+                    position,
+                    mode: *self,
+                })
+            })
+            .collect::<Result<Vec<u16>, QrError>>()?;
+
+        Ok(values
+            .iter()
+            .flat_map(|value| Bit::from(*value as u32, 13, false, true))
+            .collect())
+    }
+
+    /// Packs a double byte Shift JIS code into 13 bits (ISO/IEC 18004, 7.4.6).
+    fn kanji_code_value(code: u16) -> Option<u16> {
+        let offset = match code {
+            0x8140..=0x9FFC => code - 0x8140,
+            0xE040..=0xEBBF => code - 0xC140,
+            _ => return None,
+        };
+
+        Some((offset >> 8) * 0xC0 + (offset & 0xFF))
+    }
+
+    fn char_to_shift_jis(c: char) -> Option<u16> {
+        let mut buffer = [0u8; 4];
+        let (bytes, _, unmappable) = SHIFT_JIS.encode(c.encode_utf8(&mut buffer));
+
+        match (unmappable, bytes.as_ref()) {
+            (false, &[high, low]) => Some(u16::from_be_bytes([high, low])),
+            _ => None,
+        }
+    }
+
+    fn encode_numeric(&self, data: &[u8]) -> Result<Vec<Bit>, QrError> {
+        let digits = self.values(data, |c| c.to_digit(10))?;
+
+        // Groups of 3 digits take 10 bits, a trailing group of 2 digits 7 bits and of 1 digit 4 bits
+        Ok(digits
+            .chunks(3)
+            .flat_map(|group| {
+                let value = group.iter().fold(0, |acc, digit| acc * 10 + digit);
+                Bit::from(value, group.len() as u8 * 3 + 1, false, true)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod kanji_tests {
+    use super::*;
+
+    fn kanji_value(c: char) -> Option<u16> {
+        Encoding::char_to_shift_jis(c).and_then(Encoding::kanji_code_value)
+    }
+
+    fn to_u32(bits: &[Bit]) -> u32 {
+        bits.iter()
+            .fold(0, |acc, bit| (acc << 1) | bit.value() as u32)
+    }
+
+    #[test]
+    fn kanji_value_works_in_first_range() {
+        assert_eq!(kanji_value('点'), Some(0x0D9F));
+    }
+
+    #[test]
+    fn kanji_value_works_in_second_range() {
+        assert_eq!(kanji_value('茗'), Some(0x1AAA));
+    }
+
+    #[test]
+    fn kanji_value_rejects_single_byte_characters() {
+        assert!(kanji_value('A').is_none());
+        assert!(kanji_value('ｱ').is_none());
+    }
+
+    #[test]
+    fn kanji_value_rejects_unmappable_characters() {
+        assert!(kanji_value('é').is_none());
+        assert!(kanji_value('😀').is_none());
+    }
+
+    #[test]
+    fn encode_kanji_packs_13_bits_per_character() {
+        let bits = Encoding::Kanji.encode("点茗").unwrap();
+        assert_eq!(bits.len(), 26);
+        assert_eq!(to_u32(&bits[..13]), 0x0D9F);
+        assert_eq!(to_u32(&bits[13..]), 0x1AAA);
+    }
+
+    #[test]
+    fn char_count_counts_kanji_characters() {
+        assert_eq!(Encoding::Kanji.char_count("点茗"), 2);
+        assert_eq!(Encoding::Byte.char_count("ABC"), 3);
+        assert_eq!(Encoding::Byte.char_count("café"), 4);
+    }
+
+    #[test]
+    fn encode_reports_position_of_invalid_character() {
+        assert_eq!(
+            Encoding::Kanji.encode("点茗A"),
+            Err(QrError::InvalidCharacter {
+                ch: 'A',
+                position: 2,
+                mode: Encoding::Kanji
+            })
+        );
     }
 }
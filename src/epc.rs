@@ -0,0 +1,474 @@
+use crate::builder::QrCodeBuilder;
+use crate::ec::EcLevel;
+use crate::eci::Charset;
+use crate::error::QrError;
+use crate::qrcode::QrCode;
+
+/// Largest payload, in bytes.
+pub const MAX_BYTES: usize = 331;
+
+/// IBAN lengths of the SEPA countries.
+const SEPA_COUNTRIES: [(&str, usize); 37] = [
+    ("AD", 24),
+    ("AT", 20),
+    ("BE", 16),
+    ("BG", 22),
+    ("CH", 21),
+    ("CY", 28),
+    ("CZ", 24),
+    ("DE", 22),
+    ("DK", 18),
+    ("EE", 20),
+    ("ES", 24),
+    ("FI", 18),
+    ("FR", 27),
+    ("GB", 22),
+    ("GI", 23),
+    ("GR", 27),
+    ("HR", 21),
+    ("HU", 28),
+    ("IE", 22),
+    ("IS", 26),
+    ("IT", 27),
+    ("LI", 21),
+    ("LT", 20),
+    ("LU", 20),
+    ("LV", 21),
+    ("MC", 27),
+    ("MT", 31),
+    ("NL", 18),
+    ("NO", 15),
+    ("PL", 28),
+    ("PT", 25),
+    ("RO", 24),
+    ("SE", 24),
+    ("SI", 19),
+    ("SK", 24),
+    ("SM", 27),
+    ("VA", 22),
+];
+
+/// Version of the EPC069-12 guidelines. Version 002 makes the BIC optional.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EpcVersion {
+    V1,
+    V2,
+}
+
+/// Remittance information, either structured or free text.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Remittance {
+    /// ISO 11649 creditor reference, like `RF18539007547034`.
+    Reference(String),
+    Text(String),
+}
+
+/// SEPA credit transfer payload of the EPC069-12 guidelines, also known as GiroCode.
+///
+/// ```
+/// use qrcode::Epc;
+///
+/// let epc = Epc::new("Red Cross of Belgium", "BE72 0000 0000 1616")
+///     .bic("BPOTBEB1")
+///     .amount(100)
+///     .purpose("CHAR")
+///     .text("Urgency fund");
+/// assert_eq!(
+///     epc.data().unwrap(),
+///     "BCD\n002\n1\nSCT\nBPOTBEB1\nRed Cross of Belgium\nBE72000000001616\nEUR1.00\nCHAR\n\nUrgency fund"
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Epc {
+    version: EpcVersion,
+    charset: Charset,
+    bic: Option<String>,
+    name: String,
+    iban: String,
+    amount: Option<u64>,
+    purpose: Option<String>,
+    remittance: Option<Remittance>,
+    information: Option<String>,
+}
+
+impl Epc {
+    /// Version 002 transfer in UTF-8 to the beneficiary `name`. Spaces in the IBAN are dropped.
+    pub fn new(name: &str, iban: &str) -> Epc {
+        Epc {
+            version: EpcVersion::V2,
+            charset: Charset::Utf8,
+            bic: None,
+            name: name.to_string(),
+            iban: iban.replace(' ', ""),
+            amount: None,
+            purpose: None,
+            remittance: None,
+            information: None,
+        }
+    }
+
+    pub fn version(mut self, version: EpcVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Charset of the payload: UTF-8, ISO-8859-1, 2, 4, 5, 7, 10 or 15.
+    pub fn charset(mut self, charset: Charset) -> Self {
+        self.charset = charset;
+        self
+    }
+
+    pub fn bic(mut self, bic: &str) -> Self {
+        self.bic = Some(bic.to_string());
+        self
+    }
+
+    /// Amount in euro cents.
+    pub fn amount(mut self, cents: u64) -> Self {
+        self.amount = Some(cents);
+        self
+    }
+
+    /// Four letter ISO 20022 purpose code, like `CHAR` or `GDDS`.
+    pub fn purpose(mut self, purpose: &str) -> Self {
+        self.purpose = Some(purpose.to_string());
+        self
+    }
+
+    /// Structured creditor reference, replacing any remittance text.
+    pub fn reference(mut self, reference: &str) -> Self {
+        self.remittance = Some(Remittance::Reference(reference.replace(' ', "")));
+        self
+    }
+
+    /// Unstructured remittance text, replacing any creditor reference.
+    pub fn text(mut self, text: &str) -> Self {
+        self.remittance = Some(Remittance::Text(text.to_string()));
+        self
+    }
+
+    /// Information shown to the originator.
+    pub fn information(mut self, information: &str) -> Self {
+        self.information = Some(information.to_string());
+        self
+    }
+
+    /// Payload, with fields separated by LF and the trailing empty ones left out.
+    pub fn data(&self) -> Result<String, QrError> {
+        self.check()?;
+
+        let optional = |value: &Option<String>| value.clone().unwrap_or_default();
+        let (reference, text) = match &self.remittance {
+            Some(Remittance::Reference(reference)) => (reference.clone(), String::new()),
+            Some(Remittance::Text(text)) => (String::new(), text.clone()),
+            None => (String::new(), String::new()),
+        };
+        let mut fields = vec![
+            "BCD".to_string(),
+            match self.version {
+                EpcVersion::V1 => "001",
+                EpcVersion::V2 => "002",
+            }
+            .to_string(),
+            charset_code(self.charset).unwrap().to_string(),
+            "SCT".to_string(),
+            optional(&self.bic),
+            self.name.clone(),
+            self.iban.clone(),
+            self.amount
+                .map(|cents| format!("EUR{}.{:02}", cents / 100, cents % 100))
+                .unwrap_or_default(),
+            optional(&self.purpose),
+            reference,
+            text,
+            optional(&self.information),
+        ];
+        while fields.last().is_some_and(String::is_empty) {
+            fields.pop();
+        }
+
+        let data = fields.join("\n");
+        let length = self.charset.encode(&data)?.len();
+        if length > MAX_BYTES {
+            return Err(QrError::InvalidPayload(format!(
+                "the payload takes {} bytes, more than {}",
+                length, MAX_BYTES
+            )));
+        }
+        Ok(data)
+    }
+
+    /// QR code holding the payload in byte mode, without ECI header, at ec level M as the
+    /// guidelines require.
+    pub fn build(&self) -> Result<QrCode, QrError> {
+        let bytes = self.charset.encode(&self.data()?)?;
+        QrCodeBuilder::binary(&bytes).ec_level(EcLevel::M).build()
+    }
+
+    fn check(&self) -> Result<(), QrError> {
+        let invalid = |reason: String| Err(QrError::InvalidPayload(reason));
+        let too_long = |field: &str, value: &str, max: usize| {
+            let length = value.chars().count();
+            match length > max {
+                true => invalid(format!(
+                    "the {} has {} characters, more than {}",
+                    field, length, max
+                )),
+                false => Ok(()),
+            }
+        };
+
+        if charset_code(self.charset).is_none() {
+            return invalid(format!("{} is not an EPC charset", self.charset.name()));
+        }
+        match &self.bic {
+            Some(bic) => check_bic(bic)?,
+            None if self.version == EpcVersion::V1 => {
+                return invalid("version 001 needs a BIC".to_string())
+            }
+            None => {}
+        }
+        if self.name.is_empty() {
+            return invalid("the beneficiary needs a name".to_string());
+        }
+        too_long("name", &self.name, 70)?;
+        check_iban(&self.iban)?;
+        if let Some(cents) = self.amount {
+            if !(1..=99_999_999_999).contains(&cents) {
+                return invalid("the amount must be from EUR0.01 to EUR999999999.99".to_string());
+            }
+        }
+        if let Some(purpose) = &self.purpose {
+            if purpose.len() != 4 || !purpose.chars().all(|c| c.is_ascii_uppercase()) {
+                return invalid(format!("{:?} is not a purpose code", purpose));
+            }
+        }
+        match &self.remittance {
+            Some(Remittance::Reference(reference)) => check_creditor_reference(reference)?,
+            Some(Remittance::Text(text)) => too_long("remittance text", text, 140)?,
+            None => {}
+        }
+        if let Some(information) = &self.information {
+            too_long("information", information, 70)?;
+        }
+        Ok(())
+    }
+}
+
+/// Charset code of the guidelines, or `None` if they do not allow the charset.
+fn charset_code(charset: Charset) -> Option<u8> {
+    Some(match charset {
+        Charset::Utf8 => 1,
+        Charset::Iso8859_1 => 2,
+        Charset::Iso8859_2 => 3,
+        Charset::Iso8859_4 => 4,
+        Charset::Iso8859_5 => 5,
+        Charset::Iso8859_7 => 6,
+        Charset::Iso8859_10 => 7,
+        Charset::Iso8859_15 => 8,
+        _ => return None,
+    })
+}
+
+fn check_bic(bic: &str) -> Result<(), QrError> {
+    let valid = bic.is_ascii()
+        && matches!(bic.len(), 8 | 11)
+        && bic[..6].chars().all(|c| c.is_ascii_uppercase())
+        && bic[6..]
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+    match valid {
+        true => Ok(()),
+        false => Err(QrError::InvalidPayload(format!("{:?} is not a BIC", bic))),
+    }
+}
+
+/// Checks the length of a SEPA IBAN and its check digits.
+pub(crate) fn check_iban(iban: &str) -> Result<(), QrError> {
+    let invalid = |reason: &str| Err(QrError::InvalidPayload(format!("IBAN {} {}", iban, reason)));
+
+    if !iban
+        .chars()
+        .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+    {
+        return invalid("has characters other than capital letters and digits");
+    }
+    let country = iban.get(..2).unwrap_or(iban);
+    match SEPA_COUNTRIES.iter().find(|(code, _)| *code == country) {
+        None => return invalid("is not from a SEPA country"),
+        Some((_, length)) if iban.len() != *length => {
+            return invalid(&format!("needs {} characters", length))
+        }
+        _ => {}
+    }
+    match mod_97(&format!("{}{}", &iban[4..], &iban[..4])) {
+        Some(1) => Ok(()),
+        _ => invalid("has wrong check digits"),
+    }
+}
+
+/// Checks an ISO 11649 creditor reference: `RF`, two check digits and up to 21 letters or
+/// digits.
+pub(crate) fn check_creditor_reference(reference: &str) -> Result<(), QrError> {
+    let valid = reference.is_ascii()
+        && reference.starts_with("RF")
+        && (5..=25).contains(&reference.len())
+        && reference[2..4].chars().all(|c| c.is_ascii_digit())
+        && mod_97(&format!("{}{}", &reference[4..], &reference[..4])) == Some(1);
+    match valid {
+        true => Ok(()),
+        false => Err(QrError::InvalidPayload(format!(
+            "{:?} is not an ISO 11649 creditor reference",
+            reference
+        ))),
+    }
+}
+
+/// Remainder by 97 of the number made by writing letters as 10 to 35, or `None` if there are
+/// other characters.
+fn mod_97(value: &str) -> Option<u32> {
+    value.chars().try_fold(0, |remainder, c| {
+        let digit = c.to_digit(36)?;
+        Some(match digit {
+            0..=9 => (remainder * 10 + digit) % 97,
+            _ => (remainder * 100 + digit) % 97,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::decode;
+    use crate::version::Version;
+
+    fn reason(epc: Epc) -> String {
+        match epc.data() {
+            Err(QrError::InvalidPayload(reason)) => reason,
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn data_writes_the_fields() {
+        let epc = Epc::new("Red Cross of Belgium", "BE72000000001616")
+            .version(EpcVersion::V1)
+            .charset(Charset::Iso8859_1)
+            .bic("BPOTBEB1")
+            .amount(123_456)
+            .reference("RF18 5390 0754 7034")
+            .information("Sample EPC QR code");
+        assert_eq!(
+            epc.data().unwrap(),
+            "BCD\n001\n2\nSCT\nBPOTBEB1\nRed Cross of Belgium\nBE72000000001616\nEUR1234.56\n\n\
+             RF18539007547034\n\nSample EPC QR code"
+        );
+
+        let epc = Epc::new("Red Cross", "BE72000000001616");
+        assert_eq!(
+            epc.data().unwrap(),
+            "BCD\n002\n1\nSCT\n\nRed Cross\nBE72000000001616"
+        );
+        assert_eq!(
+            epc.text("Gift")
+                .reference("RF18539007547034")
+                .data()
+                .unwrap(),
+            "BCD\n002\n1\nSCT\n\nRed Cross\nBE72000000001616\n\n\nRF18539007547034"
+        );
+    }
+
+    #[test]
+    fn data_checks_the_iban() {
+        let epc = |iban| Epc::new("Red Cross", iban);
+        assert!(epc("DE89 3704 0044 0532 0130 00").data().is_ok());
+        assert_eq!(
+            reason(epc("DE89370400440532013001")),
+            "IBAN DE89370400440532013001 has wrong check digits"
+        );
+        assert_eq!(
+            reason(epc("DE8937040044053201300")),
+            "IBAN DE8937040044053201300 needs 22 characters"
+        );
+        assert_eq!(
+            reason(epc("US12345678901234")),
+            "IBAN US12345678901234 is not from a SEPA country"
+        );
+        assert_eq!(
+            reason(epc("de89370400440532013000")),
+            "IBAN de89370400440532013000 has characters other than capital letters and digits"
+        );
+    }
+
+    #[test]
+    fn data_checks_the_other_fields() {
+        let epc = || Epc::new("Red Cross", "BE72000000001616");
+
+        assert_eq!(
+            reason(epc().version(EpcVersion::V1)),
+            "version 001 needs a BIC"
+        );
+        assert_eq!(reason(epc().bic("BPOTBE")), "\"BPOTBE\" is not a BIC");
+        assert_eq!(
+            reason(epc().charset(Charset::ShiftJis)),
+            "Shift_JIS is not an EPC charset"
+        );
+        assert_eq!(
+            reason(Epc::new("", "BE72000000001616")),
+            "the beneficiary needs a name"
+        );
+        assert_eq!(
+            reason(epc().amount(0)),
+            "the amount must be from EUR0.01 to EUR999999999.99"
+        );
+        assert_eq!(
+            reason(epc().purpose("char")),
+            "\"char\" is not a purpose code"
+        );
+        assert_eq!(
+            reason(epc().reference("RF19539007547034")),
+            "\"RF19539007547034\" is not an ISO 11649 creditor reference"
+        );
+        assert_eq!(
+            reason(epc().text(&"x".repeat(141))),
+            "the remittance text has 141 characters, more than 140"
+        );
+        assert!(matches!(
+            epc()
+                .charset(Charset::Iso8859_1)
+                .text("Zahlung für € 10")
+                .data(),
+            Err(QrError::InvalidCharacter { ch: '€', .. })
+        ));
+    }
+
+    #[test]
+    fn data_enforces_the_byte_limit() {
+        let epc = Epc::new(&"é".repeat(70), "BE72000000001616")
+            .bic("BPOTBEB1")
+            .amount(100)
+            .text(&"ü".repeat(100))
+            .information(&"ö".repeat(40));
+        assert_eq!(
+            reason(epc.clone()),
+            "the payload takes 472 bytes, more than 331"
+        );
+        assert!(epc.charset(Charset::Iso8859_1).data().is_ok());
+    }
+
+    #[test]
+    fn build_encodes_bytes_at_ec_level_m() {
+        let epc = Epc::new("Zoë Müller", "BE72000000001616")
+            .charset(Charset::Iso8859_15)
+            .amount(2_500);
+        let qrcode = epc.build().unwrap();
+        assert_eq!(qrcode.ec_level(), EcLevel::M);
+        assert_eq!(qrcode.version(), Version::Normal(4));
+
+        let decoded = decode(&qrcode).unwrap();
+        assert_eq!(
+            decoded.bytes(),
+            Charset::Iso8859_15.encode(&epc.data().unwrap()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use crate::ec::EcLevel;
+use crate::encoding::Encoding;
+use crate::version::Version;
+use std::fmt;
+use std::fmt::Formatter;
+
+/// Errors returned while generating, rendering or decoding a QR code.
+#[derive(Clone, Debug, PartialEq)]
+pub enum QrError {
+    /// The encoded data needs more bits than the largest allowed version holds at the
+    /// requested ec level.
+    DataTooLong {
+        needed_bits: usize,
+        max_bits: usize,
+        version: Version,
+        ec_level: EcLevel,
+    },
+    /// A character cannot be encoded in the given mode. `position` counts characters, not
+    /// bytes.
+    InvalidCharacter {
+        ch: char,
+        position: usize,
+        mode: Encoding,
+    },
+    /// The version is not between 1 and 40, or M1 and M4.
+    InvalidVersion(Version),
+    /// The smallest allowed version is larger than the largest one.
+    InvalidVersionRange {
+        min_version: Version,
+        max_version: Version,
+    },
+    /// The symbol cannot use the requested ec level, mask or mode, such as Micro QR codes with
+    /// ec level H.
+    Unsupported(String),
+    /// A block has more errors than its ec codewords can correct.
+    Uncorrectable,
+    /// The modules do not form a readable symbol.
+    InvalidSymbol(String),
+    /// The image could not be rendered.
+    Render(String),
+    /// A structured payload breaks the rules of its format, such as a GTIN with a wrong check
+    /// digit.
+    InvalidPayload(String),
+}
+
+impl fmt::Display for QrError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            QrError::DataTooLong {
+                needed_bits,
+                max_bits,
+                version,
+                ec_level,
+            } => write!(
+                f,
+                "Data too long: {} bits needed but version {} holds at most {} with ec level \
+                 {:?}. Allow a larger version or a lower ec level.",
+                needed_bits, version, max_bits, ec_level
+            ),
+            QrError::InvalidCharacter { ch, position, mode } => write!(
+                f,
+                "Invalid character {:?} at position {} for {:?} mode.",
+                ch, position, mode
+            ),
+            QrError::InvalidVersion(version) => {
+                write!(
+                    f,
+                    "Invalid version: {}, expected 1 to 40 or M1 to M4.",
+                    version
+                )
+            }
+            QrError::InvalidVersionRange {
+                min_version,
+                max_version,
+            } => write!(
+                f,
+                "Invalid version range: minimum version {} is above maximum version {}.",
+                min_version, max_version
+            ),
+            QrError::Unsupported(reason) => write!(f, "Unsupported: {}", reason),
+            QrError::Uncorrectable => write!(f, "Uncorrectable block."),
+            QrError::InvalidSymbol(reason) => write!(f, "Invalid symbol: {}", reason),
+            QrError::Render(reason) => write!(f, "Rendering failed: {}", reason),
+            QrError::InvalidPayload(reason) => write!(f, "Invalid payload: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for QrError {}
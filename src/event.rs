@@ -0,0 +1,473 @@
+use crate::builder::QrCodeBuilder;
+use crate::contact::{escape_text, fold};
+use crate::ec::EcLevel;
+use crate::error::QrError;
+use crate::version::Version;
+use std::fmt::{Display, Formatter};
+
+/// Time zone of a [`DateTime`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Zone {
+    /// Local time wherever the event is read.
+    Floating,
+    Utc,
+    /// Time zone identifier, like `Europe/Zurich`.
+    Tzid(String),
+}
+
+/// Date and time of an event, in the RFC 5545 `DATE-TIME` form.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub zone: Zone,
+}
+
+impl DateTime {
+    /// Floating date and time, see [`DateTime::utc`] and [`DateTime::tzid`].
+    pub fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> DateTime {
+        DateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            zone: Zone::Floating,
+        }
+    }
+
+    pub fn utc(mut self) -> Self {
+        self.zone = Zone::Utc;
+        self
+    }
+
+    pub fn tzid(mut self, tzid: &str) -> Self {
+        self.zone = Zone::Tzid(tzid.to_string());
+        self
+    }
+
+    fn check(&self) -> Result<(), QrError> {
+        let leap = self.year.is_multiple_of(4)
+            && (!self.year.is_multiple_of(100) || self.year.is_multiple_of(400));
+        let days = match self.month {
+            2 if leap => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        };
+        // A second of 60 is a leap second
+        if self.year > 9999
+            || !(1..=12).contains(&self.month)
+            || !(1..=days).contains(&self.day)
+            || self.hour > 23
+            || self.minute > 59
+            || self.second > 60
+        {
+            return Err(invalid(format!("{} is not a valid date and time", self)));
+        }
+
+        match &self.zone {
+            Zone::Tzid(tzid)
+                if tzid.is_empty() || tzid.chars().any(|c| c.is_control() || c == '"') =>
+            {
+                Err(invalid(format!(
+                    "{:?} is not a valid time zone identifier",
+                    tzid
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Property with this value, along with the time zone parameter.
+    fn property(&self, name: &str) -> String {
+        match &self.zone {
+            // Parameter values with separators are quoted
+            Zone::Tzid(tzid) if tzid.contains([':', ';', ',']) => {
+                format!("{};TZID=\"{}\":{}", name, tzid, self)
+            }
+            Zone::Tzid(tzid) => format!("{};TZID={}:{}", name, tzid, self),
+            _ => format!("{}:{}", name, self),
+        }
+    }
+
+    fn key(&self) -> (u16, u8, u8, u8, u8, u8) {
+        (
+            self.year,
+            self.month,
+            self.day,
+            self.hour,
+            self.minute,
+            self.second,
+        )
+    }
+}
+
+impl Display for DateTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:04}{:02}{:02}T{:02}{:02}{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )?;
+        if self.zone == Zone::Utc {
+            write!(f, "Z")?;
+        }
+        Ok(())
+    }
+}
+
+/// Calendar event payload, a minimal RFC 5545 `VEVENT` block.
+///
+/// ```
+/// use qrcode::event::DateTime;
+/// use qrcode::Event;
+///
+/// let start = DateTime::new(2026, 10, 16, 18, 30, 0).utc();
+/// let stamp = DateTime::new(2026, 10, 1, 8, 0, 0).utc();
+/// let event = Event::new("Rust meetup", start, stamp)
+///     .uid("meetup-2026-10@example.com")
+///     .location("Room 4, Main building");
+/// assert_eq!(
+///     event.data().unwrap(),
+///     "BEGIN:VEVENT\r\n\
+///      UID:meetup-2026-10@example.com\r\n\
+///      DTSTAMP:20261001T080000Z\r\n\
+///      SUMMARY:Rust meetup\r\n\
+///      DTSTART:20261016T183000Z\r\n\
+///      LOCATION:Room 4\\, Main building\r\n\
+///      END:VEVENT\r\n"
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Event {
+    uid: Option<String>,
+    stamp: DateTime,
+    summary: String,
+    start: DateTime,
+    end: Option<DateTime>,
+    location: Option<String>,
+    description: Option<String>,
+    url: Option<String>,
+}
+
+impl Event {
+    /// Event created at `stamp`, a UTC date and time written as its `DTSTAMP`.
+    pub fn new(summary: &str, start: DateTime, stamp: DateTime) -> Event {
+        Event {
+            uid: None,
+            stamp,
+            summary: summary.to_string(),
+            start,
+            end: None,
+            location: None,
+            description: None,
+            url: None,
+        }
+    }
+
+    /// Unique identifier. By default, one is derived from the stamp, the summary and the start.
+    pub fn uid(mut self, uid: &str) -> Self {
+        self.uid = Some(uid.to_string());
+        self
+    }
+
+    pub fn end(mut self, end: DateTime) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    pub fn location(mut self, location: &str) -> Self {
+        self.location = Some(location.to_string());
+        self
+    }
+
+    /// Description, where line breaks are kept.
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    pub fn url(mut self, url: &str) -> Self {
+        self.url = Some(url.to_string());
+        self
+    }
+
+    /// Payload, with lines ended by CRLF and folded at 75 bytes.
+    pub fn data(&self) -> Result<String, QrError> {
+        self.check()?;
+
+        let uid = self.uid.clone().unwrap_or_else(|| self.generated_uid());
+        let mut lines = vec![
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}", escape_text(&uid)),
+            self.stamp.property("DTSTAMP"),
+            format!("SUMMARY:{}", escape_text(&self.summary)),
+            self.start.property("DTSTART"),
+        ];
+        if let Some(end) = &self.end {
+            lines.push(end.property("DTEND"));
+        }
+        if let Some(location) = &self.location {
+            lines.push(format!("LOCATION:{}", escape_text(location)));
+        }
+        if let Some(description) = &self.description {
+            let description = description.replace("\r\n", "\n");
+            lines.push(format!("DESCRIPTION:{}", escape_text(&description)));
+        }
+        if let Some(url) = &self.url {
+            lines.push(format!("URL:{}", url));
+        }
+        lines.push("END:VEVENT".to_string());
+
+        Ok(lines.iter().map(|line| fold(line)).collect())
+    }
+
+    /// Builder of a QR code holding the payload.
+    pub fn builder(&self) -> Result<QrCodeBuilder, QrError> {
+        Ok(QrCodeBuilder::new(&self.data()?))
+    }
+
+    /// Smallest version of the QR code at each ec level, from L to H, or `None` when the event
+    /// does not fit.
+    pub fn size_estimates(&self) -> Result<Vec<(EcLevel, Option<Version>)>, QrError> {
+        let data = self.data()?;
+        EcLevel::ALL
+            .into_iter()
+            .map(|ec_level| {
+                match QrCodeBuilder::new(&data)
+                    .ec_level(ec_level)
+                    .smallest_version()
+                {
+                    Ok(version) => Ok((ec_level, Some(version))),
+                    Err(QrError::DataTooLong { .. }) => Ok((ec_level, None)),
+                    Err(error) => Err(error),
+                }
+            })
+            .collect()
+    }
+
+    /// Stamp followed by the 64 bit FNV-1a hash of the summary and the start.
+    fn generated_uid(&self) -> String {
+        let hash = format!("{}\n{}\n{:?}", self.summary, self.start, self.start.zone)
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            });
+        format!("{}-{:016x}", self.stamp, hash)
+    }
+
+    fn check(&self) -> Result<(), QrError> {
+        if self.summary.is_empty() {
+            return Err(invalid("an event needs a summary".to_string()));
+        }
+        if self.uid.as_ref().is_some_and(|uid| uid.is_empty()) {
+            return Err(invalid("the UID is empty".to_string()));
+        }
+        self.stamp.check()?;
+        if self.stamp.zone != Zone::Utc {
+            return Err(invalid(format!("the stamp {} is not in UTC", self.stamp)));
+        }
+        self.start.check()?;
+
+        if let Some(end) = &self.end {
+            end.check()?;
+            // Times in different zones are not comparable without a time zone database
+            if end.zone == self.start.zone && end.key() < self.start.key() {
+                return Err(invalid(format!(
+                    "the event ends at {}, before it starts at {}",
+                    end, self.start
+                )));
+            }
+        }
+        if matches!(&self.url, Some(url) if url.chars().any(|c| c.is_whitespace())) {
+            return Err(invalid("the URL has white space".to_string()));
+        }
+        Ok(())
+    }
+}
+
+fn invalid(reason: String) -> QrError {
+    QrError::InvalidPayload(reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::decode;
+    fn stamp() -> DateTime {
+        DateTime::new(2026, 5, 1, 12, 0, 0).utc()
+    }
+
+    #[test]
+    fn data_writes_every_property() {
+        let event = Event::new(
+            "RustConf",
+            DateTime::new(2026, 9, 8, 9, 0, 0).tzid("Europe/Zurich"),
+            stamp(),
+        )
+        .end(DateTime::new(2026, 9, 10, 17, 30, 0).tzid("Europe/Zurich"))
+        .location("Kongresshaus; Zürich")
+        .description("Talks, workshops\r\nand more")
+        .url("https://rustconf.example/2026")
+        .uid("rustconf-2026@rustconf.example");
+        assert_eq!(
+            event.data().unwrap(),
+            "BEGIN:VEVENT\r\n\
+             UID:rustconf-2026@rustconf.example\r\n\
+             DTSTAMP:20260501T120000Z\r\n\
+             SUMMARY:RustConf\r\n\
+             DTSTART;TZID=Europe/Zurich:20260908T090000\r\n\
+             DTEND;TZID=Europe/Zurich:20260910T173000\r\n\
+             LOCATION:Kongresshaus\\; Zürich\r\n\
+             DESCRIPTION:Talks\\, workshops\\nand more\r\n\
+             URL:https://rustconf.example/2026\r\n\
+             END:VEVENT\r\n"
+        );
+
+        let start = DateTime::new(2026, 1, 1, 0, 0, 0).tzid("GMT+1; custom");
+        assert_eq!(
+            start.property("DTSTART"),
+            "DTSTART;TZID=\"GMT+1; custom\":20260101T000000"
+        );
+        assert_eq!(
+            DateTime::new(2026, 1, 1, 0, 0, 0).property("DTSTART"),
+            "DTSTART:20260101T000000"
+        );
+    }
+
+    #[test]
+    fn data_generates_a_uid() {
+        let event = Event::new("Meetup", DateTime::new(2026, 6, 1, 18, 0, 0), stamp());
+        let data = event.data().unwrap();
+        let uid = data.lines().nth(1).unwrap();
+        assert!(uid.starts_with("UID:20260501T120000Z-"), "{}", uid);
+        assert_eq!(uid.len(), 4 + 16 + 1 + 16);
+        assert_eq!(event.data().unwrap(), data);
+
+        let other = Event::new("Meetup", DateTime::new(2026, 6, 2, 18, 0, 0), stamp());
+        assert_ne!(other.data().unwrap().lines().nth(1).unwrap(), uid);
+    }
+
+    #[test]
+    fn data_folds_long_lines() {
+        let event = Event::new("Meetup", DateTime::new(2026, 1, 1, 0, 0, 0), stamp())
+            .description(&"a".repeat(100));
+        let data = event.data().unwrap();
+        assert!(data.contains(&format!(
+            "DESCRIPTION:{}\r\n {}\r\n",
+            "a".repeat(63),
+            "a".repeat(37)
+        )));
+    }
+
+    #[test]
+    fn data_checks_the_event() {
+        let start = DateTime::new(2026, 3, 1, 10, 0, 0).utc();
+        let reason = |event: Event| match event.data() {
+            Err(QrError::InvalidPayload(reason)) => reason,
+            result => panic!("{:?}", result),
+        };
+
+        assert_eq!(
+            reason(Event::new("", start.clone(), stamp())),
+            "an event needs a summary"
+        );
+        assert_eq!(
+            reason(Event::new(
+                "Meetup",
+                DateTime::new(2027, 2, 29, 10, 0, 0),
+                stamp()
+            )),
+            "20270229T100000 is not a valid date and time"
+        );
+        assert_eq!(
+            reason(
+                Event::new("Meetup", start.clone(), stamp())
+                    .end(DateTime::new(2026, 2, 28, 10, 0, 0).utc())
+            ),
+            "the event ends at 20260228T100000Z, before it starts at 20260301T100000Z"
+        );
+        assert_eq!(
+            reason(Event::new(
+                "Meetup",
+                start.clone().tzid("Bad\"Zone"),
+                stamp()
+            )),
+            "\"Bad\\\"Zone\" is not a valid time zone identifier"
+        );
+        assert_eq!(
+            reason(Event::new("Meetup", start.clone(), stamp()).url("https://a b")),
+            "the URL has white space"
+        );
+        assert_eq!(
+            reason(Event::new(
+                "Meetup",
+                DateTime::new(10000, 1, 1, 0, 0, 0),
+                stamp()
+            )),
+            "100000101T000000 is not a valid date and time"
+        );
+        assert_eq!(
+            reason(Event::new("Meetup", start.clone(), stamp()).uid("")),
+            "the UID is empty"
+        );
+        assert_eq!(
+            reason(Event::new(
+                "Meetup",
+                start.clone(),
+                DateTime::new(2026, 1, 1, 0, 0, 0)
+            )),
+            "the stamp 20260101T000000 is not in UTC"
+        );
+
+        // Leap years, and ends in another zone
+        let event = Event::new(
+            "Meetup",
+            DateTime::new(2024, 2, 29, 10, 0, 0).tzid("Asia/Tokyo"),
+            stamp(),
+        )
+        .end(DateTime::new(2024, 2, 29, 2, 0, 0).utc());
+        assert!(event.data().is_ok());
+    }
+
+    #[test]
+    fn size_estimates_grow_with_the_ec_level() {
+        let event = Event::new(
+            "RustConf",
+            DateTime::new(2026, 9, 8, 9, 0, 0).utc(),
+            stamp(),
+        )
+        .location("Kongresshaus, Zürich");
+        let estimates = event.size_estimates().unwrap();
+
+        assert_eq!(
+            estimates,
+            vec![
+                (EcLevel::L, Some(Version::Normal(7))),
+                (EcLevel::M, Some(Version::Normal(9))),
+                (EcLevel::Q, Some(Version::Normal(11))),
+                (EcLevel::H, Some(Version::Normal(13))),
+            ]
+        );
+        let qrcode = event
+            .builder()
+            .unwrap()
+            .ec_level(EcLevel::Q)
+            .build()
+            .unwrap();
+        assert_eq!(qrcode.version(), Version::Normal(11));
+        assert_eq!(
+            decode(&qrcode).unwrap().text().unwrap(),
+            event.data().unwrap()
+        );
+
+        let event = event.description(&"x".repeat(3000));
+        assert_eq!(event.size_estimates().unwrap()[3..], [(EcLevel::H, None)]);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::bit::Bit;
+use crate::encoding::Encoding;
+use crate::error::QrError;
+use crate::segment::Segment;
+use crate::version::Version;
+
+/// Group separator ending the variable length fields of GS1 data.
+pub const GS: char = '\u{1D}';
+
+/// FNC1 mode, announcing data formatted following the GS1 or an industry specification.
+///
+/// In alphanumeric mode, `%` stands for the [`GS`] separator and a literal `%` is doubled.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fnc1 {
+    /// GS1 data, with the 0101 mode indicator.
+    First,
+    /// Data of the industry application identified by the 8 bit application indicator, with the
+    /// 1001 mode indicator. See [`Fnc1::second`].
+    Second(u8),
+}
+
+impl Fnc1 {
+    /// Second position FNC1 with an application indicator of two digits, `"00"` to `"99"`, or
+    /// a single ASCII letter, written as the letter code plus 100.
+    pub fn second(indicator: &str) -> Option<Fnc1> {
+        let value = match indicator.as_bytes() {
+            [letter] if letter.is_ascii_alphabetic() => letter + 100,
+            [tens, units] if tens.is_ascii_digit() && units.is_ascii_digit() => {
+                (tens - b'0') * 10 + units - b'0'
+            }
+            _ => return None,
+        };
+        Some(Fnc1::Second(value))
+    }
+
+    /// Application indicator of second position FNC1, as given to [`Fnc1::second`].
+    pub fn application_indicator(&self) -> Option<String> {
+        match self {
+            Fnc1::First => None,
+            Fnc1::Second(value @ 0..=99) => Some(format!("{:02}", value)),
+            Fnc1::Second(value) => Some(((value - 100) as char).to_string()),
+        }
+    }
+
+    /// Mode indicator, followed by the application indicator in second position. rMQR codes use
+    /// 3 bit mode indicators, and Micro QR codes have no FNC1.
+    pub fn header(&self, version: Version) -> Result<Vec<Bit>, QrError> {
+        let (mode, mode_bits) = match (self, version) {
+            (_, Version::Micro(_)) => {
+                return Err(QrError::Unsupported(format!(
+                    "FNC1 is not available in version {}",
+                    version
+                )))
+            }
+            (Fnc1::First, Version::Rmqr(..)) => (0b101, 3),
+            (Fnc1::First, Version::Normal(_)) => (0b0101, 4),
+            (Fnc1::Second(_), Version::Rmqr(..)) => (0b110, 3),
+            (Fnc1::Second(_), Version::Normal(_)) => (0b1001, 4),
+        };
+
+        let mut bits = Bit::from(mode, mode_bits, false, true);
+        if let Fnc1::Second(value) = self {
+            bits.append(&mut Bit::from(*value as u32, 8, false, true));
+        }
+        Ok(bits)
+    }
+}
+
+/// Alphanumeric mode form of FNC1 data: `%` for each separator and `%%` for each `%`.
+pub fn escape_alphanumeric(data: &str) -> String {
+    data.chars()
+        .map(|c| match c {
+            GS => "%".to_string(),
+            '%' => "%%".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// FNC1 data of an alphanumeric segment: `%%` back to `%` and a single `%` to the separator.
+pub fn unescape_alphanumeric(data: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(data.len());
+    let mut iter = data.iter().copied().peekable();
+    while let Some(byte) = iter.next() {
+        match byte {
+            b'%' if iter.next_if_eq(&b'%').is_some() => bytes.push(b'%'),
+            b'%' => bytes.push(GS as u8),
+            byte => bytes.push(byte),
+        }
+    }
+    bytes
+}
+
+/// Error for FNC1 data given a charset, since it always takes the default one.
+pub(crate) fn fnc1_charset() -> QrError {
+    QrError::Unsupported("FNC1 data is only encoded in the default charset".to_string())
+}
+
+/// Payload bytes of `segment`. With `fnc1`, the separators of an alphanumeric segment are back
+/// to [`GS`].
+pub fn payload_bytes(segment: &Segment, fnc1: bool) -> Vec<u8> {
+    match fnc1 && segment.encoding == Encoding::Alphanumeric {
+        true => unescape_alphanumeric(&segment.data),
+        false => segment.data.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_reads_digits_and_letters() {
+        assert_eq!(Fnc1::second("37"), Some(Fnc1::Second(37)));
+        assert_eq!(Fnc1::second("a"), Some(Fnc1::Second(197)));
+        assert_eq!(Fnc1::second("Z"), Some(Fnc1::Second(190)));
+        assert_eq!(Fnc1::second("100"), None);
+        assert_eq!(Fnc1::second("%"), None);
+
+        assert_eq!(Fnc1::Second(5).application_indicator().unwrap(), "05");
+        assert_eq!(Fnc1::Second(197).application_indicator().unwrap(), "a");
+        assert_eq!(Fnc1::First.application_indicator(), None);
+    }
+
+    #[test]
+    fn header_depends_on_the_version() {
+        let bits = |fnc1: Fnc1, version| -> String {
+            fnc1.header(version)
+                .unwrap()
+                .iter()
+                .map(|bit| if bit.value() { '1' } else { '0' })
+                .collect()
+        };
+
+        assert_eq!(bits(Fnc1::First, Version::Normal(1)), "0101");
+        assert_eq!(bits(Fnc1::First, Version::Rmqr(7, 43)), "101");
+        assert_eq!(bits(Fnc1::Second(37), Version::Normal(1)), "100100100101");
+        assert_eq!(bits(Fnc1::Second(37), Version::Rmqr(7, 43)), "11000100101");
+        assert!(matches!(
+            Fnc1::First.header(Version::Micro(4)),
+            Err(QrError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn escape_alphanumeric_round_trips() {
+        let data = "10ABC\u{1D}2150%";
+        assert_eq!(escape_alphanumeric(data), "10ABC%2150%%");
+        assert_eq!(
+            unescape_alphanumeric(escape_alphanumeric(data).as_bytes()),
+            data.as_bytes()
+        );
+        assert_eq!(unescape_alphanumeric(b"%%%"), b"%\x1D");
+    }
+}
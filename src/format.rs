@@ -1,7 +1,7 @@
 use crate::bit::Bit;
 
 impl Bit {
-    pub fn bytes(data: &Vec<Bit>) -> Vec<u8> {
+    pub fn bytes(data: &[Bit]) -> Vec<u8> {
         data.chunks(8)
             .map(|chunk| {
                 let mut byte = 0u8;
@@ -13,9 +13,9 @@ impl Bit {
             .collect()
     }
 
-    pub fn bits(data: &Vec<u8>, size: usize) -> Vec<Bit> {
+    pub fn bits(data: &[u8], size: usize) -> Vec<Bit> {
         data.iter()
-            .map(|byte| {
+            .flat_map(|byte| {
                 (0..8)
                     .map(|i| {
                         let bit = (byte >> (7 - i)) & 1u8;
@@ -27,7 +27,6 @@ impl Bit {
                     })
                     .collect::<Vec<Bit>>()
             })
-            .flatten()
             .take(size)
             .collect()
     }
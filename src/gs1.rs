@@ -0,0 +1,384 @@
+use crate::builder::QrCodeBuilder;
+use crate::error::QrError;
+use crate::fnc1::{Fnc1, GS};
+use std::fmt;
+use std::fmt::Formatter;
+
+/// Characters allowed in the data of an application identifier.
+#[derive(Clone, Copy)]
+enum Chars {
+    Digits,
+    /// GS1 AI encodable character set 82.
+    Cset82,
+}
+
+/// Extra check on the data of an application identifier.
+#[derive(Clone, Copy)]
+enum Rule {
+    Plain,
+    /// The last digit is the GS1 mod 10 check digit of the others.
+    CheckDigit,
+    /// YYMMDD date, the day being 00 for the end of the month.
+    Date,
+}
+
+/// Application identifiers with their characters and their minimum and maximum data lengths,
+/// following the GS1 General Specifications.
+const APPLICATION_IDENTIFIERS: [(&str, Chars, usize, usize, Rule); 49] = [
+    ("00", Chars::Digits, 18, 18, Rule::CheckDigit),
+    ("01", Chars::Digits, 14, 14, Rule::CheckDigit),
+    ("02", Chars::Digits, 14, 14, Rule::CheckDigit),
+    ("10", Chars::Cset82, 1, 20, Rule::Plain),
+    ("11", Chars::Digits, 6, 6, Rule::Date),
+    ("12", Chars::Digits, 6, 6, Rule::Date),
+    ("13", Chars::Digits, 6, 6, Rule::Date),
+    ("15", Chars::Digits, 6, 6, Rule::Date),
+    ("16", Chars::Digits, 6, 6, Rule::Date),
+    ("17", Chars::Digits, 6, 6, Rule::Date),
+    ("20", Chars::Digits, 2, 2, Rule::Plain),
+    ("21", Chars::Cset82, 1, 20, Rule::Plain),
+    ("22", Chars::Cset82, 1, 20, Rule::Plain),
+    ("240", Chars::Cset82, 1, 30, Rule::Plain),
+    ("241", Chars::Cset82, 1, 30, Rule::Plain),
+    ("250", Chars::Cset82, 1, 30, Rule::Plain),
+    ("30", Chars::Digits, 1, 8, Rule::Plain),
+    ("3100", Chars::Digits, 6, 6, Rule::Plain),
+    ("3101", Chars::Digits, 6, 6, Rule::Plain),
+    ("3102", Chars::Digits, 6, 6, Rule::Plain),
+    ("3103", Chars::Digits, 6, 6, Rule::Plain),
+    ("3104", Chars::Digits, 6, 6, Rule::Plain),
+    ("3105", Chars::Digits, 6, 6, Rule::Plain),
+    ("37", Chars::Digits, 1, 8, Rule::Plain),
+    ("400", Chars::Cset82, 1, 30, Rule::Plain),
+    ("401", Chars::Cset82, 1, 30, Rule::Plain),
+    ("403", Chars::Cset82, 1, 30, Rule::Plain),
+    ("410", Chars::Digits, 13, 13, Rule::CheckDigit),
+    ("411", Chars::Digits, 13, 13, Rule::CheckDigit),
+    ("412", Chars::Digits, 13, 13, Rule::CheckDigit),
+    ("413", Chars::Digits, 13, 13, Rule::CheckDigit),
+    ("414", Chars::Digits, 13, 13, Rule::CheckDigit),
+    ("415", Chars::Digits, 13, 13, Rule::CheckDigit),
+    ("420", Chars::Cset82, 1, 20, Rule::Plain),
+    ("422", Chars::Digits, 3, 3, Rule::Plain),
+    ("424", Chars::Digits, 3, 3, Rule::Plain),
+    ("426", Chars::Digits, 3, 3, Rule::Plain),
+    ("7003", Chars::Digits, 10, 10, Rule::Plain),
+    ("8020", Chars::Cset82, 1, 25, Rule::Plain),
+    ("90", Chars::Cset82, 1, 30, Rule::Plain),
+    ("91", Chars::Cset82, 1, 90, Rule::Plain),
+    ("92", Chars::Cset82, 1, 90, Rule::Plain),
+    ("93", Chars::Cset82, 1, 90, Rule::Plain),
+    ("94", Chars::Cset82, 1, 90, Rule::Plain),
+    ("95", Chars::Cset82, 1, 90, Rule::Plain),
+    ("96", Chars::Cset82, 1, 90, Rule::Plain),
+    ("97", Chars::Cset82, 1, 90, Rule::Plain),
+    ("98", Chars::Cset82, 1, 90, Rule::Plain),
+    ("99", Chars::Cset82, 1, 90, Rule::Plain),
+];
+
+/// First two digits of the application identifiers whose element string has a predefined
+/// length, and so needs no separator. Other fixed length ones, such as (422), still do.
+const PREDEFINED_LENGTH: [&str; 22] = [
+    "00", "01", "02", "03", "04", "11", "12", "13", "14", "15", "16", "17", "18", "19", "20", "31",
+    "32", "33", "34", "35", "36", "41",
+];
+
+const CSET_82: &str = "!\"%&'()*+,-./0123456789:;<=>?ABCDEFGHIJKLMNOPQRSTUVWXYZ_\
+                       abcdefghijklmnopqrstuvwxyz";
+
+/// GS1 element string: application identifiers (AIs) and their data, encoded after an FNC1
+/// first position mode indicator. The elements are checked when the data is built.
+///
+/// ```
+/// use qrcode::decoder::decode;
+/// use qrcode::Gs1;
+///
+/// let gs1 = Gs1::new()
+///     .gtin("9501101020917")
+///     .expiry("190508")
+///     .batch("ABCD1234")
+///     .serial("10");
+/// assert_eq!(gs1.to_string(), "(01)09501101020917(17)190508(10)ABCD1234(21)10");
+///
+/// let qrcode = gs1.builder().unwrap().build().unwrap();
+/// assert_eq!(decode(&qrcode).unwrap().text().unwrap(), gs1.data().unwrap());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Gs1 {
+    elements: Vec<(String, String)>,
+}
+
+impl Gs1 {
+    pub fn new() -> Gs1 {
+        Gs1::default()
+    }
+
+    /// GTIN (01). GTIN-8, GTIN-12 and GTIN-13 are padded to 14 digits with zeros.
+    pub fn gtin(self, gtin: &str) -> Self {
+        match gtin.len() {
+            8 | 12 | 13 => self.element("01", &format!("{:0>14}", gtin)),
+            _ => self.element("01", gtin),
+        }
+    }
+
+    /// Serial Shipping Container Code (00), 18 digits.
+    pub fn sscc(self, sscc: &str) -> Self {
+        self.element("00", sscc)
+    }
+
+    /// Batch or lot number (10), up to 20 characters.
+    pub fn batch(self, batch: &str) -> Self {
+        self.element("10", batch)
+    }
+
+    /// Production date (11), as YYMMDD.
+    pub fn production_date(self, date: &str) -> Self {
+        self.element("11", date)
+    }
+
+    /// Best before date (15), as YYMMDD.
+    pub fn best_before(self, date: &str) -> Self {
+        self.element("15", date)
+    }
+
+    /// Expiration date (17), as YYMMDD.
+    pub fn expiry(self, date: &str) -> Self {
+        self.element("17", date)
+    }
+
+    /// Serial number (21), up to 20 characters.
+    pub fn serial(self, serial: &str) -> Self {
+        self.element("21", serial)
+    }
+
+    /// Count of trade items (37).
+    pub fn count(self, count: u32) -> Self {
+        self.element("37", &count.to_string())
+    }
+
+    /// Any element of the table, such as `("3102", "001250")` for a net weight of 12.50 kg.
+    pub fn element(mut self, ai: &str, data: &str) -> Self {
+        self.elements.push((ai.to_string(), data.to_string()));
+        self
+    }
+
+    /// Element string to encode: the AIs and their data in order, with a [`GS`] separator after
+    /// each element whose length is not predefined, except the last one.
+    pub fn data(&self) -> Result<String, QrError> {
+        if self.elements.is_empty() {
+            return Err(QrError::InvalidPayload(
+                "a GS1 element string needs at least one element".to_string(),
+            ));
+        }
+
+        let mut data = String::new();
+        for (index, (ai, value)) in self.elements.iter().enumerate() {
+            check_element(ai, value)?;
+            if self.elements[..index].iter().any(|(other, _)| other == ai) {
+                return Err(QrError::InvalidPayload(format!("({}) is repeated", ai)));
+            }
+
+            data.push_str(ai);
+            data.push_str(value);
+            let predefined = PREDEFINED_LENGTH
+                .iter()
+                .any(|prefix| ai.starts_with(prefix));
+            if !predefined && index + 1 < self.elements.len() {
+                data.push(GS);
+            }
+        }
+        Ok(data)
+    }
+
+    /// Builder of a QR code holding the element string in FNC1 mode, split into the shortest mix
+    /// of modes.
+    pub fn builder(&self) -> Result<QrCodeBuilder, QrError> {
+        Ok(QrCodeBuilder::new(&self.data()?).fnc1(Fnc1::First))
+    }
+}
+
+/// Human readable interpretation, with the AIs in parentheses.
+impl fmt::Display for Gs1 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (ai, value) in &self.elements {
+            write!(f, "({}){}", ai, value)?;
+        }
+        Ok(())
+    }
+}
+
+fn check_element(ai: &str, value: &str) -> Result<(), QrError> {
+    let invalid = |reason: String| Err(QrError::InvalidPayload(format!("({}) {}", ai, reason)));
+
+    let Some(&(_, chars, min, max, rule)) = APPLICATION_IDENTIFIERS
+        .iter()
+        .find(|(identifier, ..)| *identifier == ai)
+    else {
+        return Err(QrError::InvalidPayload(format!(
+            "unknown application identifier ({})",
+            ai
+        )));
+    };
+
+    let length = value.chars().count();
+    if length < min || length > max {
+        return match min == max {
+            true => invalid(format!("needs {} characters, not {}", min, length)),
+            false => invalid(format!(
+                "needs {} to {} characters, not {}",
+                min, max, length
+            )),
+        };
+    }
+
+    let allowed = |c: char| match chars {
+        Chars::Digits => c.is_ascii_digit(),
+        Chars::Cset82 => CSET_82.contains(c),
+    };
+    if let Some(c) = value.chars().find(|c| !allowed(*c)) {
+        return invalid(format!("cannot hold {:?}", c));
+    }
+
+    match rule {
+        Rule::Plain => Ok(()),
+        Rule::CheckDigit => {
+            let expected = check_digit(&value[..length - 1]);
+            match value.ends_with(expected) {
+                true => Ok(()),
+                false => invalid(format!("has a wrong check digit, expected {}", expected)),
+            }
+        }
+        Rule::Date => {
+            let number = |i: usize| value[i..i + 2].parse::<u8>().unwrap_or(u8::MAX);
+            let (year, month, day) = (number(0), number(2), number(4));
+            let days = days_in_month(year, month);
+            match (1..=12).contains(&month) && day <= days {
+                true => Ok(()),
+                false => invalid(format!("is not a YYMMDD date: {}", value)),
+            }
+        }
+    }
+}
+
+/// Number of days in `month` of the two digit `year`, 31 for an unknown month. Every fourth
+/// year is a leap year, as it is from 1901 to 2099.
+fn days_in_month(year: u8, month: u8) -> u8 {
+    match month {
+        2 if year.is_multiple_of(4) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// GS1 mod 10 check digit: the digits weigh 3 and 1 in turn from the right.
+fn check_digit(digits: &str) -> char {
+    let sum: u32 = digits
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(position, digit)| (digit - b'0') as u32 * if position % 2 == 0 { 3 } else { 1 })
+        .sum();
+    char::from(b'0' + ((10 - sum % 10) % 10) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::decode;
+    use crate::encoding::Encoding;
+
+    #[test]
+    fn check_digit_follows_gs1_weights() {
+        assert_eq!(check_digit("0950110102091"), '7');
+        assert_eq!(check_digit("00614141123452"), '4');
+        assert_eq!(check_digit("0000000000000"), '0');
+    }
+
+    #[test]
+    fn data_separates_variable_length_elements_only() {
+        let gs1 = Gs1::new()
+            .gtin("09501101020917")
+            .batch("AB-12")
+            .expiry("251231")
+            .element("422", "250")
+            .serial("XYZ");
+        assert_eq!(
+            gs1.data().unwrap(),
+            "0109501101020917\
+             10AB-12\u{1D}\
+             17251231\
+             422250\u{1D}\
+             21XYZ"
+        );
+    }
+
+    #[test]
+    fn data_rejects_invalid_elements() {
+        let reason = |gs1: Gs1| match gs1.data() {
+            Err(QrError::InvalidPayload(reason)) => reason,
+            result => panic!("{:?}", result),
+        };
+
+        assert_eq!(
+            reason(Gs1::new().gtin("09501101020918")),
+            "(01) has a wrong check digit, expected 7"
+        );
+        assert_eq!(
+            reason(Gs1::new().gtin("950110102091")),
+            "(01) has a wrong check digit, expected 8"
+        );
+        assert_eq!(
+            reason(Gs1::new().gtin("123")),
+            "(01) needs 14 characters, not 3"
+        );
+        assert_eq!(
+            reason(Gs1::new().batch(&"A".repeat(21))),
+            "(10) needs 1 to 20 characters, not 21"
+        );
+        assert_eq!(reason(Gs1::new().serial("A B")), "(21) cannot hold ' '");
+        assert_eq!(
+            reason(Gs1::new().expiry("251301")),
+            "(17) is not a YYMMDD date: 251301"
+        );
+        assert_eq!(
+            reason(Gs1::new().expiry("250231")),
+            "(17) is not a YYMMDD date: 250231"
+        );
+        assert_eq!(
+            reason(Gs1::new().best_before("250229")),
+            "(15) is not a YYMMDD date: 250229"
+        );
+        assert!(Gs1::new().best_before("240229").data().is_ok());
+        assert!(Gs1::new().expiry("250200").data().is_ok());
+        assert!(Gs1::new().expiry("250430").data().is_ok());
+        assert_eq!(
+            reason(Gs1::new().element("19", "1")),
+            "unknown application identifier (19)"
+        );
+        assert_eq!(
+            reason(Gs1::new().serial("1").serial("2")),
+            "(21) is repeated"
+        );
+        assert_eq!(
+            reason(Gs1::new()),
+            "a GS1 element string needs at least one element"
+        );
+    }
+
+    #[test]
+    fn builder_encodes_in_fnc1_mode() {
+        let gs1 = Gs1::new()
+            .gtin("9501101020917")
+            .expiry("190508")
+            .batch("ABCD1234")
+            .serial("10");
+        let qrcode = gs1.builder().unwrap().build().unwrap();
+
+        let decoded = decode(&qrcode).unwrap();
+        assert_eq!(decoded.fnc1, Some(Fnc1::First));
+        assert_eq!(decoded.text().unwrap(), gs1.data().unwrap());
+        // The digit runs go to numeric mode
+        assert_eq!(decoded.segments[0].encoding, Encoding::Numeric);
+    }
+}
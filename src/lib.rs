@@ -0,0 +1,47 @@
+//! QR code generation, rendering and decoding.
+
+mod builder;
+mod format;
+mod tables;
+
+pub mod bit;
+pub mod color;
+pub mod contact;
+pub mod decoder;
+pub mod ec;
+pub mod eci;
+pub mod encoding;
+pub mod epc;
+pub mod error;
+pub mod event;
+pub mod fnc1;
+pub mod gs1;
+pub mod mask;
+pub mod png;
+pub mod preprocessor;
+pub mod qr_bill;
+pub mod qrcode;
+pub mod segment;
+pub mod structured_append;
+pub mod svg;
+pub mod terminal;
+pub mod version;
+pub mod wifi;
+
+pub use builder::QrCodeBuilder;
+pub use color::Color;
+pub use contact::Contact;
+pub use ec::EcLevel;
+pub use eci::Charset;
+pub use encoding::Encoding;
+pub use epc::Epc;
+pub use error::QrError;
+pub use event::Event;
+pub use fnc1::Fnc1;
+pub use gs1::Gs1;
+pub use mask::{MaskPattern, MaskSelection};
+pub use qr_bill::QrBill;
+pub use qrcode::QrCode;
+pub use structured_append::StructuredAppend;
+pub use version::Version;
+pub use wifi::Wifi;
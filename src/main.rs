@@ -1,22 +1,410 @@
-use crate::ec::*;
-use crate::encoding::Encoding;
-use crate::mask::MaskPattern;
-use crate::preprocessor::Preprocessor;
-
-mod bit;
-mod debug_utils;
-mod ec;
-mod encoding;
-mod format;
-mod mask;
-mod preprocessor;
-mod qrcode;
-mod tables;
-
-fn main() {
-    let data = "https://example.com https://example.com https://example.com";
-    let preprocessor = Preprocessor::new(data, Encoding::Byte, EcLevel::H, MaskPattern::Diagonal);
-    let qrcode = preprocessor.generate_qrcode();
-
-    println!("{}", qrcode);
+use clap::{Parser, ValueEnum};
+use qrcode::{
+    Charset, EcLevel, Encoding, MaskPattern, MaskSelection, QrCode, QrCodeBuilder, QrError, Version,
+};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Exit code for invalid command-line arguments, as used by clap.
+const EXIT_USAGE: u8 = 2;
+/// Exit code when the data does not fit in the allowed versions.
+const EXIT_DATA_TOO_LONG: u8 = 3;
+/// Exit code when the data has a character the encoding cannot represent.
+const EXIT_INVALID_CHARACTER: u8 = 4;
+
+/// Generates a QR code.
+#[derive(Parser, Debug)]
+#[command(
+    version,
+    after_help = "Exit codes: 0 success, 1 I/O or rendering error, 2 invalid arguments, \
+                  3 data too long, 4 invalid character"
+)]
+struct Cli {
+    /// Data to encode. Read from stdin when missing or `-`.
+    data: Option<String>,
+
+    /// Read the data from a file.
+    #[arg(short, long, value_name = "FILE", conflicts_with = "data")]
+    input: Option<PathBuf>,
+
+    /// Encode the data as raw bytes. Implied when it is not valid UTF-8.
+    #[arg(short, long)]
+    binary: bool,
+
+    /// Error correction level: L, M, Q or H.
+    #[arg(short, long, default_value = "M", value_parser = parse_ec_level)]
+    ec_level: EcLevel,
+
+    /// Raise the ec level as far as the data fits without a larger version.
+    #[arg(long)]
+    boost_ec: bool,
+
+    /// Encoding mode, or auto for the shortest mix of modes.
+    #[arg(long, default_value = "auto")]
+    encoding: EncodingArg,
+
+    /// Character set of byte mode data, such as UTF-8, ISO-8859-5 or Shift_JIS. By default
+    /// ISO-8859-1, or UTF-8 when the data needs it.
+    #[arg(short, long, value_parser = parse_charset)]
+    charset: Option<Charset>,
+
+    /// Mask pattern from 0 to 7, or auto for the lowest penalty.
+    #[arg(short, long, default_value = "auto", value_parser = parse_mask)]
+    mask: MaskSelection,
+
+    /// Exact version to use, for a fixed symbol size: 1 to 40, M1 to M4 for Micro QR, or
+    /// R7x43 to R17x139 for rMQR.
+    #[arg(
+        long,
+        value_name = "VERSION",
+        conflicts_with_all = ["min_version", "max_version", "micro", "rmqr"],
+        value_parser = parse_version
+    )]
+    fixed_version: Option<Version>,
+
+    /// Allow the smaller Micro QR symbols M1 to M4.
+    #[arg(long)]
+    micro: bool,
+
+    /// Use a rectangular Micro QR symbol (rMQR) with the lowest height holding the data.
+    #[arg(long, conflicts_with_all = ["micro", "min_version", "max_version"])]
+    rmqr: bool,
+
+    /// Smallest version to use.
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..=40))]
+    min_version: u8,
+
+    /// Largest version to use.
+    #[arg(long, default_value_t = 40, value_parser = clap::value_parser!(u8).range(1..=40))]
+    max_version: u8,
+
+    /// Width of the quiet zone in modules. 4 by default, 2 for Micro QR and rMQR.
+    #[arg(short, long)]
+    quiet_zone: Option<u32>,
+
+    /// Size of a module in pixels for SVG and PNG output.
+    #[arg(long, default_value_t = 8)]
+    module_size: u32,
+
+    /// Output format. Guessed from the extension of the output file by default.
+    #[arg(short, long)]
+    format: Option<Format>,
+
+    /// Write to a file instead of stdout.
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum EncodingArg {
+    Auto,
+    Numeric,
+    Alphanumeric,
+    Byte,
+    Kanji,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum Format {
+    Terminal,
+    Svg,
+    Png,
+}
+
+fn parse_ec_level(value: &str) -> Result<EcLevel, String> {
+    match value.to_ascii_uppercase().as_str() {
+        "L" => Ok(EcLevel::L),
+        "M" => Ok(EcLevel::M),
+        "Q" => Ok(EcLevel::Q),
+        "H" => Ok(EcLevel::H),
+        _ => Err("expected L, M, Q or H".to_string()),
+    }
+}
+
+fn parse_charset(value: &str) -> Result<Charset, String> {
+    Charset::from_name(value).ok_or_else(|| {
+        let names: Vec<&str> = Charset::ALL.iter().map(Charset::name).collect();
+        format!("expected one of {}", names.join(", "))
+    })
+}
+
+fn parse_version(value: &str) -> Result<Version, String> {
+    let version = if let Some(number) = value.strip_prefix(['M', 'm']) {
+        number.parse().ok().map(Version::Micro)
+    } else if let Some(size) = value.strip_prefix(['R', 'r']) {
+        size.split_once(['x', 'X']).and_then(|(height, width)| {
+            Some(Version::Rmqr(height.parse().ok()?, width.parse().ok()?))
+        })
+    } else {
+        value.parse().ok().map(Version::Normal)
+    };
+
+    version
+        .filter(|version| version.check().is_ok())
+        .ok_or("expected 1 to 40, M1 to M4 or an rMQR size from R7x43 to R17x139".to_string())
+}
+
+fn parse_mask(value: &str) -> Result<MaskSelection, String> {
+    if value.eq_ignore_ascii_case("auto") {
+        return Ok(MaskSelection::Auto);
+    }
+
+    value
+        .parse::<usize>()
+        .ok()
+        .and_then(|index| MaskPattern::ALL.get(index))
+        .map(|pattern| MaskSelection::Fixed(*pattern))
+        .ok_or("expected auto or a number from 0 to 7".to_string())
+}
+
+impl Cli {
+    fn read_data(&self) -> io::Result<Vec<u8>> {
+        match (&self.input, self.data.as_deref()) {
+            (Some(path), _) => fs::read(path),
+            (None, Some(data)) if data != "-" => Ok(data.as_bytes().to_vec()),
+            _ => {
+                let mut data = Vec::new();
+                io::stdin().read_to_end(&mut data)?;
+                Ok(data)
+            }
+        }
+    }
+
+    fn format(&self) -> Format {
+        let extension = self
+            .output
+            .as_ref()
+            .and_then(|path| path.extension())
+            .and_then(|extension| extension.to_str());
+
+        match (self.format, extension) {
+            (Some(format), _) => format,
+            (None, Some(extension)) if extension.eq_ignore_ascii_case("svg") => Format::Svg,
+            (None, Some(extension)) if extension.eq_ignore_ascii_case("png") => Format::Png,
+            _ => Format::Terminal,
+        }
+    }
+
+    fn build(&self, data: &[u8]) -> Result<QrCode, QrError> {
+        let builder = match std::str::from_utf8(data) {
+            Ok(text) if !self.binary => QrCodeBuilder::new(text),
+            _ => QrCodeBuilder::binary(data),
+        };
+
+        let builder = builder
+            .ec_level(self.ec_level)
+            .boost_ec(self.boost_ec)
+            .mask(self.mask)
+            .min_version(self.min_version)
+            .max_version(self.max_version)
+            .micro(self.micro)
+            .rmqr(self.rmqr);
+
+        let builder = match self.fixed_version {
+            Some(Version::Normal(number)) => builder.version(number),
+            Some(Version::Micro(number)) => builder.micro_version(number),
+            Some(Version::Rmqr(height, width)) => builder.rmqr_version(height, width),
+            None => builder,
+        };
+
+        let builder = match self.charset {
+            Some(charset) => builder.charset(charset),
+            None => builder,
+        };
+
+        let builder = match self.encoding {
+            EncodingArg::Auto => builder,
+            EncodingArg::Numeric => builder.encoding(Encoding::Numeric),
+            EncodingArg::Alphanumeric => builder.encoding(Encoding::Alphanumeric),
+            EncodingArg::Byte => builder.encoding(Encoding::Byte),
+            EncodingArg::Kanji => builder.encoding(Encoding::Kanji),
+        };
+
+        builder.build()
+    }
+
+    fn render(&self, qrcode: &QrCode) -> Result<Vec<u8>, QrError> {
+        let quiet_zone = self.quiet_zone.unwrap_or(qrcode.version().quiet_zone());
+
+        match self.format() {
+            Format::Terminal => Ok(qrcode
+                .terminal()
+                .quiet_zone(quiet_zone)
+                .render()
+                .into_bytes()),
+            Format::Svg => Ok(qrcode
+                .svg()
+                .quiet_zone(quiet_zone)
+                .module_size(self.module_size)
+                .render()
+                .into_bytes()),
+            Format::Png => qrcode
+                .png()
+                .quiet_zone(quiet_zone)
+                .module_size(self.module_size)
+                .render(),
+        }
+    }
+}
+
+fn exit_code(error: &QrError) -> ExitCode {
+    match error {
+        QrError::DataTooLong { .. } => ExitCode::from(EXIT_DATA_TOO_LONG),
+        QrError::InvalidCharacter { .. } => ExitCode::from(EXIT_INVALID_CHARACTER),
+        QrError::InvalidVersion(_)
+        | QrError::InvalidVersionRange { .. }
+        | QrError::Unsupported(_) => ExitCode::from(EXIT_USAGE),
+        _ => ExitCode::FAILURE,
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let data = match cli.read_data() {
+        Ok(data) => data,
+        Err(error) => {
+            eprintln!("error: cannot read the data: {}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let output = match cli.build(&data).and_then(|qrcode| cli.render(&qrcode)) {
+        Ok(output) => output,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            return exit_code(&error);
+        }
+    };
+
+    let written = match &cli.output {
+        Some(path) => fs::write(path, output),
+        None => io::stdout().write_all(&output),
+    };
+
+    if let Err(error) = written {
+        eprintln!("error: cannot write the output: {}", error);
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cli(args: &[&str]) -> Cli {
+        Cli::try_parse_from([&["qrcode"], args].concat()).unwrap()
+    }
+
+    #[test]
+    fn parse_uses_defaults() {
+        let cli = cli(&["HELLO"]);
+        assert_eq!(cli.ec_level, EcLevel::M);
+        assert_eq!(cli.mask, MaskSelection::Auto);
+        assert_eq!((cli.min_version, cli.max_version), (1, 40));
+        assert_eq!(cli.format(), Format::Terminal);
+    }
+
+    #[test]
+    fn parse_reads_ec_level_and_mask() {
+        let cli = cli(&["-e", "q", "-m", "3", "HELLO"]);
+        assert_eq!(cli.ec_level, EcLevel::Q);
+        assert_eq!(cli.mask, MaskSelection::Fixed(MaskPattern::Diagonal));
+    }
+
+    #[test]
+    fn parse_reads_charset() {
+        assert_eq!(cli(&["-c", "utf-8", "A"]).charset, Some(Charset::Utf8));
+        assert_eq!(
+            cli(&["--charset", "Shift_JIS", "A"]).charset,
+            Some(Charset::ShiftJis)
+        );
+    }
+
+    #[test]
+    fn parse_rejects_bad_values() {
+        assert!(Cli::try_parse_from(["qrcode", "-m", "8", "A"]).is_err());
+        assert!(Cli::try_parse_from(["qrcode", "-e", "X", "A"]).is_err());
+        assert!(Cli::try_parse_from(["qrcode", "--min-version", "41", "A"]).is_err());
+        assert!(Cli::try_parse_from(["qrcode", "-i", "file", "A"]).is_err());
+        assert!(
+            Cli::try_parse_from(["qrcode", "--fixed-version", "3", "--min-version", "2", "A"])
+                .is_err()
+        );
+        assert!(Cli::try_parse_from(["qrcode", "-c", "latin-9", "A"]).is_err());
+        assert!(Cli::try_parse_from(["qrcode", "--fixed-version", "M5", "A"]).is_err());
+        assert!(Cli::try_parse_from(["qrcode", "--fixed-version", "R7x27", "A"]).is_err());
+        assert!(Cli::try_parse_from(["qrcode", "--rmqr", "--micro", "A"]).is_err());
+    }
+
+    #[test]
+    fn format_is_guessed_from_output_extension() {
+        assert_eq!(cli(&["-o", "code.SVG", "A"]).format(), Format::Svg);
+        assert_eq!(cli(&["-o", "code.png", "A"]).format(), Format::Png);
+        assert_eq!(cli(&["-o", "code.txt", "A"]).format(), Format::Terminal);
+        assert_eq!(
+            cli(&["-f", "svg", "-o", "code.png", "A"]).format(),
+            Format::Svg
+        );
+    }
+
+    #[test]
+    fn build_encodes_binary_data_as_is() {
+        let data = [0x00, 0xFF, 0xC3];
+        let qrcode = cli(&["A"]).build(&data).unwrap();
+        assert_eq!(qrcode::decoder::decode(&qrcode).unwrap().bytes(), data);
+
+        let qrcode = cli(&["--binary", "A"]).build("é".as_bytes()).unwrap();
+        assert_eq!(
+            qrcode::decoder::decode(&qrcode).unwrap().bytes(),
+            "é".as_bytes()
+        );
+    }
+
+    #[test]
+    fn errors_map_to_exit_codes() {
+        let too_long = cli(&["--max-version", "1"]).build("A".repeat(100).as_bytes());
+        assert_eq!(
+            exit_code(&too_long.err().unwrap()),
+            ExitCode::from(EXIT_DATA_TOO_LONG)
+        );
+
+        let invalid = cli(&["--encoding", "numeric"]).build(b"12a");
+        assert_eq!(
+            exit_code(&invalid.err().unwrap()),
+            ExitCode::from(EXIT_INVALID_CHARACTER)
+        );
+
+        let range = cli(&["--min-version", "5", "--max-version", "3"]).build(b"A");
+        assert_eq!(exit_code(&range.err().unwrap()), ExitCode::from(EXIT_USAGE));
+    }
+
+    #[test]
+    fn build_applies_selection_policy() {
+        let qrcode = cli(&["--fixed-version", "3", "-e", "L", "--boost-ec", "A"])
+            .build(b"HELLO")
+            .unwrap();
+        assert_eq!(
+            (qrcode.version(), qrcode.ec_level()),
+            (Version::Normal(3), EcLevel::H)
+        );
+
+        let qrcode = cli(&["--fixed-version", "m3", "A"]).build(b"12").unwrap();
+        assert_eq!(qrcode.version(), Version::Micro(3));
+
+        let qrcode = cli(&["--micro", "-e", "L", "A"]).build(b"12").unwrap();
+        assert_eq!(qrcode.version(), Version::Micro(1));
+
+        let qrcode = cli(&["--fixed-version", "R11x27", "A"])
+            .build(b"12")
+            .unwrap();
+        assert_eq!(qrcode.version(), Version::Rmqr(11, 27));
+
+        let qrcode = cli(&["--rmqr", "A"]).build(b"12").unwrap();
+        assert_eq!(qrcode.version(), Version::Rmqr(7, 43));
+    }
 }
@@ -1,4 +1,4 @@
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum MaskPattern {
     Checkerboard,
     Horizontal,
@@ -10,14 +10,46 @@ pub enum MaskPattern {
     Meadow,
 }
 
+/// How the mask of a symbol is chosen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MaskSelection {
+    /// Try every pattern the symbol allows and keep the best one: the eight patterns scored by
+    /// [`penalty`] in regular QR codes, lowest first, and the four patterns scored by
+    /// [`micro_score`] in Micro QR codes, highest first. rMQR codes have a single pattern.
+    Auto,
+    Fixed(MaskPattern),
+}
+
 impl MaskPattern {
+    pub const ALL: [MaskPattern; 8] = [
+        MaskPattern::Checkerboard,
+        MaskPattern::Horizontal,
+        MaskPattern::Vertical,
+        MaskPattern::Diagonal,
+        MaskPattern::LargeCheckerboard,
+        MaskPattern::Fields,
+        MaskPattern::Diamonds,
+        MaskPattern::Meadow,
+    ];
+
+    /// The patterns available in Micro QR codes, indexed by their Micro QR ordinal.
+    pub const MICRO: [MaskPattern; 4] = [
+        MaskPattern::Horizontal,
+        MaskPattern::LargeCheckerboard,
+        MaskPattern::Diamonds,
+        MaskPattern::Meadow,
+    ];
+
+    /// The single pattern of rMQR codes.
+    pub const RMQR: MaskPattern = MaskPattern::LargeCheckerboard;
+
     pub fn get_mask(&self) -> fn(u32, u32) -> bool {
         match self {
             MaskPattern::Checkerboard => |x, y| (x + y) % 2 == 0,
             MaskPattern::Horizontal => |_, y| y % 2 == 0,
             MaskPattern::Vertical => |x, _| x % 3 == 0,
             MaskPattern::Diagonal => |x, y| (x + y) % 3 == 0,
-            MaskPattern::LargeCheckerboard => |x, y| ((x / 2) + (y / 3)) % 2 == 0,
+            MaskPattern::LargeCheckerboard => |x, y| ((y / 2) + (x / 3)) % 2 == 0,
             MaskPattern::Fields => |x, y| ((x * y) % 2) + ((x * y) % 3) == 0,
             MaskPattern::Diamonds => |x, y| (((x * y) % 2) + ((x * y) % 3)) % 2 == 0,
             MaskPattern::Meadow => |x, y| (((x + y) % 2) + ((x * y) % 3)) % 2 == 0,
@@ -36,4 +68,157 @@ impl MaskPattern {
             MaskPattern::Meadow => 7,
         }
     }
+
+    /// Ordinal of the pattern in Micro QR codes, or `None` if they cannot use it.
+    pub fn micro_ordinal(self) -> Option<u8> {
+        Self::MICRO
+            .iter()
+            .position(|pattern| *pattern == self)
+            .map(|ordinal| ordinal as u8)
+    }
+}
+
+/// Penalty score of a square module grid (`true` is dark) following ISO/IEC 18004, 7.8.3.
+pub fn penalty(modules: &[bool], size: usize) -> u32 {
+    let rows: Vec<Vec<bool>> = modules.chunks(size).map(|row| row.to_vec()).collect();
+    let columns: Vec<Vec<bool>> = (0..size)
+        .map(|x| (0..size).map(|y| modules[x + size * y]).collect())
+        .collect();
+
+    let lines = rows.iter().chain(columns.iter());
+    let lines_penalty: u32 = lines
+        .map(|line| run_penalty(line) + finder_like_penalty(line))
+        .sum();
+
+    lines_penalty + block_penalty(modules, size) + balance_penalty(modules)
+}
+
+/// N1: 3 points for each run of 5 same coloured modules, plus 1 per extra module.
+fn run_penalty(line: &[bool]) -> u32 {
+    line.chunk_by(|a, b| a == b)
+        .filter(|run| run.len() >= 5)
+        .map(|run| run.len() as u32 - 2)
+        .sum()
+}
+
+/// N2: 3 points for each 2x2 block of same coloured modules.
+fn block_penalty(modules: &[bool], size: usize) -> u32 {
+    let mut penalty = 0;
+    for y in 0..size - 1 {
+        for x in 0..size - 1 {
+            let color = modules[x + size * y];
+            if modules[x + 1 + size * y] == color
+                && modules[x + size * (y + 1)] == color
+                && modules[x + 1 + size * (y + 1)] == color
+            {
+                penalty += 3;
+            }
+        }
+    }
+    penalty
+}
+
+/// N3: 40 points for each 1:1:3:1:1 dark pattern preceded or followed by 4 light modules.
+/// The quiet zone around the symbol counts as light.
+fn finder_like_penalty(line: &[bool]) -> u32 {
+    const PATTERN: [bool; 11] = [
+        true, false, true, true, true, false, true, false, false, false, false,
+    ];
+
+    let mut padded = vec![false; 4];
+    padded.extend_from_slice(line);
+    padded.extend_from_slice(&[false; 4]);
+
+    padded
+        .windows(11)
+        .filter(|window| window.iter().eq(PATTERN.iter()) || window.iter().eq(PATTERN.iter().rev()))
+        .count() as u32
+        * 40
+}
+
+/// Score of a Micro QR module grid following ISO/IEC 18004, 7.8.3.2, from the dark modules of
+/// the right and bottom edges. Unlike the penalty, the highest score is the best.
+pub fn micro_score(modules: &[bool], size: usize) -> u32 {
+    let right = (1..size).filter(|y| modules[size - 1 + size * y]).count() as u32;
+    let bottom = (1..size).filter(|x| modules[x + size * (size - 1)]).count() as u32;
+
+    right.min(bottom) * 16 + right.max(bottom)
+}
+
+/// N4: 10 points for each 5% the proportion of dark modules deviates from 50%.
+fn balance_penalty(modules: &[bool]) -> u32 {
+    let total = modules.len();
+    let dark = modules.iter().filter(|module| **module).count();
+    ((dark * 20).abs_diff(total * 10) / total) as u32 * 10
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_penalty_counts_runs_of_five_or_more() {
+        assert_eq!(run_penalty(&[true; 4]), 0);
+        assert_eq!(run_penalty(&[true; 5]), 3);
+        assert_eq!(run_penalty(&[false; 7]), 5);
+        assert_eq!(
+            run_penalty(&[true, true, true, true, true, false, false, false, false, false]),
+            6
+        );
+    }
+
+    #[test]
+    fn block_penalty_counts_overlapping_blocks() {
+        assert_eq!(block_penalty(&[true; 9], 3), 12);
+        assert_eq!(block_penalty(&[true, false, false, true], 2), 0);
+    }
+
+    #[test]
+    fn finder_like_penalty_matches_both_directions() {
+        let line = [
+            false, false, false, false, true, false, true, true, true, false, true, false, false,
+            false, false,
+        ];
+        assert_eq!(finder_like_penalty(&line), 80);
+        assert_eq!(finder_like_penalty(&[true; 11]), 0);
+    }
+
+    #[test]
+    fn finder_like_penalty_counts_quiet_zone_as_light() {
+        assert_eq!(
+            finder_like_penalty(&[true, false, true, true, true, false, true]),
+            80
+        );
+    }
+
+    #[test]
+    fn micro_score_favours_dark_edges() {
+        let mut modules = vec![false; 121];
+        assert_eq!(micro_score(&modules, 11), 0);
+
+        // Right edge, which shares its last module with the bottom edge
+        for y in 1..11 {
+            modules[10 + 11 * y] = true;
+        }
+        assert_eq!(micro_score(&modules, 11), 16 + 10);
+
+        // Bottom edge, the corner is already dark
+        for x in 1..10 {
+            modules[x + 11 * 10] = true;
+        }
+        assert_eq!(micro_score(&modules, 11), 10 * 16 + 10);
+        assert_eq!(MaskPattern::Meadow.micro_ordinal(), Some(3));
+        assert_eq!(MaskPattern::Checkerboard.micro_ordinal(), None);
+    }
+
+    #[test]
+    fn balance_penalty_grows_every_five_percent() {
+        let mut modules = vec![false; 100];
+        modules[..50].fill(true);
+        assert_eq!(balance_penalty(&modules), 0);
+        modules[..58].fill(true);
+        assert_eq!(balance_penalty(&modules), 10);
+        modules.fill(true);
+        assert_eq!(balance_penalty(&modules), 100);
+    }
 }
@@ -0,0 +1,368 @@
+use crate::color::Color;
+use crate::error::QrError;
+use crate::qr_bill::swiss_cross;
+use crate::qrcode::QrCode;
+use png::{BitDepth, ColorType, Encoder, PixelDimensions, Unit};
+
+/// Pixel format of the PNG output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PngColor {
+    /// 1 bit per pixel, black on white. The foreground and background colours are ignored.
+    Grayscale,
+    /// 8 bits per channel with the foreground and background colours.
+    Rgba,
+}
+
+/// Renders a [`QrCode`] as a PNG image with an integer number of pixels per module.
+pub struct PngRenderer<'a> {
+    qrcode: &'a QrCode,
+    module_size: u32,
+    quiet_zone: u32,
+    foreground: Color,
+    background: Color,
+    color: PngColor,
+    dpi: Option<u32>,
+    fit: Option<(u32, u32)>,
+    swiss_cross: bool,
+}
+
+impl QrCode {
+    pub fn png(&self) -> PngRenderer<'_> {
+        PngRenderer::new(self)
+    }
+}
+
+impl<'a> PngRenderer<'a> {
+    pub fn new(qrcode: &'a QrCode) -> PngRenderer<'a> {
+        PngRenderer {
+            qrcode,
+            module_size: 8,
+            quiet_zone: qrcode.version().quiet_zone(),
+            foreground: Color::BLACK,
+            background: Color::WHITE,
+            color: PngColor::Grayscale,
+            dpi: None,
+            fit: None,
+            swiss_cross: false,
+        }
+    }
+
+    /// Number of pixels per module.
+    pub fn module_size(mut self, module_size: u32) -> Self {
+        self.module_size = module_size;
+        self.fit = None;
+        self
+    }
+
+    /// Width of the quiet zone in modules. By default 4 for regular QR codes, and 2 for Micro
+    /// QR and rMQR codes.
+    pub fn quiet_zone(mut self, quiet_zone: u32) -> Self {
+        self.quiet_zone = quiet_zone;
+        self
+    }
+
+    pub fn foreground(mut self, foreground: Color) -> Self {
+        self.foreground = foreground;
+        self
+    }
+
+    pub fn background(mut self, background: Color) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn color(mut self, color: PngColor) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Resolution written in the pHYs chunk, so that print software uses the intended size.
+    pub fn dpi(mut self, dpi: u32) -> Self {
+        self.dpi = Some(dpi);
+        self
+    }
+
+    /// Uses the largest module size for which the image, quiet zone included, fits in
+    /// `width` x `height` pixels.
+    pub fn fit(mut self, width: u32, height: u32) -> Self {
+        self.fit = Some((width, height));
+        self
+    }
+
+    /// Draws the Swiss cross of QR-bills in the centre, 7 mm wide for a symbol of 46 mm, in
+    /// the colours of the dark and light modules.
+    pub fn swiss_cross(mut self, swiss_cross: bool) -> Self {
+        self.swiss_cross = swiss_cross;
+        self
+    }
+
+    /// Width and height of the image in pixels.
+    pub fn dimensions(&self) -> Result<(u32, u32), QrError> {
+        let module_size = self.resolved_module_size()?;
+        let (width, height) = self.modules();
+        Ok((width * module_size, height * module_size))
+    }
+
+    /// Number of modules per row and per column, quiet zone included.
+    fn modules(&self) -> (u32, u32) {
+        (
+            self.qrcode.width() + 2 * self.quiet_zone,
+            self.qrcode.height() + 2 * self.quiet_zone,
+        )
+    }
+
+    fn resolved_module_size(&self) -> Result<u32, QrError> {
+        let (modules_x, modules_y) = self.modules();
+        let module_size = match self.fit {
+            Some((width, height)) => (width / modules_x).min(height / modules_y),
+            None => self.module_size,
+        };
+
+        if module_size == 0 {
+            Err(QrError::Render(
+                "The symbol does not fit in the requested size.".to_string(),
+            ))
+        } else {
+            Ok(module_size)
+        }
+    }
+
+    pub fn render(&self) -> Result<Vec<u8>, QrError> {
+        let module_size = self.resolved_module_size()?;
+        let (width, height) = self.dimensions()?;
+
+        let mut output = Vec::new();
+        let mut encoder = Encoder::new(&mut output, width, height);
+
+        match self.color {
+            PngColor::Grayscale => {
+                encoder.set_color(ColorType::Grayscale);
+                encoder.set_depth(BitDepth::One);
+            }
+            PngColor::Rgba => {
+                encoder.set_color(ColorType::Rgba);
+                encoder.set_depth(BitDepth::Eight);
+            }
+        }
+
+        if let Some(dpi) = self.dpi {
+            // The pHYs chunk stores pixels per meter
+            let ppm = (dpi as f64 / 0.0254).round() as u32;
+            encoder.set_pixel_dims(Some(PixelDimensions {
+                xppu: ppm,
+                yppu: ppm,
+                unit: Unit::Meter,
+            }));
+        }
+
+        let mut writer = encoder
+            .write_header()
+            .map_err(|e| QrError::Render(e.to_string()))?;
+        writer
+            .write_image_data(&self.pixels(module_size))
+            .map_err(|e| QrError::Render(e.to_string()))?;
+        writer
+            .finish()
+            .map_err(|e| QrError::Render(e.to_string()))?;
+
+        Ok(output)
+    }
+
+    fn is_dark(&self, module_x: u32, module_y: u32) -> bool {
+        let (width, height) = (self.qrcode.width(), self.qrcode.height());
+        let (Some(x), Some(y)) = (
+            module_x.checked_sub(self.quiet_zone),
+            module_y.checked_sub(self.quiet_zone),
+        ) else {
+            return false;
+        };
+
+        x < width && y < height && self.qrcode.data[(x + width * y) as usize].value()
+    }
+
+    fn pixels(&self, module_size: u32) -> Vec<u8> {
+        let (modules_x, modules_y) = self.modules();
+        let mut pixels = Vec::new();
+
+        for module_y in 0..modules_y {
+            let row: Vec<bool> = (0..modules_x)
+                .flat_map(|module_x| {
+                    std::iter::repeat_n(self.is_dark(module_x, module_y), module_size as usize)
+                })
+                .collect();
+
+            if self.swiss_cross {
+                for y in module_y * module_size..(module_y + 1) * module_size {
+                    let mut row = row.clone();
+                    self.draw_swiss_cross(&mut row, y, module_size);
+                    pixels.extend_from_slice(&self.line(&row));
+                }
+            } else {
+                let line = self.line(&row);
+                for _ in 0..module_size {
+                    pixels.extend_from_slice(&line);
+                }
+            }
+        }
+
+        pixels
+    }
+
+    /// Draws the pixels of the Swiss cross in row `y`, its edges rounded to whole pixels.
+    fn draw_swiss_cross(&self, row: &mut [bool], y: u32, module_size: u32) {
+        let pixel = |modules: f64| {
+            ((modules + self.quiet_zone as f64) * module_size as f64).round() as usize
+        };
+
+        for rect in swiss_cross(self.qrcode.width(), self.qrcode.height()) {
+            let (top, bottom) = (pixel(rect.y), pixel(rect.y + rect.height));
+            if (top..bottom).contains(&(y as usize)) {
+                row[pixel(rect.x)..pixel(rect.x + rect.width)].fill(rect.dark);
+            }
+        }
+    }
+
+    fn line(&self, row: &[bool]) -> Vec<u8> {
+        match self.color {
+            // Rows are padded to a whole byte, a set bit is white
+            PngColor::Grayscale => row
+                .chunks(8)
+                .map(|chunk| {
+                    chunk
+                        .iter()
+                        .enumerate()
+                        .fold(0, |byte, (i, dark)| byte | ((!dark as u8) << (7 - i)))
+                })
+                .collect(),
+            PngColor::Rgba => row
+                .iter()
+                .flat_map(|dark| {
+                    let color = if *dark {
+                        self.foreground
+                    } else {
+                        self.background
+                    };
+                    [color.r, color.g, color.b, color.a]
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ec::EcLevel;
+    use crate::encoding::Encoding;
+    use crate::mask::MaskSelection;
+    use crate::preprocessor::Preprocessor;
+    use png::Decoder;
+
+    fn qrcode() -> QrCode {
+        Preprocessor::new(
+            "HELLO WORLD",
+            Encoding::Alphanumeric,
+            EcLevel::M,
+            MaskSelection::Auto,
+        )
+        .unwrap()
+        .generate_qrcode()
+        .unwrap()
+    }
+
+    fn decode(png: &[u8]) -> (png::OutputInfo, png::Info<'static>, Vec<u8>) {
+        let mut reader = Decoder::new(png).read_info().unwrap();
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let output = reader.next_frame(&mut buffer).unwrap();
+        let info = reader.info().clone();
+        (output, info, buffer)
+    }
+
+    #[test]
+    fn render_uses_exact_pixel_size() {
+        let png = qrcode()
+            .png()
+            .module_size(3)
+            .quiet_zone(2)
+            .render()
+            .unwrap();
+        let (output, _, _) = decode(&png);
+        assert_eq!((output.width, output.height), (75, 75));
+        assert_eq!(output.color_type, ColorType::Grayscale);
+        assert_eq!(output.bit_depth, BitDepth::One);
+    }
+
+    #[test]
+    fn render_draws_dark_modules_in_black() {
+        let png = qrcode()
+            .png()
+            .module_size(1)
+            .quiet_zone(1)
+            .render()
+            .unwrap();
+        let (output, _, buffer) = decode(&png);
+        let pixel = |x: usize, y: usize| (buffer[y * output.line_size + x / 8] >> (7 - x % 8)) & 1;
+
+        // Quiet zone, then the top left corner of the finder pattern
+        assert_eq!(pixel(0, 0), 1);
+        assert_eq!(pixel(1, 1), 0);
+    }
+
+    #[test]
+    fn render_writes_rgba_colors() {
+        let png = qrcode()
+            .png()
+            .module_size(2)
+            .quiet_zone(1)
+            .color(PngColor::Rgba)
+            .foreground(Color::rgb(10, 20, 30))
+            .background(Color::TRANSPARENT)
+            .render()
+            .unwrap();
+        let (output, _, buffer) = decode(&png);
+
+        assert_eq!(output.color_type, ColorType::Rgba);
+        assert_eq!(&buffer[..4], &[0, 0, 0, 0]);
+        let corner = (2 * output.line_size) + 2 * 4;
+        assert_eq!(&buffer[corner..corner + 4], &[10, 20, 30, 255]);
+    }
+
+    #[test]
+    fn render_writes_dpi_in_phys_chunk() {
+        let png = qrcode().png().dpi(300).render().unwrap();
+        let (_, info, _) = decode(&png);
+        let dims = info.pixel_dims.unwrap();
+        assert_eq!((dims.xppu, dims.yppu), (11811, 11811));
+        assert_eq!(dims.unit, Unit::Meter);
+    }
+
+    #[test]
+    fn fit_picks_largest_module_size() {
+        let qrcode = qrcode();
+        // 21 modules and a quiet zone of 4 on each side
+        assert_eq!(qrcode.png().fit(300, 400).dimensions(), Ok((290, 290)));
+        assert_eq!(qrcode.png().fit(29, 29).dimensions(), Ok((29, 29)));
+        assert!(qrcode.png().fit(28, 100).render().is_err());
+    }
+
+    #[test]
+    fn render_draws_the_swiss_cross() {
+        let png = qrcode()
+            .png()
+            .module_size(10)
+            .swiss_cross(true)
+            .render()
+            .unwrap();
+        let (output, _, buffer) = decode(&png);
+        let pixel = |x: usize, y: usize| (buffer[y * output.line_size + x / 8] >> (7 - x % 8)) & 1;
+
+        // The symbol spans pixels 40 to 250, the cross 129 to 161 and its black square 131 to
+        // 159, with bars 5 pixels wide
+        assert_eq!(pixel(129, 129), 1);
+        assert_eq!(pixel(131, 131), 0);
+        assert_eq!(pixel(158, 131), 0);
+        assert_eq!(pixel(145, 145), 1);
+        assert_eq!(pixel(137, 145), 1);
+        assert_eq!(pixel(137, 140), 0);
+    }
+}
@@ -1,153 +1,811 @@
 use crate::bit::Bit;
-use crate::debug_vec;
 use crate::ec::*;
+use crate::eci::Charset;
 use crate::encoding::*;
-use crate::mask::MaskPattern;
+use crate::error::QrError;
+use crate::fnc1::{escape_alphanumeric, fnc1_charset, Fnc1};
+use crate::mask::{MaskPattern, MaskSelection};
 use crate::qrcode::QrCode;
-use crate::tables::{
-    ALPHANUMERIC_CHAR_COUNT, ALPHANUMERIC_SIZE, BYTE_CHAR_COUNT, BYTE_SIZE, DATA_BYTES_PER_BLOCK,
-    EC_BYTES_PER_BLOCK, KANJI_CHAR_COUNT, KANJI_SIZE, NUMERIC_CHAR_COUNT, NUMERIC_SIZE,
-};
+use crate::segment::{optimal_fnc1_segments_in, optimal_segments_in, Segment};
+use crate::structured_append::{balanced_split, parity, StructuredAppend};
+use crate::version::Version;
+use std::ops::RangeInclusive;
 
 pub struct Preprocessor {
     qrcode_bits: Vec<Bit>,
-    encoding: Encoding,
     ec_level: EcLevel,
-    version: u8,
-    mask_pattern: MaskPattern,
+    version: Version,
+    mask: MaskSelection,
 }
 
+/// Every regular QR code version.
+const NORMAL_VERSIONS: RangeInclusive<Version> = Version::Normal(1)..=Version::Normal(40);
+
 impl Preprocessor {
-    pub fn generate_qrcode(&self) -> QrCode {
-        let mut res = QrCode::new(
-            self.version,
-            self.ec_level,
-            self.mask_pattern,
-            self.encoding,
-        )
-        .expect("QR code generation error");
+    pub fn generate_qrcode(&self) -> Result<QrCode, QrError> {
+        let mask_pattern = match self.mask {
+            MaskSelection::Fixed(mask_pattern) => mask_pattern,
+            MaskSelection::Auto => match self.version {
+                Version::Micro(_) => MaskPattern::MICRO[0],
+                Version::Normal(_) => MaskPattern::Checkerboard,
+                Version::Rmqr(..) => MaskPattern::RMQR,
+            },
+        };
+
+        let mut res = QrCode::new(self.version, self.ec_level, mask_pattern)?;
 
         res.all_functional_patterns();
         res.fill(&self.qrcode_bits);
-        res.apply_mask();
+        match self.mask {
+            MaskSelection::Fixed(_) => res.apply_mask(),
+            MaskSelection::Auto => {
+                res.apply_best_mask();
+            }
+        }
+
+        Ok(res)
+    }
 
-        res
+    pub fn version(&self) -> Version {
+        self.version
     }
 
+    /// Encodes the whole of `data` in `encoding` and picks the smallest version able to hold
+    /// it.
     pub fn new(
         data: &str,
         encoding: Encoding,
         ec_level: EcLevel,
-        mask_pattern: MaskPattern,
-    ) -> Preprocessor {
-        let mut bits = encoding.encode(data).unwrap();
+        mask: MaskSelection,
+    ) -> Result<Preprocessor, QrError> {
+        Self::encode(
+            data,
+            Some(encoding),
+            None,
+            ec_level,
+            false,
+            mask,
+            NORMAL_VERSIONS,
+        )
+    }
+
+    /// Splits `data` into the shortest mix of numeric, alphanumeric, byte and kanji segments
+    /// and picks the smallest version able to hold them.
+    pub fn segmented(
+        data: &str,
+        ec_level: EcLevel,
+        mask: MaskSelection,
+    ) -> Result<Preprocessor, QrError> {
+        Self::encode(data, None, None, ec_level, false, mask, NORMAL_VERSIONS)
+    }
+
+    /// Encodes `data` in `encoding`, or in the shortest mix of modes when it is `None`, and
+    /// picks the smallest version in `versions` able to hold it. With `boost_ec`, the ec level
+    /// is then raised as long as the data still fits in that version.
+    ///
+    /// Byte mode data is transcoded to `charset`. Without one, it is ISO-8859-1 unless the data
+    /// has characters that neither ISO-8859-1 nor kanji mode can encode, in which case it is
+    /// UTF-8.
+    pub fn encode(
+        data: &str,
+        encoding: Option<Encoding>,
+        charset: Option<Charset>,
+        ec_level: EcLevel,
+        boost_ec: bool,
+        mask: MaskSelection,
+        versions: RangeInclusive<Version>,
+    ) -> Result<Preprocessor, QrError> {
+        Self::check_versions(&versions)?;
 
-        let table = Self::table_from_encoding(encoding);
+        let charset = charset.unwrap_or_else(|| Self::default_charset(data, encoding));
+        let segments_for = |version| Self::text_segments(data, encoding, charset, false, version);
 
-        let (v, _) = table
-            .iter()
-            .skip(ec_level.ordinal() as usize)
-            .step_by(4)
-            .enumerate()
-            .find(|(_, &size)| data.len() <= size as usize)
-            .expect("Not enough space.");
+        Self::smallest_version(
+            segments_for,
+            |_| Ok(vec![]),
+            ec_level,
+            boost_ec,
+            mask,
+            versions,
+        )
+    }
+
+    /// Encodes FNC1 data like [`Self::encode`], after the FNC1 mode indicator. [`GS`](crate::fnc1::GS) separators
+    /// are written as `%` in alphanumeric mode, where a literal `%` is doubled. Byte mode data
+    /// is transcoded to the default charset.
+    pub fn fnc1(
+        data: &str,
+        fnc1: Fnc1,
+        encoding: Option<Encoding>,
+        ec_level: EcLevel,
+        boost_ec: bool,
+        mask: MaskSelection,
+        versions: RangeInclusive<Version>,
+    ) -> Result<Preprocessor, QrError> {
+        Self::check_versions(&versions)?;
+
+        let charset = Self::default_charset(data, encoding);
+        let segments_for = |version| Self::text_segments(data, encoding, charset, true, version);
+        let header = |version| fnc1.header(version);
+
+        Self::smallest_version(segments_for, header, ec_level, boost_ec, mask, versions)
+    }
+
+    /// Encodes `data` as is in a single byte mode segment and picks the smallest version in
+    /// `versions` able to hold it, raising the ec level with `boost_ec` like [`Self::encode`].
+    pub fn binary(
+        data: &[u8],
+        ec_level: EcLevel,
+        boost_ec: bool,
+        mask: MaskSelection,
+        versions: RangeInclusive<Version>,
+    ) -> Result<Preprocessor, QrError> {
+        Self::check_versions(&versions)?;
 
-        let version = v + 1;
+        let segments_for = |_| Ok(vec![Segment::binary(data)]);
 
-        let char_count = Self::char_count(version as u8, encoding);
-        let mut char_count_to_bits = Bit::from(data.len() as u32, char_count, false, true);
+        Self::smallest_version(
+            segments_for,
+            |_| Ok(vec![]),
+            ec_level,
+            boost_ec,
+            mask,
+            versions,
+        )
+    }
 
-        if bits.len() < char_count as usize {
-            bits.append(&mut vec![
-                Bit::Zero(false);
-                (char_count as usize) - bits.len()
-            ]);
+    /// Encodes `data` like [`Self::encode`], split into a Structured Append sequence of up to 16
+    /// symbols when it does not fit in a single one.
+    ///
+    /// The characters are spread evenly over the fewest symbols able to hold them, and every
+    /// symbol takes the version of the largest one. With `fnc1`, every symbol has the FNC1 mode
+    /// indicator, as in [`Self::fnc1`], and `charset` must be `None`. Structured Append is only
+    /// available in regular QR codes.
+    pub fn structured_append(
+        data: &str,
+        encoding: Option<Encoding>,
+        charset: Option<Charset>,
+        fnc1: Option<Fnc1>,
+        ec_level: EcLevel,
+        mask: MaskSelection,
+        versions: RangeInclusive<Version>,
+    ) -> Result<Vec<Preprocessor>, QrError> {
+        Self::check_structured_append_versions(&versions)?;
+        if fnc1.is_some() && charset.is_some() {
+            return Err(fnc1_charset());
         }
 
-        let mut data_segment = Vec::new();
-        let mut mod_indicator_bits = encoding.mod_indicator();
+        let charset = charset.unwrap_or_else(|| Self::default_charset(data, encoding));
+        let chars: Vec<char> = data.chars().collect();
+        let segments_for = |chars: &[char], version| {
+            let data: String = chars.iter().collect();
+            Self::text_segments(&data, encoding, charset, fnc1.is_some(), version)
+        };
 
-        data_segment.append(&mut mod_indicator_bits);
-        data_segment.append(&mut char_count_to_bits);
-        data_segment.append(&mut bits);
+        Self::sequence(&chars, segments_for, fnc1, ec_level, mask, versions)
+    }
 
-        // Compute total size without ec bits
-        let (block_1_size, block_1_count, block_2_size, block_2_count) =
-            DATA_BYTES_PER_BLOCK[version - 1][ec_level.ordinal() as usize];
-        let total_data_bits = (block_1_size * block_1_count + block_2_size * block_2_count) * 8;
-
-        // Add terminator bits (at most 4 0s)
-        if data_segment.len() < total_data_bits {
-            let empty_bits = total_data_bits - data_segment.len();
-            if empty_bits >= 4 {
-                data_segment.append(&mut vec![Bit::Zero(false); 4]);
-            } else {
-                data_segment.append(&mut vec![Bit::Zero(false); empty_bits]);
+    /// Encodes `data` as is in byte mode like [`Self::binary`], split into a Structured Append
+    /// sequence like [`Self::structured_append`].
+    pub fn structured_append_binary(
+        data: &[u8],
+        ec_level: EcLevel,
+        mask: MaskSelection,
+        versions: RangeInclusive<Version>,
+    ) -> Result<Vec<Preprocessor>, QrError> {
+        Self::check_structured_append_versions(&versions)?;
+
+        let segments_for = |data: &[u8], _| Ok(vec![Segment::binary(data)]);
+
+        Self::sequence(data, segments_for, None, ec_level, mask, versions)
+    }
+
+    fn check_structured_append_versions(versions: &RangeInclusive<Version>) -> Result<(), QrError> {
+        Self::check_versions(versions)?;
+        match (versions.start(), versions.end()) {
+            (Version::Normal(_), Version::Normal(_)) => Ok(()),
+            _ => Err(QrError::Unsupported(
+                "Structured Append is only available in regular QR codes".to_string(),
+            )),
+        }
+    }
+
+    /// Splits `items` into the fewest balanced runs whose segments, given by `segments_for`,
+    /// fit in a version of `versions` after a Structured Append header and the `fnc1` header. A
+    /// single run is encoded without Structured Append header.
+    fn sequence<T>(
+        items: &[T],
+        segments_for: impl Fn(&[T], Version) -> Result<Vec<Segment>, QrError>,
+        fnc1: Option<Fnc1>,
+        ec_level: EcLevel,
+        mask: MaskSelection,
+        versions: RangeInclusive<Version>,
+    ) -> Result<Vec<Preprocessor>, QrError> {
+        for total in 1..=StructuredAppend::MAX_SYMBOLS {
+            // The parity does not change the length of the header
+            let structured_append = (total > 1).then_some(StructuredAppend {
+                index: 0,
+                total: total as u8,
+                parity: 0,
+            });
+            let header = |version| Self::headers(structured_append, fnc1, version);
+            let runs = balanced_split(items, total);
+
+            let mut largest = Some(*versions.start());
+            for run in &runs {
+                let run_segments = |version| segments_for(run, version);
+                let fit = Self::first_fit(
+                    &run_segments,
+                    &header,
+                    ec_level,
+                    false,
+                    mask,
+                    versions.clone(),
+                )?;
+                largest = largest
+                    .zip(fit)
+                    .map(|(largest, (_, version, _))| largest.max(version));
             }
+            let Some(version) = largest else {
+                continue;
+            };
+
+            let segments = runs
+                .iter()
+                .map(|run| segments_for(run, version))
+                .collect::<Result<Vec<_>, _>>()?;
+            let parity = parity(segments.iter().flatten(), fnc1.is_some());
+            return segments
+                .iter()
+                .enumerate()
+                .map(|(index, segments)| {
+                    let structured_append = structured_append.map(|_| StructuredAppend {
+                        index: index as u8,
+                        total: total as u8,
+                        parity,
+                    });
+                    let mut stream = Self::headers(structured_append, fnc1, version)?;
+                    stream.extend(Segment::stream(segments, version)?);
+                    Self::from_stream(stream, version, ec_level, mask)
+                })
+                .collect();
         }
 
-        // Add padding bits
-        while data_segment.len() % 8 != 0 {
-            data_segment.push(Bit::Zero(false));
+        // Bits of the longest sequence, against what as many symbols of the largest version hold
+        let max_version = *versions.end();
+        let mut needed_bits = 0;
+        let header_bits = Self::headers(None, fnc1, max_version)?.len();
+        for run in balanced_split(items, StructuredAppend::MAX_SYMBOLS) {
+            let segments = segments_for(run, max_version)?;
+            needed_bits += StructuredAppend::HEADER_BITS
+                + header_bits
+                + Segment::total_bits(&segments, max_version)?;
         }
+        Err(QrError::DataTooLong {
+            needed_bits,
+            max_bits: StructuredAppend::MAX_SYMBOLS * Self::capacity(max_version, ec_level)?.0,
+            version: max_version,
+            ec_level,
+        })
+    }
 
-        // Add padding bytes
-        if data_segment.len() < total_data_bits {
-            let mut byte_1 = to_bits_array(&[236]);
-            let mut byte_2 = to_bits_array(&[17]);
+    /// Structured Append header, then FNC1 header, each if any.
+    fn headers(
+        structured_append: Option<StructuredAppend>,
+        fnc1: Option<Fnc1>,
+        version: Version,
+    ) -> Result<Vec<Bit>, QrError> {
+        let mut bits = structured_append.map_or(vec![], |header| header.header());
+        if let Some(fnc1) = fnc1 {
+            bits.append(&mut fnc1.header(version)?);
+        }
+        Ok(bits)
+    }
 
-            while data_segment.len() < total_data_bits {
-                data_segment.extend_from_slice(&byte_1);
-                std::mem::swap(&mut byte_1, &mut byte_2);
+    /// Segments of `data` in `encoding`, or in the shortest mix of modes when it is `None`. FNC1
+    /// data has its separators escaped in alphanumeric mode.
+    fn text_segments(
+        data: &str,
+        encoding: Option<Encoding>,
+        charset: Charset,
+        fnc1: bool,
+        version: Version,
+    ) -> Result<Vec<Segment>, QrError> {
+        match (encoding, fnc1) {
+            (Some(Encoding::Alphanumeric), true) => Ok(vec![Segment::new(
+                Encoding::Alphanumeric,
+                &escape_alphanumeric(data),
+            )?]),
+            (Some(encoding), _) => Ok(vec![Segment::with_charset(encoding, data, charset)?]),
+            (None, false) => optimal_segments_in(data, version, charset),
+            (None, true) => optimal_fnc1_segments_in(data, version, charset),
+        }
+    }
+
+    fn check_versions(versions: &RangeInclusive<Version>) -> Result<(), QrError> {
+        let (min_version, max_version) = (*versions.start(), *versions.end());
+        max_version.check()?;
+        min_version.check()?;
+        if min_version > max_version {
+            return Err(QrError::InvalidVersionRange {
+                min_version,
+                max_version,
+            });
+        }
+        Ok(())
+    }
+
+    /// ISO-8859-1, unless some characters going to byte mode are missing from it.
+    fn default_charset(data: &str, encoding: Option<Encoding>) -> Charset {
+        let latin_1 = |c| Charset::Iso8859_1.encode_char(c).is_some();
+        let fits = match encoding {
+            None => data
+                .chars()
+                .all(|c| latin_1(c) || Encoding::Kanji.can_encode(c)),
+            Some(Encoding::Byte) => data.chars().all(latin_1),
+            Some(_) => true,
+        };
+
+        match fits {
+            true => Charset::Iso8859_1,
+            false => Charset::Utf8,
+        }
+    }
+
+    /// Picks the smallest version in `versions` whose data capacity holds the encoded bit length
+    /// of `header` and of the segments given by `segments_for`.
+    fn smallest_version(
+        segments_for: impl Fn(Version) -> Result<Vec<Segment>, QrError>,
+        header: impl Fn(Version) -> Result<Vec<Bit>, QrError>,
+        ec_level: EcLevel,
+        boost_ec: bool,
+        mask: MaskSelection,
+        versions: RangeInclusive<Version>,
+    ) -> Result<Preprocessor, QrError> {
+        let max_version = *versions.end();
+        match Self::first_fit(&segments_for, &header, ec_level, boost_ec, mask, versions)? {
+            Some((segments, version, ec_level)) => {
+                let mut stream = header(version)?;
+                stream.extend(Segment::stream(&segments, version)?);
+                Self::from_stream(stream, version, ec_level, mask)
             }
+            None => Err(QrError::DataTooLong {
+                needed_bits: header(max_version)?.len()
+                    + Segment::total_bits(&segments_for(max_version)?, max_version)?,
+                max_bits: max_version.data_capacity(ec_level).unwrap_or(0),
+                version: max_version,
+                ec_level,
+            }),
         }
+    }
 
-        let cw_per_block = EC_BYTES_PER_BLOCK[version - 1][ec_level.ordinal() as usize];
+    /// Smallest version in `versions` holding `header` followed by the segments given by
+    /// `segments_for`, with those segments and the ec level to use, or `None` if none does.
+    ///
+    /// Micro QR versions lacking a mode, the ec level or the mask are skipped, unless they are
+    /// the largest allowed version.
+    fn first_fit(
+        segments_for: &impl Fn(Version) -> Result<Vec<Segment>, QrError>,
+        header: &impl Fn(Version) -> Result<Vec<Bit>, QrError>,
+        ec_level: EcLevel,
+        boost_ec: bool,
+        mask: MaskSelection,
+        versions: RangeInclusive<Version>,
+    ) -> Result<Option<(Vec<Segment>, Version, EcLevel)>, QrError> {
+        let max_version = *versions.end();
+        let mut widths = None;
+        let mut segments = Ok(vec![]);
 
-        let (data_codewords, ec_codewords) = codewords(
-            &Bit::bytes(&data_segment),
-            version as u8,
-            &ec_level,
-            cw_per_block,
-        );
+        for version in Version::all().filter(|version| versions.contains(version)) {
+            // The segments only change with the width of the mode and character count indicators
+            if widths != Some(Self::header_widths(version)) {
+                widths = Some(Self::header_widths(version));
+                segments = segments_for(version);
+            }
+
+            let fit = segments.clone().and_then(|segments| {
+                let header_bits = header(version)?.len();
+                Self::fit(&segments, header_bits, version, ec_level, boost_ec, mask)
+                    .map(|fit| fit.map(|ec_level| (segments, ec_level)))
+            });
+
+            match fit {
+                Ok(Some((segments, ec_level))) => return Ok(Some((segments, version, ec_level))),
+                Ok(None) => {}
+                Err(_) if version.is_micro() && version != max_version => {}
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Width of the mode indicator and of the character count indicator of each mode.
+    fn header_widths(version: Version) -> (Option<usize>, [Option<u8>; 4]) {
+        (
+            version.mode_bits().ok(),
+            Encoding::ALL.map(|mode| mode.char_count_bits(version).ok()),
+        )
+    }
+
+    /// Ec level to use if `header_bits` and the segments fit in the given version, raised with
+    /// `boost_ec`.
+    fn fit(
+        segments: &[Segment],
+        header_bits: usize,
+        version: Version,
+        ec_level: EcLevel,
+        boost_ec: bool,
+        mask: MaskSelection,
+    ) -> Result<Option<EcLevel>, QrError> {
+        if let MaskSelection::Fixed(mask_pattern) = mask {
+            let available = match version {
+                Version::Micro(_) => mask_pattern.micro_ordinal().is_some(),
+                Version::Normal(_) => true,
+                Version::Rmqr(..) => mask_pattern == MaskPattern::RMQR,
+            };
+            if !available {
+                return Err(QrError::Unsupported(format!(
+                    "mask pattern {:?} is not available in version {}",
+                    mask_pattern, version
+                )));
+            }
+        }
+
+        let (capacity, _) = Self::capacity(version, ec_level)?;
+        let needed_bits = header_bits + Segment::total_bits(segments, version)?;
+        if needed_bits > capacity {
+            return Ok(None);
+        }
+
+        Ok(Some(match boost_ec {
+            // rMQR codes skip from M to H
+            true => EcLevel::ALL
+                .into_iter()
+                .skip(ec_level.ordinal() as usize)
+                .filter_map(|level| Some((level, version.data_capacity(level)?)))
+                .take_while(|(_, capacity)| needed_bits <= *capacity)
+                .last()
+                .map_or(ec_level, |(level, _)| level),
+            false => ec_level,
+        }))
+    }
+
+    /// Encodes the segments in the given version.
+    pub fn from_segments(
+        segments: &[Segment],
+        version: Version,
+        ec_level: EcLevel,
+        mask: MaskSelection,
+    ) -> Result<Preprocessor, QrError> {
+        version.check()?;
+        Self::from_stream(Segment::stream(segments, version)?, version, ec_level, mask)
+    }
+
+    /// Pads the data bit stream and adds the ec codewords.
+    fn from_stream(
+        mut data_segment: Vec<Bit>,
+        version: Version,
+        ec_level: EcLevel,
+        mask: MaskSelection,
+    ) -> Result<Preprocessor, QrError> {
+        // Compute total size without ec bits
+        let (total_data_bits, cw_per_block) = Self::capacity(version, ec_level)?;
+        if data_segment.len() > total_data_bits {
+            return Err(QrError::DataTooLong {
+                needed_bits: data_segment.len(),
+                max_bits: total_data_bits,
+                version,
+                ec_level,
+            });
+        }
+
+        // Add terminator bits (at most 4 0s, 3 to 9 in Micro QR codes)
+        let empty_bits = total_data_bits - data_segment.len();
+        let terminator_bits = empty_bits.min(version.terminator_bits()?);
+        data_segment.append(&mut vec![Bit::Zero(false); terminator_bits]);
+
+        // Add padding bits, the last codeword of M1 and M3 only has 4 bits
+        while !data_segment.len().is_multiple_of(8) && data_segment.len() < total_data_bits {
+            data_segment.push(Bit::Zero(false));
+        }
+
+        // Add padding bytes
+        let mut byte_1 = to_bits_array(&[236]);
+        let mut byte_2 = to_bits_array(&[17]);
+
+        while data_segment.len() + 8 <= total_data_bits {
+            data_segment.extend_from_slice(&byte_1);
+            std::mem::swap(&mut byte_1, &mut byte_2);
+        }
+        data_segment.resize(total_data_bits, Bit::Zero(false));
 
-        let mut data_bits = Bit::bits(&data_codewords, data_codewords.len() * 8);
+        let (data_codewords, ec_codewords) =
+            codewords(&Bit::bytes(&data_segment), version, &ec_level, cw_per_block);
+
+        let mut data_bits = Bit::bits(&data_codewords, total_data_bits);
         let error_correction = Bit::bits(&ec_codewords, ec_codewords.len() * 8);
 
         data_bits.extend(error_correction);
 
-        debug_vec!(&data_bits);
-
-        Preprocessor {
+        Ok(Preprocessor {
             qrcode_bits: data_bits,
-            encoding,
             ec_level,
-            version: version as u8,
-            mask_pattern,
+            version,
+            mask,
+        })
+    }
+
+    /// Number of data bits (without ec codewords) available in the given version, and number
+    /// of ec codewords per block.
+    fn capacity(version: Version, ec_level: EcLevel) -> Result<(usize, usize), QrError> {
+        version.check()?;
+        match (
+            version.data_capacity(ec_level),
+            version.ec_codewords(ec_level),
+        ) {
+            (Some(data_bits), Some(ec_codewords)) => Ok((data_bits, ec_codewords)),
+            _ => Err(QrError::Unsupported(format!(
+                "ec level {:?} is not available in version {}",
+                ec_level, version
+            ))),
         }
     }
+}
 
-    fn table_from_encoding(encoding: Encoding) -> &'static [u32; 160] {
-        match encoding {
-            Encoding::Numeric => &NUMERIC_SIZE,
-            Encoding::Alphanumeric => &ALPHANUMERIC_SIZE,
-            Encoding::Byte => &BYTE_SIZE,
-            Encoding::Kanji => &KANJI_SIZE,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tables::{ALPHANUMERIC_SIZE, BYTE_SIZE, KANJI_SIZE, NUMERIC_SIZE};
+
+    #[test]
+    fn new_matches_character_capacity_tables() {
+        let cases = [
+            (Encoding::Numeric, "1", &NUMERIC_SIZE),
+            (Encoding::Alphanumeric, "A", &ALPHANUMERIC_SIZE),
+            (Encoding::Byte, "a", &BYTE_SIZE),
+            (Encoding::Kanji, "点", &KANJI_SIZE),
+        ];
+
+        for (encoding, ch, table) in cases {
+            for ec_level in EcLevel::ALL {
+                // Around the versions where the character count indicators widen
+                for number in [1, 2, 9, 10, 26, 27, 40] {
+                    let size = table[(number as usize - 1) * 4 + ec_level.ordinal() as usize];
+                    let data = ch.repeat(size as usize);
+                    let mask = MaskSelection::Fixed(MaskPattern::Checkerboard);
+
+                    let fitting = Preprocessor::new(&data, encoding, ec_level, mask).unwrap();
+                    let version = Version::Normal(number);
+                    assert_eq!(fitting.version, version, "{:?} {:?}", encoding, ec_level);
+
+                    let data = data + ch;
+                    match Preprocessor::new(&data, encoding, ec_level, mask) {
+                        Ok(larger) => assert_eq!(larger.version, Version::Normal(number + 1)),
+                        Err(error) => assert!(matches!(error, QrError::DataTooLong { .. })),
+                    }
+                }
+            }
         }
     }
 
-    fn char_count(version: u8, encoding: Encoding) -> u8 {
-        let index = match version {
-            1_u8..=9_u8 => 0,
-            10_u8..=26_u8 => 1,
-            27u8..=40u8 => 2,
-            _ => panic!("Invalid version."),
+    #[test]
+    fn new_counts_encoded_bytes_of_multi_byte_characters() {
+        let mask = MaskSelection::Auto;
+
+        // 12 bit ECI header, 4 + 8 bit segment header and 16 UTF-8 bytes fill the 152 bits
+        let fitting = Preprocessor::new(&"😀".repeat(4), Encoding::Byte, EcLevel::L, mask);
+        assert_eq!(fitting.unwrap().version, Version::Normal(1));
+
+        let larger = Preprocessor::new(&"😀".repeat(5), Encoding::Byte, EcLevel::L, mask);
+        assert_eq!(larger.unwrap().version, Version::Normal(2));
+    }
+
+    #[test]
+    fn encode_boosts_ec_level_within_the_version() {
+        let encode = |data: &str, boost_ec| {
+            let mask = MaskSelection::Auto;
+            Preprocessor::encode(
+                data,
+                None,
+                None,
+                EcLevel::L,
+                boost_ec,
+                mask,
+                NORMAL_VERSIONS,
+            )
+            .unwrap()
         };
-        match encoding {
-            Encoding::Numeric => NUMERIC_CHAR_COUNT[index],
-            Encoding::Alphanumeric => ALPHANUMERIC_CHAR_COUNT[index],
-            Encoding::Byte => BYTE_CHAR_COUNT[index],
-            Encoding::Kanji => KANJI_CHAR_COUNT[index],
-        }
+
+        // 4 + 9 + 11 * 5 + 6 = 74 bits, more than the 72 of version 1-H
+        let boosted = encode("HELLO WORLD", true);
+        assert_eq!(
+            (boosted.version, boosted.ec_level),
+            (Version::Normal(1), EcLevel::Q)
+        );
+
+        let kept = encode("HELLO WORLD", false);
+        assert_eq!(
+            (kept.version, kept.ec_level),
+            (Version::Normal(1), EcLevel::L)
+        );
+
+        let short = encode("1", true);
+        assert_eq!(
+            (short.version, short.ec_level),
+            (Version::Normal(1), EcLevel::H)
+        );
+    }
+
+    #[test]
+    fn encode_boosts_rmqr_from_m_to_h() {
+        let mask = MaskSelection::Auto;
+        let version = Version::Rmqr(7, 43)..=Version::Rmqr(7, 43);
+        let boosted = Preprocessor::encode("1", None, None, EcLevel::M, true, mask, version);
+        assert_eq!(boosted.unwrap().ec_level, EcLevel::H);
+    }
+
+    #[test]
+    fn encode_reports_the_failed_constraint() {
+        let mask = MaskSelection::Auto;
+        let data = "A".repeat(30);
+        let (min_version, max_version) = (Version::Normal(5), Version::Normal(3));
+        let version_1 = Version::Normal(1)..=Version::Normal(1);
+
+        assert_eq!(
+            Preprocessor::encode(&data, None, None, EcLevel::M, true, mask, version_1).err(),
+            Some(QrError::DataTooLong {
+                needed_bits: 4 + 9 + 15 * 11,
+                max_bits: 16 * 8,
+                version: Version::Normal(1),
+                ec_level: EcLevel::M
+            })
+        );
+        assert_eq!(
+            Preprocessor::encode(
+                &data,
+                None,
+                None,
+                EcLevel::M,
+                false,
+                mask,
+                min_version..=max_version
+            )
+            .err(),
+            Some(QrError::InvalidVersionRange {
+                min_version,
+                max_version
+            })
+        );
+
+        let versions = Version::Normal(0)..=Version::Normal(3);
+        assert_eq!(
+            Preprocessor::binary(b"A", EcLevel::M, false, mask, versions).err(),
+            Some(QrError::InvalidVersion(Version::Normal(0)))
+        );
+
+        let micro = Version::Micro(1)..=Version::Micro(4);
+        assert!(matches!(
+            Preprocessor::encode("1", None, None, EcLevel::H, false, mask, micro).err(),
+            Some(QrError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn encode_prefers_micro_qr_when_allowed() {
+        let encode = |data: &str, ec_level| {
+            let versions = Version::Micro(1)..=Version::Normal(40);
+            let mask = MaskSelection::Auto;
+            Preprocessor::encode(data, None, None, ec_level, false, mask, versions).unwrap()
+        };
+
+        // 3 + 10 + 10 + 4 = 27 bits do not fit the 20 of M1
+        assert_eq!(encode("12345", EcLevel::L).version, Version::Micro(1));
+        assert_eq!(encode("0123456", EcLevel::L).version, Version::Micro(2));
+        // Byte mode starts at M3, ec level Q at M4 and H at version 1
+        assert_eq!(encode("hello", EcLevel::L).version, Version::Micro(3));
+        assert_eq!(encode("HELLO", EcLevel::Q).version, Version::Micro(4));
+        assert_eq!(encode("HELLO", EcLevel::H).version, Version::Normal(1));
+        // Micro QR codes have no ECI
+        assert_eq!(encode("😀", EcLevel::L).version, Version::Normal(1));
+    }
+
+    #[test]
+    fn from_segments_pads_the_short_last_codeword() {
+        // 20 data bits in M1: 3 bit count, 4 bits of data and a 3 bit terminator, then zeros
+        // up to the second codeword and a 4 bit last codeword, too short for a pad byte
+        let segments = [Segment::new(Encoding::Numeric, "1").unwrap()];
+        let mask = MaskSelection::Auto;
+        let preprocessor =
+            Preprocessor::from_segments(&segments, Version::Micro(1), EcLevel::L, mask).unwrap();
+
+        let data_bits: Vec<bool> = preprocessor.qrcode_bits[..20]
+            .iter()
+            .map(Bit::value)
+            .collect();
+        let expected: Vec<bool> = "00100010000000000000".chars().map(|c| c == '1').collect();
+        assert_eq!(data_bits, expected);
+        assert_eq!(preprocessor.qrcode_bits.len(), 20 + 2 * 8);
+    }
+
+    #[test]
+    fn structured_append_balances_the_symbols() {
+        let mask = MaskSelection::Auto;
+        let data = "9".repeat(8000);
+        let sequence = Preprocessor::structured_append(
+            &data,
+            None,
+            None,
+            None,
+            EcLevel::M,
+            mask,
+            NORMAL_VERSIONS,
+        )
+        .unwrap();
+        assert_eq!(sequence.len(), 2);
+        assert_eq!(sequence[0].version, sequence[1].version);
+
+        // Data fitting in one symbol has no header
+        let single = Preprocessor::structured_append(
+            "HELLO",
+            None,
+            None,
+            None,
+            EcLevel::M,
+            mask,
+            NORMAL_VERSIONS,
+        )
+        .unwrap();
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].version, Version::Normal(1));
+    }
+
+    #[test]
+    fn structured_append_binary_fills_up_to_16_symbols() {
+        let mask = MaskSelection::Auto;
+        let version_40 = Version::Normal(40)..=Version::Normal(40);
+
+        // 20 + 4 + 16 header bits and 2329 bytes fill the 18672 bits of version 40-M
+        let data = vec![0; 16 * 2329];
+        let sequence =
+            Preprocessor::structured_append_binary(&data, EcLevel::M, mask, version_40.clone());
+        assert_eq!(sequence.unwrap().len(), 16);
+
+        let data = vec![0; 16 * 2329 + 1];
+        assert_eq!(
+            Preprocessor::structured_append_binary(&data, EcLevel::M, mask, version_40).err(),
+            Some(QrError::DataTooLong {
+                needed_bits: 16 * 18672 + 8,
+                max_bits: 16 * 18672,
+                version: Version::Normal(40),
+                ec_level: EcLevel::M
+            })
+        );
+
+        let micro = Version::Micro(1)..=Version::Normal(40);
+        assert!(matches!(
+            Preprocessor::structured_append_binary(b"A", EcLevel::M, mask, micro),
+            Err(QrError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn fnc1_counts_the_mode_indicator() {
+        let mask = MaskSelection::Auto;
+        // 4 + 10 header bits and 41 digits fill 151 of the 152 bits of version 1-L
+        let data = "1".repeat(41);
+        let plain =
+            Preprocessor::encode(&data, None, None, EcLevel::L, false, mask, NORMAL_VERSIONS);
+        assert_eq!(plain.unwrap().version, Version::Normal(1));
+
+        let first = Fnc1::First;
+        let gs1 = Preprocessor::fnc1(&data, first, None, EcLevel::L, false, mask, NORMAL_VERSIONS);
+        assert_eq!(gs1.unwrap().version, Version::Normal(2));
+
+        let micro = Version::Micro(1)..=Version::Micro(4);
+        assert!(matches!(
+            Preprocessor::fnc1("1", first, None, EcLevel::L, false, mask, micro),
+            Err(QrError::Unsupported(_))
+        ));
     }
 }
@@ -0,0 +1,585 @@
+use crate::builder::QrCodeBuilder;
+use crate::ec::EcLevel;
+use crate::epc::{check_creditor_reference, check_iban};
+use crate::error::QrError;
+use crate::qrcode::QrCode;
+
+/// Largest payload, in bytes, which is what version 25 holds at ec level M.
+pub const MAX_BYTES: usize = 997;
+
+/// Largest version of a Swiss QR code.
+pub const MAX_VERSION: u8 = 25;
+
+/// Recursive mod 10 table of QR references.
+const QRR_TABLE: [u32; 10] = [0, 9, 4, 6, 8, 2, 7, 1, 3, 5];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Currency {
+    Chf,
+    Eur,
+}
+
+/// Payment reference, which must be a QR reference with a QR-IBAN and a creditor reference or
+/// none otherwise.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Reference {
+    /// QR reference of 27 digits, the last one a recursive mod 10 check digit.
+    Qrr(String),
+    /// ISO 11649 creditor reference.
+    Scor(String),
+}
+
+/// Structured address. The name, postal code, town and country are required.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Address {
+    pub name: String,
+    pub street: String,
+    pub building_number: String,
+    pub postal_code: String,
+    pub town: String,
+    /// ISO 3166 country code, like `CH`.
+    pub country: String,
+}
+
+impl Address {
+    fn fields(&self) -> [&str; 7] {
+        [
+            "S",
+            &self.name,
+            &self.street,
+            &self.building_number,
+            &self.postal_code,
+            &self.town,
+            &self.country,
+        ]
+    }
+
+    fn check(&self, party: &str) -> Result<(), QrError> {
+        let required = |field: &str, value: &str| match value.is_empty() {
+            true => Err(QrError::InvalidPayload(format!(
+                "the {} needs a {}",
+                party, field
+            ))),
+            false => Ok(()),
+        };
+
+        required("name", &self.name)?;
+        required("postal code", &self.postal_code)?;
+        required("town", &self.town)?;
+        check_text(&format!("{} name", party), &self.name, 70)?;
+        check_text(&format!("{} street", party), &self.street, 70)?;
+        check_text(
+            &format!("{} building number", party),
+            &self.building_number,
+            16,
+        )?;
+        check_text(&format!("{} postal code", party), &self.postal_code, 16)?;
+        check_text(&format!("{} town", party), &self.town, 35)?;
+        if self.country.len() != 2 || !self.country.chars().all(|c| c.is_ascii_uppercase()) {
+            return Err(QrError::InvalidPayload(format!(
+                "{:?} is not a country code",
+                self.country
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Swiss QR-bill payload, the Swiss Payments Code of the SIX implementation guidelines.
+///
+/// ```
+/// use qrcode::qr_bill::Address;
+/// use qrcode::QrBill;
+///
+/// let creditor = Address {
+///     name: "Robert Schneider AG".to_string(),
+///     street: "Rue du Lac".to_string(),
+///     building_number: "1268".to_string(),
+///     postal_code: "2501".to_string(),
+///     town: "Biel".to_string(),
+///     country: "CH".to_string(),
+/// };
+/// let bill = QrBill::new("CH44 3199 9123 0008 8901 2", creditor)
+///     .amount(194_975)
+///     .qr_reference("21 00000 00003 13947 14300 09017");
+/// let qrcode = bill.build().unwrap();
+/// let svg = qrcode.svg().swiss_cross(true).render();
+/// assert_eq!(svg.matches("<rect").count(), 5);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct QrBill {
+    iban: String,
+    creditor: Address,
+    amount: Option<u64>,
+    currency: Currency,
+    debtor: Option<Address>,
+    reference: Option<Reference>,
+    message: Option<String>,
+    bill_information: Option<String>,
+    alternative_procedures: Vec<String>,
+}
+
+impl QrBill {
+    /// Bill in CHF to the account `iban`, where spaces are dropped.
+    pub fn new(iban: &str, creditor: Address) -> QrBill {
+        QrBill {
+            iban: iban.replace(' ', ""),
+            creditor,
+            amount: None,
+            currency: Currency::Chf,
+            debtor: None,
+            reference: None,
+            message: None,
+            bill_information: None,
+            alternative_procedures: vec![],
+        }
+    }
+
+    /// Amount in cents (Rappen). Without one, the debtor fills it in.
+    pub fn amount(mut self, cents: u64) -> Self {
+        self.amount = Some(cents);
+        self
+    }
+
+    pub fn currency(mut self, currency: Currency) -> Self {
+        self.currency = currency;
+        self
+    }
+
+    pub fn debtor(mut self, debtor: Address) -> Self {
+        self.debtor = Some(debtor);
+        self
+    }
+
+    /// QR reference, for QR-IBANs. Spaces are dropped.
+    pub fn qr_reference(mut self, reference: &str) -> Self {
+        self.reference = Some(Reference::Qrr(reference.replace(' ', "")));
+        self
+    }
+
+    /// ISO 11649 creditor reference, for regular IBANs. Spaces are dropped.
+    pub fn creditor_reference(mut self, reference: &str) -> Self {
+        self.reference = Some(Reference::Scor(reference.replace(' ', "")));
+        self
+    }
+
+    /// Unstructured message to the debtor.
+    pub fn message(mut self, message: &str) -> Self {
+        self.message = Some(message.to_string());
+        self
+    }
+
+    /// Structured bill information, like Swico S1 `//S1/10/...` data.
+    pub fn bill_information(mut self, bill_information: &str) -> Self {
+        self.bill_information = Some(bill_information.to_string());
+        self
+    }
+
+    /// Parameters of an alternative procedure, up to 2 of them.
+    pub fn alternative_procedure(mut self, parameters: &str) -> Self {
+        self.alternative_procedures.push(parameters.to_string());
+        self
+    }
+
+    /// Payload, with fields separated by LF.
+    pub fn data(&self) -> Result<String, QrError> {
+        self.check()?;
+
+        let mut fields = vec!["SPC", "0200", "1", &self.iban];
+        fields.extend(self.creditor.fields());
+        // Ultimate creditor, reserved for future use
+        fields.extend([""; 7]);
+        let amount = self
+            .amount
+            .map(|cents| format!("{}.{:02}", cents / 100, cents % 100))
+            .unwrap_or_default();
+        fields.push(&amount);
+        fields.push(match self.currency {
+            Currency::Chf => "CHF",
+            Currency::Eur => "EUR",
+        });
+        match &self.debtor {
+            Some(debtor) => fields.extend(debtor.fields()),
+            None => fields.extend([""; 7]),
+        }
+        match &self.reference {
+            Some(Reference::Qrr(reference)) => fields.extend(["QRR", reference]),
+            Some(Reference::Scor(reference)) => fields.extend(["SCOR", reference]),
+            None => fields.extend(["NON", ""]),
+        }
+        fields.push(self.message.as_deref().unwrap_or_default());
+        fields.push("EPD");
+        // The bill information may be left out when it is the last field
+        if self.bill_information.is_some() || !self.alternative_procedures.is_empty() {
+            fields.push(self.bill_information.as_deref().unwrap_or_default());
+        }
+        fields.extend(self.alternative_procedures.iter().map(String::as_str));
+
+        let data = fields.join("\n");
+        if data.len() > MAX_BYTES {
+            return Err(QrError::InvalidPayload(format!(
+                "the payload takes {} bytes, more than {}",
+                data.len(),
+                MAX_BYTES
+            )));
+        }
+        Ok(data)
+    }
+
+    /// QR code holding the UTF-8 payload in byte mode, at ec level M and up to version 25 as
+    /// the guidelines require. Render it with the Swiss cross, see
+    /// [`crate::svg::SvgRenderer::swiss_cross`] and [`crate::png::PngRenderer::swiss_cross`].
+    pub fn build(&self) -> Result<QrCode, QrError> {
+        QrCodeBuilder::binary(self.data()?.as_bytes())
+            .ec_level(EcLevel::M)
+            .max_version(MAX_VERSION)
+            .build()
+    }
+
+    fn check(&self) -> Result<(), QrError> {
+        let invalid = |reason: &str| Err(QrError::InvalidPayload(reason.to_string()));
+
+        check_iban(&self.iban)?;
+        if !self.iban.starts_with("CH") && !self.iban.starts_with("LI") {
+            return invalid("the IBAN must be from Switzerland or Liechtenstein");
+        }
+        // QR-IBANs have an institution identification from 30000 to 31999
+        let qr_iban = matches!(self.iban[4..9].parse(), Ok(30000..=31999));
+
+        self.creditor.check("creditor")?;
+        if let Some(debtor) = &self.debtor {
+            debtor.check("debtor")?;
+        }
+        if let Some(cents) = self.amount {
+            if !(1..=99_999_999_999).contains(&cents) {
+                return invalid("the amount must be from 0.01 to 999999999.99");
+            }
+        }
+
+        match (&self.reference, qr_iban) {
+            (Some(Reference::Qrr(reference)), true) => check_qr_reference(reference)?,
+            (Some(Reference::Qrr(_)), false) => return invalid("a QR reference needs a QR-IBAN"),
+            (_, true) => return invalid("a QR-IBAN needs a QR reference"),
+            (Some(Reference::Scor(reference)), false) => check_creditor_reference(reference)?,
+            (None, false) => {}
+        }
+
+        let message = self.message.as_deref().unwrap_or_default();
+        let bill_information = self.bill_information.as_deref().unwrap_or_default();
+        check_text("message", message, 140)?;
+        check_text("bill information", bill_information, 140)?;
+        let length = message.chars().count() + bill_information.chars().count();
+        if length > 140 {
+            return Err(QrError::InvalidPayload(format!(
+                "the message and bill information have {} characters, more than 140",
+                length
+            )));
+        }
+
+        if self.alternative_procedures.len() > 2 {
+            return invalid("a bill has at most 2 alternative procedures");
+        }
+        for parameters in &self.alternative_procedures {
+            check_text("alternative procedure", parameters, 100)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks the length of a field, and that it only has characters of the Latin set of the
+/// guidelines.
+fn check_text(field: &str, value: &str, max: usize) -> Result<(), QrError> {
+    let latin = |c: char| {
+        matches!(c,
+            '\u{20}'..='\u{7E}'
+            | '\u{A0}'..='\u{17F}'
+            | '\u{218}'..='\u{21B}'
+            | '€')
+    };
+
+    if let Some(c) = value.chars().find(|c| !latin(*c)) {
+        return Err(QrError::InvalidPayload(format!(
+            "the {} cannot hold {:?}",
+            field, c
+        )));
+    }
+    let length = value.chars().count();
+    match length > max {
+        true => Err(QrError::InvalidPayload(format!(
+            "the {} has {} characters, more than {}",
+            field, length, max
+        ))),
+        false => Ok(()),
+    }
+}
+
+/// Checks a QR reference: 27 digits, the last one being the recursive mod 10 check digit of
+/// the others.
+fn check_qr_reference(reference: &str) -> Result<(), QrError> {
+    let valid = reference.len() == 27
+        && reference.chars().all(|c| c.is_ascii_digit())
+        && reference != "0".repeat(27)
+        && {
+            let carry = reference[..26].chars().fold(0, |carry, c| {
+                QRR_TABLE[((carry + c.to_digit(10).unwrap()) % 10) as usize]
+            });
+            reference.ends_with(char::from_digit((10 - carry) % 10, 10).unwrap())
+        };
+    match valid {
+        true => Ok(()),
+        false => Err(QrError::InvalidPayload(format!(
+            "{:?} is not a QR reference",
+            reference
+        ))),
+    }
+}
+
+/// Rectangle of the Swiss cross, in modules from the top left corner of the symbol.
+pub(crate) struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub dark: bool,
+}
+
+/// Swiss cross in the centre of a symbol of `width` x `height` modules, drawn in order: a light
+/// square of 7 mm for a symbol of 46 mm on its shorter side, a dark square inside with a border
+/// of 0.5 mm, and the two light bars of the cross, in the proportions of the flag.
+pub(crate) fn swiss_cross(width: u32, height: u32) -> [Rect; 4] {
+    let symbol = (width as f64, height as f64);
+    let size = symbol.0.min(symbol.1) * 7.0 / 46.0;
+    let square = size * 6.0 / 7.0;
+    // The cross of the flag spans 20 of its 32 units, with arms 6 units wide
+    let (arms, bar) = (square * 20.0 / 32.0, square * 6.0 / 32.0);
+
+    let centered = |width: f64, height: f64, dark| Rect {
+        x: (symbol.0 - width) / 2.0,
+        y: (symbol.1 - height) / 2.0,
+        width,
+        height,
+        dark,
+    };
+    [
+        centered(size, size, false),
+        centered(square, square, true),
+        centered(arms, bar, false),
+        centered(bar, arms, false),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::decode;
+
+    fn creditor() -> Address {
+        Address {
+            name: "Robert Schneider AG".to_string(),
+            street: "Rue du Lac".to_string(),
+            building_number: "1268".to_string(),
+            postal_code: "2501".to_string(),
+            town: "Biel".to_string(),
+            country: "CH".to_string(),
+        }
+    }
+
+    fn debtor() -> Address {
+        Address {
+            name: "Pia-Maria Rutschmann-Schnyder".to_string(),
+            street: "Grosse Marktgasse".to_string(),
+            building_number: "28".to_string(),
+            postal_code: "9400".to_string(),
+            town: "Rorschach".to_string(),
+            country: "CH".to_string(),
+        }
+    }
+
+    fn reason(bill: QrBill) -> String {
+        match bill.data() {
+            Err(QrError::InvalidPayload(reason)) => reason,
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn data_writes_every_field() {
+        let bill = QrBill::new("CH44 3199 9123 0008 8901 2", creditor())
+            .amount(194_975)
+            .debtor(debtor())
+            .qr_reference("21 00000 00003 13947 14300 09017")
+            .message("Order of 15 June 2020")
+            .bill_information("//S1/10/10201409/11/200701/20/140.000-53/30/102673831/31/200615/32/7.7/33/7.7:0/40/0:30")
+            .alternative_procedure("Name AV1: UV;UltraPay005;12345")
+            .alternative_procedure("Name AV2: XY;XYService;54321");
+        assert_eq!(
+            bill.data().unwrap(),
+            "SPC\n0200\n1\nCH4431999123000889012\n\
+             S\nRobert Schneider AG\nRue du Lac\n1268\n2501\nBiel\nCH\n\
+             \n\n\n\n\n\n\n\
+             1949.75\nCHF\n\
+             S\nPia-Maria Rutschmann-Schnyder\nGrosse Marktgasse\n28\n9400\nRorschach\nCH\n\
+             QRR\n210000000003139471430009017\n\
+             Order of 15 June 2020\nEPD\n\
+             //S1/10/10201409/11/200701/20/140.000-53/30/102673831/31/200615/32/7.7/33/7.7:0/40/0:30\n\
+             Name AV1: UV;UltraPay005;12345\nName AV2: XY;XYService;54321"
+        );
+
+        let bill = QrBill::new("CH58 0079 1123 0008 8901 2", creditor()).currency(Currency::Eur);
+        assert_eq!(
+            bill.data().unwrap(),
+            "SPC\n0200\n1\nCH5800791123000889012\n\
+             S\nRobert Schneider AG\nRue du Lac\n1268\n2501\nBiel\nCH\n\
+             \n\n\n\n\n\n\n\
+             \nEUR\n\
+             \n\n\n\n\n\n\n\
+             NON\n\n\nEPD"
+        );
+    }
+
+    #[test]
+    fn data_checks_the_iban_and_reference() {
+        let qr_iban = || QrBill::new("CH4431999123000889012", creditor());
+        let iban = || QrBill::new("CH5800791123000889012", creditor());
+
+        assert!(iban()
+            .creditor_reference("RF18 5390 0754 7034")
+            .data()
+            .is_ok());
+        assert_eq!(
+            reason(QrBill::new("DE89370400440532013000", creditor())),
+            "the IBAN must be from Switzerland or Liechtenstein"
+        );
+        assert_eq!(reason(qr_iban()), "a QR-IBAN needs a QR reference");
+        assert_eq!(
+            reason(qr_iban().creditor_reference("RF18539007547034")),
+            "a QR-IBAN needs a QR reference"
+        );
+        assert_eq!(
+            reason(iban().qr_reference("210000000003139471430009017")),
+            "a QR reference needs a QR-IBAN"
+        );
+        assert_eq!(
+            reason(qr_iban().qr_reference("210000000003139471430009018")),
+            "\"210000000003139471430009018\" is not a QR reference"
+        );
+        assert_eq!(
+            reason(qr_iban().qr_reference(&"0".repeat(27))),
+            format!("{:?} is not a QR reference", "0".repeat(27))
+        );
+        assert_eq!(
+            reason(iban().creditor_reference("RF19539007547034")),
+            "\"RF19539007547034\" is not an ISO 11649 creditor reference"
+        );
+    }
+
+    #[test]
+    fn data_checks_the_other_fields() {
+        let bill = || QrBill::new("CH5800791123000889012", creditor());
+
+        assert_eq!(
+            reason(QrBill::new(
+                "CH5800791123000889012",
+                Address {
+                    town: String::new(),
+                    ..creditor()
+                }
+            )),
+            "the creditor needs a town"
+        );
+        assert_eq!(
+            reason(bill().debtor(Address {
+                country: "Switzerland".to_string(),
+                ..debtor()
+            })),
+            "\"Switzerland\" is not a country code"
+        );
+        assert_eq!(
+            reason(bill().debtor(Address {
+                building_number: "1".repeat(17),
+                ..debtor()
+            })),
+            "the debtor building number has 17 characters, more than 16"
+        );
+        assert_eq!(
+            reason(bill().message("Rechnung Nr. 1 ✓")),
+            "the message cannot hold '✓'"
+        );
+        assert_eq!(
+            reason(bill().message("Line\nbreak")),
+            "the message cannot hold '\\n'"
+        );
+        assert!(bill().message("Șoseaua € Łódź").data().is_ok());
+        assert_eq!(
+            reason(bill().amount(100_000_000_000)),
+            "the amount must be from 0.01 to 999999999.99"
+        );
+        assert_eq!(
+            reason(
+                bill()
+                    .message(&"m".repeat(100))
+                    .bill_information(&"b".repeat(41))
+            ),
+            "the message and bill information have 141 characters, more than 140"
+        );
+        assert_eq!(
+            reason(
+                bill()
+                    .alternative_procedure("a")
+                    .alternative_procedure("b")
+                    .alternative_procedure("c")
+            ),
+            "a bill has at most 2 alternative procedures"
+        );
+    }
+
+    #[test]
+    fn build_fits_version_25_at_ec_level_m() {
+        let bill = QrBill::new("CH4431999123000889012", creditor())
+            .amount(194_975)
+            .debtor(debtor())
+            .qr_reference("210000000003139471430009017");
+        let qrcode = bill.build().unwrap();
+        assert_eq!(qrcode.ec_level(), EcLevel::M);
+        assert_eq!(
+            decode(&qrcode).unwrap().bytes(),
+            bill.data().unwrap().as_bytes()
+        );
+
+        // A payload close to the limit still fits in version 25
+        let long = "é".repeat(35);
+        let address = || Address {
+            name: long.clone(),
+            street: long.clone(),
+            town: long.clone(),
+            ..creditor()
+        };
+        let bill = QrBill::new("CH4431999123000889012", address())
+            .debtor(address())
+            .qr_reference("210000000003139471430009017")
+            .message(&"ü".repeat(120))
+            .alternative_procedure(&"ä".repeat(100));
+        assert_eq!(bill.data().unwrap().len(), 979);
+        let qrcode = bill.build().unwrap();
+        assert_eq!(qrcode.version(), crate::version::Version::Normal(25));
+
+        assert_eq!(
+            reason(bill.alternative_procedure("x".repeat(20).as_str())),
+            "the payload takes 1000 bytes, more than 997"
+        );
+    }
+
+    #[test]
+    fn swiss_cross_is_centred_on_each_axis() {
+        let [light, dark, horizontal, vertical] = swiss_cross(43, 11);
+        assert!(!light.dark && dark.dark);
+        for rect in [light, dark, horizontal, vertical] {
+            assert!((rect.x * 2.0 + rect.width - 43.0).abs() < 1e-9);
+            assert!((rect.y * 2.0 + rect.height - 11.0).abs() < 1e-9);
+        }
+        // Scaled on the shorter side, so it stays inside the symbol
+        let [light, ..] = swiss_cross(43, 11);
+        assert!((light.width - 11.0 * 7.0 / 46.0).abs() < 1e-9);
+        assert_eq!(light.width, light.height);
+    }
+}
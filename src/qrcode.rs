@@ -1,16 +1,22 @@
 use crate::bit::Bit;
 use crate::ec::EcLevel;
-use crate::encoding::Encoding;
-use crate::mask::MaskPattern;
+use crate::error::QrError;
+use crate::mask::{micro_score, penalty, MaskPattern};
+use crate::tables::{
+    FORMAT_BITS, MICRO_FORMAT_BITS, RMQR_ALIGNMENT_COLUMNS, RMQR_FORMAT_BITS, RMQR_FORMAT_MASKS,
+    VERSION_BITS,
+};
+use crate::version::Version;
 use std::fmt;
 use std::fmt::Formatter;
 
+#[derive(Clone)]
 pub struct QrCode {
     pub data: Vec<Bit>,
-    version: u8,
+    version: Version,
     ec_level: EcLevel,
     mask_pattern: MaskPattern,
-    encoding: Encoding,
+    mask_scores: Option<[u32; 8]>,
 }
 
 impl QrCode {
@@ -28,51 +34,94 @@ impl QrCode {
         }
     }
 
-    fn coords_to_index(x: u32, y: u32, size: u32) -> Option<u32> {
-        if !(x < size && y < size) {
+    fn coords_to_index(x: u32, y: u32, width: u32, height: u32) -> Option<u32> {
+        if !(x < width && y < height) {
             None
         } else {
-            Some(x + size * y)
+            Some(x + width * y)
         }
     }
 
-    fn coords_to_index_from_version(x: u32, y: u32, version: u8) -> Option<u32> {
-        Self::coords_to_index(x, y, Self::size_from_version(version))
-    }
-
     fn coords_to_index_from_instance(&self, x: u32, y: u32) -> Option<u32> {
-        Self::coords_to_index(x, y, self.size())
+        Self::coords_to_index(x, y, self.width(), self.height())
     }
 
     pub fn new(
-        version: u8,
+        version: Version,
         ec_level: EcLevel,
         mask_pattern: MaskPattern,
-        encoding: Encoding,
-    ) -> Result<QrCode, String> {
-        if version > 40 || version == 0 {
-            Err("Invalid version.".to_string())
-        } else {
-            let size = Self::size_from_version(version);
-            let data = vec![Bit::Zero(false); (size * size) as usize];
-            Ok(QrCode {
-                data,
-                version,
-                ec_level,
-                mask_pattern,
-                encoding,
-            })
+    ) -> Result<QrCode, QrError> {
+        version.check()?;
+
+        if version.data_capacity(ec_level).is_none() {
+            return Err(QrError::Unsupported(format!(
+                "ec level {:?} is not available in version {}",
+                ec_level, version
+            )));
         }
+        if version.is_micro() && mask_pattern.micro_ordinal().is_none() {
+            return Err(QrError::Unsupported(format!(
+                "mask pattern {:?} is not available in Micro QR codes",
+                mask_pattern
+            )));
+        }
+        if version.is_rmqr() && mask_pattern != MaskPattern::RMQR {
+            return Err(QrError::Unsupported(format!(
+                "mask pattern {:?} is not available in rMQR codes",
+                mask_pattern
+            )));
+        }
+
+        let data = vec![Bit::Zero(false); (version.width() * version.height()) as usize];
+        Ok(QrCode {
+            data,
+            version,
+            ec_level,
+            mask_pattern,
+            mask_scores: None,
+        })
     }
 
-    fn size_from_version(version: u8) -> u32 {
-        17 + 4 * version as u32
+    fn size_from_version(version: Version) -> u32 {
+        version.width()
     }
 
-    fn size(&self) -> u32 {
+    /// Number of modules per side of a square symbol. For rMQR symbols this is only the
+    /// width, see [`QrCode::width`] and [`QrCode::height`].
+    #[deprecated(note = "rMQR symbols are not square, use `width` and `height`")]
+    pub fn size(&self) -> u32 {
         Self::size_from_version(self.version)
     }
 
+    /// Number of modules per row.
+    pub fn width(&self) -> u32 {
+        self.version.width()
+    }
+
+    /// Number of modules per column.
+    pub fn height(&self) -> u32 {
+        self.version.height()
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    pub fn ec_level(&self) -> EcLevel {
+        self.ec_level
+    }
+
+    pub fn mask_pattern(&self) -> MaskPattern {
+        self.mask_pattern
+    }
+
+    /// Penalty score of each mask pattern, indexed by ordinal, when the mask was chosen by
+    /// [`QrCode::apply_best_mask`]. Micro QR codes only score their four patterns, and keep the
+    /// highest score instead.
+    pub fn mask_scores(&self) -> Option<[u32; 8]> {
+        self.mask_scores
+    }
+
     fn finder_patterns(&mut self) {
         const FINDER_PATTERN: [Bit; 49] = [
             Bit::One(true),
@@ -128,10 +177,16 @@ impl QrCode {
 
         const PATTERN_LENGTH: u32 = 7;
 
-        let size = self.size();
+        let size = self.width();
         let corners = [(0, 0), (size - 7, 0), (0, size - 7)];
+        // Micro QR and rMQR codes only have the top left one
+        let count = if let Version::Normal(_) = self.version {
+            3
+        } else {
+            1
+        };
 
-        for corner in corners {
+        for corner in corners.into_iter().take(count) {
             let (x, y) = corner;
             for dx in 0..PATTERN_LENGTH {
                 for dy in 0..PATTERN_LENGTH {
@@ -146,17 +201,22 @@ impl QrCode {
     }
 
     fn separators_patterns(&mut self) {
-        let size = self.size();
+        let size = self.width();
         let top = [(7, 0), (size - 8, 0), (7, size - 8)];
         let right = [(0, 7), (size - 7, 7), (0, size - 8)];
+        let count = if let Version::Normal(_) = self.version {
+            3
+        } else {
+            1
+        };
 
-        for (x, y) in top {
+        for (x, y) in top.into_iter().take(count) {
             for dy in 0..8 {
                 self.put(x, y + dy, Bit::Zero(true))
             }
         }
 
-        for (x, y) in right {
+        for (x, y) in right.into_iter().take(count) {
             for dx in 0..7 {
                 self.put(x + dx, y, Bit::Zero(true))
             }
@@ -168,7 +228,7 @@ impl QrCode {
 
         for elem1 in array {
             for elem2 in array {
-                res.push((elem1.clone(), elem2.clone()));
+                res.push((*elem1, *elem2));
             }
         }
 
@@ -265,22 +325,109 @@ impl QrCode {
             &[6, 30, 58, 86, 114, 142, 170],
         ];
 
-        if self.version != 1 {
-            let combinations = Self::combination(COORDS[(self.version - 2) as usize]);
+        if let Version::Normal(number @ 2..) = self.version {
+            let combinations = Self::combination(COORDS[(number - 2) as usize]);
             for (x, y) in combinations {
                 self.draw_alignment_pattern(x as u32, y as u32);
             }
         }
     }
 
+    /// Sub-finder pattern in the bottom right corner, corner finder patterns in the two other
+    /// corners, alignment patterns on the top and bottom edges and timing patterns of an rMQR
+    /// symbol.
+    fn rmqr_patterns(&mut self) {
+        let (width, height) = (self.width(), self.height());
+
+        // Same as an alignment pattern
+        self.draw_alignment_pattern(width - 3, height - 3);
+
+        for (x, y, dark) in [
+            (width - 2, 0, true),
+            (width - 2, 1, false),
+            (width - 1, 1, true),
+        ] {
+            self.put(
+                x,
+                y,
+                if dark {
+                    Bit::One(true)
+                } else {
+                    Bit::Zero(true)
+                },
+            );
+        }
+        for x in 0..3 {
+            self.put(x, height - 1, Bit::One(true));
+        }
+        // Below the separator of the finder pattern
+        if height >= 11 {
+            self.put(0, height - 2, Bit::One(true));
+            self.put(1, height - 2, Bit::Zero(true));
+        }
+
+        let columns = RMQR_ALIGNMENT_COLUMNS
+            .iter()
+            .find(|(pattern_width, _)| *pattern_width as u32 == width)
+            .map_or(&[][..], |(_, columns)| columns);
+
+        for &column in columns {
+            for y in [0, height - 3] {
+                for dx in 0..3 {
+                    for dy in 0..3 {
+                        let bit = if dx == 1 && dy == 1 {
+                            Bit::Zero(true)
+                        } else {
+                            Bit::One(true)
+                        };
+                        self.put(column - 1 + dx, y + dy, bit);
+                    }
+                }
+            }
+        }
+
+        // Timing patterns along the top and bottom edges, and down the left and right edges and
+        // the alignment pattern columns
+        for y in [0, height - 1] {
+            for x in 0..width {
+                if !self.get(x, y).unwrap().is_functional() {
+                    let bit = if x % 2 == 0 {
+                        Bit::One(true)
+                    } else {
+                        Bit::Zero(true)
+                    };
+                    self.put(x, y, bit);
+                }
+            }
+        }
+        for &x in [0, width - 1].iter().chain(columns) {
+            for y in 0..height {
+                if !self.get(x, y).unwrap().is_functional() {
+                    let bit = if y % 2 == 0 {
+                        Bit::One(true)
+                    } else {
+                        Bit::Zero(true)
+                    };
+                    self.put(x, y, bit);
+                }
+            }
+        }
+    }
+
     fn timing_patterns(&mut self) {
-        let length = self.size() - 16;
+        // Row and column 6 between the separators, or the top and left edges of Micro QR codes
+        let (line, length) = match self.version {
+            Version::Micro(_) => (0, self.width() - 8),
+            Version::Normal(_) => (6, self.width() - 16),
+            // Drawn with the other rMQR patterns
+            Version::Rmqr(..) => return,
+        };
 
         let mut bit;
         for dx in 0..length {
             let x = dx + 8;
 
-            if self.get(x, 6).unwrap().is_functional() {
+            if self.get(x, line).unwrap().is_functional() {
                 continue;
             }
 
@@ -290,13 +437,13 @@ impl QrCode {
                 bit = Bit::Zero(true);
             }
 
-            self.put(x, 6, bit);
+            self.put(x, line, bit);
         }
 
         for dy in 0..length {
             let y = dy + 8;
 
-            if self.get(6, y).unwrap().is_functional() {
+            if self.get(line, y).unwrap().is_functional() {
                 continue;
             }
 
@@ -306,25 +453,25 @@ impl QrCode {
                 bit = Bit::Zero(true);
             }
 
-            self.put(6, y, bit);
+            self.put(line, y, bit);
         }
     }
 
     fn dark_module(&mut self) {
-        self.put(8, (4 * self.version + 9) as u32, Bit::One(true))
+        self.put(8, 4 * self.version.number() as u32 + 9, Bit::One(true))
     }
 
     fn format_information(&mut self) {
-        const FORMAT_BITS: [u32; 32] = [
-            0x77C4, 0x72F3, 0x7DAA, 0x789D, 0x662F, 0x6318, 0x6C41, 0x6976, 0x5412, 0x5125, 0x5E7C,
-            0x5B4B, 0x45F9, 0x40CE, 0x4F97, 0x4AA0, 0x355F, 0x3068, 0x3F31, 0x3A06, 0x24B4, 0x2183,
-            0x2EDA, 0x2BED, 0x1689, 0x13BE, 0x1CE7, 0x19D0, 0x762, 0x255, 0xD0C, 0x83B,
-        ];
+        match self.version {
+            Version::Micro(number) => return self.micro_format_information(number),
+            Version::Rmqr(..) => return self.rmqr_format_information(),
+            Version::Normal(_) => {}
+        }
 
         let mut index = self.mask_pattern.ordinal() as u32;
         match self.ec_level {
-            EcLevel::L => index += 0,
-            EcLevel::M => index += 8 * 1,
+            EcLevel::L => {}
+            EcLevel::M => index += 8,
             EcLevel::Q => index += 8 * 2,
             EcLevel::H => index += 8 * 3,
         }
@@ -332,72 +479,102 @@ impl QrCode {
         let info_bit = FORMAT_BITS[index as usize];
         let bits = Bit::from(info_bit, 15, true, true);
 
-        let mut i = 0;
-        for x in 0..9 {
-            if self.get(x, 8).unwrap().is_functional() {
-                continue;
-            }
-            self.put(x, 8, bits[i as usize]);
-            i += 1;
+        // Skip the timing patterns, the format information may be written more than once
+        for (i, x) in (0..9).filter(|x| *x != 6).enumerate() {
+            self.put(x, 8, bits[i]);
         }
 
-        let mut i = 8;
-        for y in (0..8).rev() {
-            if self.get(8, y).unwrap().is_functional() {
-                continue;
-            }
-            self.put(8, y, bits[i as usize]);
-            i += 1;
+        for (i, y) in (0..8).rev().filter(|y| *y != 6).enumerate() {
+            self.put(8, y, bits[i + 8]);
         }
 
-        let mut i = 0;
-        for y in ((self.size() - 7)..self.size()).rev() {
-            self.put(8, y, bits[i as usize]);
-            i += 1;
+        for (i, y) in ((self.width() - 7)..self.width()).rev().enumerate() {
+            self.put(8, y, bits[i]);
         }
 
-        let mut i = 7;
-        for x in (self.size() - 8)..self.size() {
-            self.put(x, 8, bits[i as usize]);
-            i += 1;
+        for (i, x) in ((self.width() - 8)..self.width()).enumerate() {
+            self.put(x, 8, bits[i + 7]);
         }
     }
 
-    fn version_information(&mut self) {
-        assert!(
-            self.version >= 7,
-            "Version information is not available for versions below 7."
-        );
+    /// Symbol number (version and ec level) and mask along the format information area of the
+    /// single finder pattern.
+    fn micro_format_information(&mut self, number: u8) {
+        let symbol_number = match number {
+            1 => 0,
+            _ => 2 * number as usize - 3 + self.ec_level.ordinal() as usize,
+        };
+        // QrCode::new only accepts Micro QR masks
+        let mask = self.mask_pattern.micro_ordinal().unwrap() as usize;
+
+        let info_bit = MICRO_FORMAT_BITS[4 * symbol_number + mask];
+        let bits = Bit::from(info_bit, 15, true, true);
 
-        const VERSION_BITS: [u32; 34] = [
-            0x07c94, 0x085bc, 0x09a99, 0x0a4d3, 0x0bbf6, 0x0c762, 0x0d847, 0x0e60d, 0x0f928,
-            0x10b78, 0x1145d, 0x12a17, 0x13532, 0x149a6, 0x15683, 0x168c9, 0x177ec, 0x18ec4,
-            0x191e1, 0x1afab, 0x1b08e, 0x1cc1a, 0x1d33f, 0x1ed75, 0x1f250, 0x209d5, 0x216f0,
-            0x228ba, 0x2379f, 0x24b0b, 0x2542e, 0x26a64, 0x27541, 0x28c69,
-        ];
+        for (i, x) in (1..9).enumerate() {
+            self.put(x, 8, bits[i]);
+        }
+
+        for (i, y) in (1..8).rev().enumerate() {
+            self.put(8, y, bits[i + 8]);
+        }
+    }
+
+    /// Version indicator and ec level in a 3x5 block and a 3 module column right of the finder
+    /// pattern, and again above the sub-finder pattern with another mask.
+    fn rmqr_format_information(&mut self) {
+        // QrCode::new only accepts rMQR versions and ec levels
+        let indicator = self.version.rmqr_indicator().unwrap() as usize;
+        let ec_index = Version::rmqr_ec_index(self.ec_level).unwrap();
+        let info_bit = RMQR_FORMAT_BITS[32 * ec_index + indicator];
+        let (width, height) = (self.width(), self.height());
+
+        // The least significant bit comes first
+        let bits = Bit::from(info_bit ^ RMQR_FORMAT_MASKS[0], 18, true, false);
+        for (i, bit) in bits.iter().enumerate().take(15) {
+            self.put(8 + i as u32 / 5, 1 + i as u32 % 5, *bit);
+        }
+        for (i, bit) in bits.iter().skip(15).enumerate() {
+            self.put(11, 1 + i as u32, *bit);
+        }
+
+        let bits = Bit::from(info_bit ^ RMQR_FORMAT_MASKS[1], 18, true, false);
+        for (i, bit) in bits.iter().enumerate().take(15) {
+            self.put(width - 8 + i as u32 / 5, height - 6 + i as u32 % 5, *bit);
+        }
+        for (i, bit) in bits.iter().skip(15).enumerate() {
+            self.put(width - 5 + i as u32, height - 6, *bit);
+        }
+    }
 
-        let version_bits = VERSION_BITS[(self.version - 7) as usize];
-        let bits = Bit::from(version_bits, 18, true, true);
+    fn version_information(&mut self) {
+        let number = match self.version {
+            Version::Normal(number @ 7..) => number,
+            _ => panic!("Version information is not available for versions below 7."),
+        };
+
+        let version_bits = VERSION_BITS[(number - 7) as usize];
+        // The least significant bit comes first
+        let bits = Bit::from(version_bits, 18, true, false);
 
         // bottom left
         let mut x = 0;
-        let mut y = self.size() - 11;
+        let mut y = self.width() - 11;
         for i in 0..18 {
             if i % 3 == 0 && i != 0 {
                 x += 1;
-                y = self.size() - 11;
+                y = self.width() - 11;
             }
             self.put(x, y, bits[i as usize]);
             y += 1;
         }
 
         // top right
-        let mut x = self.size() - 11;
+        let mut x = self.width() - 11;
         let mut y = 0;
         for i in 0..18 {
             if i % 3 == 0 && i != 0 {
                 y += 1;
-                x = self.size() - 11;
+                x = self.width() - 11;
             }
             self.put(x, y, bits[i as usize]);
             x += 1;
@@ -407,41 +584,110 @@ impl QrCode {
     pub fn apply_mask(&mut self) {
         let mask_fn = self.mask_pattern.get_mask();
 
-        for x in 0..self.size() {
-            for y in 0..self.size() {
+        for x in 0..self.width() {
+            for y in 0..self.height() {
                 let bit = self.get(x, y).unwrap();
-                if !bit.is_functional() {
-                    if mask_fn(x, y) {
-                        self.put(x, y, bit.invert());
-                    }
+                if !bit.is_functional() && mask_fn(x, y) {
+                    self.put(x, y, bit.invert());
+                }
+            }
+        }
+    }
+
+    /// Penalty score of the symbol in its current state.
+    pub fn penalty(&self) -> u32 {
+        let modules: Vec<bool> = self.data.iter().map(Bit::value).collect();
+        penalty(&modules, self.width() as usize)
+    }
+
+    /// Applies each of the eight masks with its format information and keeps the one with the
+    /// lowest penalty score. Micro QR codes try their four masks and keep the highest
+    /// [`micro_score`]. rMQR codes have a single mask, left with a score of 0.
+    pub fn apply_best_mask(&mut self) -> [u32; 8] {
+        let mut scores = [0; 8];
+        let mut best: Option<QrCode> = None;
+
+        let patterns: &[MaskPattern] = match self.version {
+            Version::Micro(_) => &MaskPattern::MICRO,
+            Version::Normal(_) => &MaskPattern::ALL,
+            Version::Rmqr(..) => &[MaskPattern::RMQR],
+        };
+
+        for &mask_pattern in patterns {
+            let mut candidate = self.clone();
+            candidate.mask_pattern = mask_pattern;
+            candidate.format_information();
+            candidate.apply_mask();
+
+            let score = match self.version {
+                Version::Micro(_) => {
+                    let modules: Vec<bool> = candidate.data.iter().map(Bit::value).collect();
+                    micro_score(&modules, candidate.width() as usize)
+                }
+                Version::Normal(_) => candidate.penalty(),
+                Version::Rmqr(..) => 0,
+            };
+            scores[mask_pattern.ordinal() as usize] = score;
+
+            let better = |best: &QrCode| {
+                let best_score = scores[best.mask_pattern.ordinal() as usize];
+                match self.version {
+                    Version::Micro(_) => score > best_score,
+                    Version::Normal(_) | Version::Rmqr(..) => score < best_score,
                 }
+            };
+
+            if best.as_ref().is_none_or(better) {
+                best = Some(candidate);
             }
         }
+
+        *self = best.unwrap();
+        self.mask_scores = Some(scores);
+
+        scores
     }
 
     pub fn all_functional_patterns(&mut self) {
         self.finder_patterns();
         self.separators_patterns();
         self.alignment_patterns();
+        if self.version.is_rmqr() {
+            self.rmqr_patterns();
+        }
         self.timing_patterns();
-        self.dark_module();
+        if let Version::Normal(number) = self.version {
+            self.dark_module();
+            if number >= 7 {
+                self.version_information();
+            }
+        }
         self.format_information();
-        if self.version >= 7 {
-            self.version_information();
+    }
+
+    pub fn fill(&mut self, bits: &[Bit]) {
+        for ((x, y), bit) in self.data_positions().into_iter().zip(bits) {
+            self.put(x, y, *bit);
         }
     }
 
-    pub fn fill(&mut self, bits: &Vec<Bit>) {
-        let n = self.size() as isize;
-        let mut bit_iter = bits.iter();
-        let mut col = n - 1;
+    /// Coordinates of the non functional modules in the order the data bits are placed.
+    pub fn data_positions(&self) -> Vec<(u32, u32)> {
+        let n = self.height() as isize;
+        let mut positions = Vec::new();
+        // The right edge of rMQR symbols is a timing pattern, the column pairs start left of it
+        let mut col = match self.version {
+            Version::Rmqr(..) => self.width() as isize - 2,
+            _ => self.width() as isize - 1,
+        };
 
         // The filling direction alternates: true = upward, false = downward.
         let mut upward = true;
 
         while col >= 0 {
-            // Skip the vertical timing pattern column if needed.
-            if col == 6 {
+            // Skip the vertical timing pattern column if needed, Micro QR codes have it on the
+            // left edge.
+            if col == 6 && !self.version.is_micro() {
                 col -= 1;
                 // If after skipping we go negative, break.
                 if col < 0 {
@@ -453,33 +699,19 @@ impl QrCode {
             // normally we process 2, but if col == 0 then only one column remains.
             let cols_to_process = if col == 0 { 1 } else { 2 };
 
-            if upward {
-                // Process rows from bottom to top.
-                for row in (0..n).rev() {
-                    for offset in 0..cols_to_process {
-                        let x = col - offset;
-                        // Skip if this cell is reserved.
-                        if !self.get(x as u32, row as u32).unwrap().is_functional() {
-                            if let Some(bit) = bit_iter.next() {
-                                self.put(x as u32, row as u32, *bit);
-                            } else {
-                                return;
-                            }
-                        }
-                    }
-                }
+            // Process rows from bottom to top when going upward, from top to bottom otherwise.
+            let rows: Vec<isize> = if upward {
+                (0..n).rev().collect()
             } else {
-                // Process rows from top to bottom.
-                for row in 0..n {
-                    for offset in 0..cols_to_process {
-                        let x = col - offset;
-                        if !self.get(x as u32, row as u32).unwrap().is_functional() {
-                            if let Some(bit) = bit_iter.next() {
-                                self.put(x as u32, row as u32, *bit);
-                            } else {
-                                return;
-                            }
-                        }
+                (0..n).collect()
+            };
+
+            for row in rows {
+                for offset in 0..cols_to_process {
+                    let x = col - offset;
+                    // Skip if this cell is reserved.
+                    if !self.get(x as u32, row as u32).unwrap().is_functional() {
+                        positions.push((x as u32, row as u32));
                     }
                 }
             }
@@ -487,6 +719,8 @@ impl QrCode {
             upward = !upward;
             col -= cols_to_process;
         }
+
+        positions
     }
 }
 
@@ -494,18 +728,19 @@ impl fmt::Display for QrCode {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let mut qrcode = String::new();
 
-        let lines: Vec<_> = self.data.chunks(self.size() as usize).collect();
+        let lines: Vec<_> = self.data.chunks(self.width() as usize).collect();
 
         // add quiet zone
-        for _ in 0..4 {
-            for _ in 0..self.size() + 8 {
+        let quiet_zone = self.version.quiet_zone();
+        for _ in 0..quiet_zone {
+            for _ in 0..self.width() + 2 * quiet_zone {
                 qrcode.push_str("██");
             }
             qrcode.push('\n');
         }
 
-        for i in 0..self.size() {
-            for _ in 0..4 {
+        for i in 0..self.height() {
+            for _ in 0..quiet_zone {
                 qrcode.push_str("██");
             }
             for module in lines[i as usize] {
@@ -515,21 +750,21 @@ impl fmt::Display for QrCode {
                     qrcode.push_str("██");
                 }
             }
-            for _ in 0..4 {
+            for _ in 0..quiet_zone {
                 qrcode.push_str("██");
             }
             qrcode.push('\n');
         }
 
-        for _ in 0..4 {
-            for _ in 0..self.size() + 8 {
+        for _ in 0..quiet_zone {
+            for _ in 0..self.width() + 2 * quiet_zone {
                 qrcode.push_str("██");
             }
             qrcode.push('\n');
         }
 
         let mut version = String::from('\n');
-        for _ in 0..self.size() {
+        for _ in 0..self.width() {
             version.push(' ');
         }
         version.push_str("Version: ");
@@ -543,154 +778,113 @@ impl fmt::Display for QrCode {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::decoder::decode_modules;
+    use crate::encoding::Encoding;
+    use crate::mask::MaskSelection;
+    use crate::preprocessor::Preprocessor;
     use crate::qrcode::Bit::{One, Zero};
+    use crate::segment::Segment;
 
     #[test]
     fn get_returns_correct_bit() {
-        let qr = QrCode::new(
-            1,
-            EcLevel::L,
-            MaskPattern::Checkerboard,
-            Encoding::Alphanumeric,
-        )
-        .unwrap();
+        let qr = QrCode::new(Version::Normal(1), EcLevel::L, MaskPattern::Checkerboard).unwrap();
         assert!(matches!(qr.get(0, 0), Some(Zero(_))));
     }
 
     #[test]
     fn get_returns_none_for_out_of_bounds() {
-        let qr = QrCode::new(
-            1,
-            EcLevel::L,
-            MaskPattern::Checkerboard,
-            Encoding::Alphanumeric,
-        )
-        .unwrap();
+        let qr = QrCode::new(Version::Normal(1), EcLevel::L, MaskPattern::Checkerboard).unwrap();
         assert_eq!(qr.get(100, 100), None);
     }
 
     #[test]
     fn new_returns_error_for_invalid_version() {
-        let result = QrCode::new(
-            41,
-            EcLevel::L,
-            MaskPattern::Checkerboard,
-            Encoding::Alphanumeric,
+        let result = QrCode::new(Version::Normal(41), EcLevel::L, MaskPattern::Checkerboard);
+        assert_eq!(
+            result.err(),
+            Some(QrError::InvalidVersion(Version::Normal(41)))
         );
-        assert!(result.is_err());
     }
 
     #[test]
     fn new_creates_qrcode_with_correct_size() {
-        let qr = QrCode::new(
-            1,
-            EcLevel::L,
-            MaskPattern::Checkerboard,
-            Encoding::Alphanumeric,
-        )
-        .unwrap();
-        assert_eq!(qr.size(), 21);
+        let qr = QrCode::new(Version::Normal(1), EcLevel::L, MaskPattern::Checkerboard).unwrap();
+        assert_eq!(qr.width(), 21);
     }
 
     #[test]
     fn size_from_version_calculates_correct_size() {
-        assert_eq!(QrCode::size_from_version(1), 21);
-        assert_eq!(QrCode::size_from_version(40), 177);
+        assert_eq!(QrCode::size_from_version(Version::Normal(1)), 21);
+        assert_eq!(QrCode::size_from_version(Version::Normal(40)), 177);
     }
 
     #[test]
     fn new_creates_qrcode_with_valid_version() {
-        let qr = QrCode::new(
-            10,
-            EcLevel::M,
-            MaskPattern::Checkerboard,
-            Encoding::Alphanumeric,
-        )
-        .unwrap();
-        assert_eq!(qr.version, 10);
-        assert_eq!(qr.size(), 57);
+        let qr = QrCode::new(Version::Normal(10), EcLevel::M, MaskPattern::Checkerboard).unwrap();
+        assert_eq!(qr.version, Version::Normal(10));
+        assert_eq!(qr.width(), 57);
     }
 
     #[test]
     fn new_creates_qrcode_with_correct_ec_level() {
-        let qr = QrCode::new(
-            5,
-            EcLevel::Q,
-            MaskPattern::Checkerboard,
-            Encoding::Alphanumeric,
-        )
-        .unwrap();
-        match qr.ec_level {
-            EcLevel::Q => assert!(true),
-            _ => assert!(false, "Expected EcLevel::Q"),
-        }
+        let qr = QrCode::new(Version::Normal(5), EcLevel::Q, MaskPattern::Checkerboard).unwrap();
+        assert!(matches!(qr.ec_level, EcLevel::Q), "Expected EcLevel::Q");
     }
 
     #[test]
     fn new_creates_qrcode_with_correct_data_size() {
-        let qr = QrCode::new(
-            2,
-            EcLevel::H,
-            MaskPattern::Checkerboard,
-            Encoding::Alphanumeric,
-        )
-        .unwrap();
+        let qr = QrCode::new(Version::Normal(2), EcLevel::H, MaskPattern::Checkerboard).unwrap();
         assert_eq!(qr.data.len(), 625);
     }
 
     #[test]
     fn new_returns_error_for_zero_version() {
-        let result = QrCode::new(
-            0,
-            EcLevel::L,
-            MaskPattern::Checkerboard,
-            Encoding::Alphanumeric,
-        );
+        let result = QrCode::new(Version::Normal(0), EcLevel::L, MaskPattern::Checkerboard);
         assert!(result.is_err());
-        assert_eq!(result.err(), Some("Invalid version.".to_string()));
+        assert_eq!(
+            result.err(),
+            Some(QrError::InvalidVersion(Version::Normal(0)))
+        );
     }
 
     #[test]
     fn new_returns_error_for_negative_version() {
         let result = QrCode::new(
-            -1i8 as u8,
+            Version::Normal(-1i8 as u8),
             EcLevel::L,
             MaskPattern::Checkerboard,
-            Encoding::Alphanumeric,
         );
         assert!(result.is_err());
-        assert_eq!(result.err(), Some("Invalid version.".to_string()));
+        assert_eq!(
+            result.err(),
+            Some(QrError::InvalidVersion(Version::Normal(255)))
+        );
     }
 
     #[test]
     fn size_from_version_calculates_size_for_min_version() {
-        assert_eq!(QrCode::size_from_version(1), 21);
+        assert_eq!(QrCode::size_from_version(Version::Normal(1)), 21);
     }
 
     #[test]
     fn size_from_version_calculates_size_for_max_version() {
-        assert_eq!(QrCode::size_from_version(40), 177);
+        assert_eq!(QrCode::size_from_version(Version::Normal(40)), 177);
     }
 
     #[test]
     fn size_from_version_calculates_size_for_intermediate_version() {
-        assert_eq!(QrCode::size_from_version(20), 97);
+        assert_eq!(QrCode::size_from_version(Version::Normal(20)), 97);
     }
 
     #[test]
     fn size_from_version_calculates_size_for_large_version() {
-        assert_eq!(QrCode::size_from_version(100), 417);
+        assert_eq!(QrCode::size_from_version(Version::Normal(100)), 417);
     }
 
     #[test]
     fn finder_patterns_creates_correct_patterns() {
-        let mut qr = QrCode::new(
-            1,
-            EcLevel::L,
-            MaskPattern::Checkerboard,
-            Encoding::Alphanumeric,
-        )
-        .unwrap();
+        let mut qr =
+            QrCode::new(Version::Normal(1), EcLevel::L, MaskPattern::Checkerboard).unwrap();
         qr.finder_patterns();
         let expected_pattern = [
             (0, 0),
@@ -725,13 +919,8 @@ mod tests {
 
     #[test]
     fn finder_patterns_handles_minimum_size() {
-        let mut qr = QrCode::new(
-            1,
-            EcLevel::L,
-            MaskPattern::Checkerboard,
-            Encoding::Alphanumeric,
-        )
-        .unwrap();
+        let mut qr =
+            QrCode::new(Version::Normal(1), EcLevel::L, MaskPattern::Checkerboard).unwrap();
         qr.finder_patterns();
         assert!(matches!(qr.get(0, 0), Some(One(_))));
         assert!(matches!(qr.get(20, 20), Some(Zero(_))));
@@ -739,15 +928,221 @@ mod tests {
 
     #[test]
     fn finder_patterns_handles_maximum_size() {
-        let mut qr = QrCode::new(
-            40,
-            EcLevel::L,
-            MaskPattern::Checkerboard,
-            Encoding::Alphanumeric,
-        )
-        .unwrap();
+        let mut qr =
+            QrCode::new(Version::Normal(40), EcLevel::L, MaskPattern::Checkerboard).unwrap();
         qr.finder_patterns();
         assert!(matches!(qr.get(0, 0), Some(One(_))));
         assert!(matches!(qr.get(176, 176), Some(Zero(_))));
     }
+
+    #[test]
+    fn new_creates_micro_qrcode_without_column_six_skip() {
+        for (number, modules) in [(1, 36), (2, 80), (3, 132), (4, 192)] {
+            let version = Version::Micro(number);
+            let mut qr = QrCode::new(version, EcLevel::L, MaskPattern::Horizontal).unwrap();
+            qr.all_functional_patterns();
+            assert_eq!(qr.width(), 9 + 2 * number as u32);
+            assert_eq!(qr.data_positions().len(), modules);
+        }
+    }
+
+    #[test]
+    fn new_rejects_what_micro_qr_cannot_encode() {
+        let result = QrCode::new(Version::Micro(4), EcLevel::H, MaskPattern::Horizontal);
+        assert!(matches!(result, Err(QrError::Unsupported(_))));
+
+        let result = QrCode::new(Version::Micro(1), EcLevel::M, MaskPattern::Horizontal);
+        assert!(matches!(result, Err(QrError::Unsupported(_))));
+
+        let result = QrCode::new(Version::Micro(2), EcLevel::L, MaskPattern::Checkerboard);
+        assert!(matches!(result, Err(QrError::Unsupported(_))));
+
+        let result = QrCode::new(Version::Micro(5), EcLevel::L, MaskPattern::Horizontal);
+        assert_eq!(
+            result.err(),
+            Some(QrError::InvalidVersion(Version::Micro(5)))
+        );
+    }
+
+    #[test]
+    fn rmqr_patterns_leave_the_codewords_and_remainder_bits() {
+        // Remainder bits of each version, by version indicator
+        const REMAINDER_BITS: [usize; 32] = [
+            0, 3, 5, 6, 1, 2, 3, 1, 4, 5, 2, 1, 0, 2, 7, 6, 4, 1, 6, 4, 3, 0, 1, 4, 6, 7, 2, 1, 2,
+            0, 3, 4,
+        ];
+
+        for (version, remainder_bits) in Version::all().filter(Version::is_rmqr).zip(REMAINDER_BITS)
+        {
+            let mut qr = QrCode::new(version, EcLevel::M, MaskPattern::RMQR).unwrap();
+            qr.all_functional_patterns();
+            assert_eq!(qr.data.len() as u32, qr.width() * qr.height());
+
+            let blocks = crate::ec::block_lengths(version, &EcLevel::M);
+            let codewords = blocks.iter().sum::<usize>()
+                + blocks.len() * version.ec_codewords(EcLevel::M).unwrap();
+            assert_eq!(
+                qr.data_positions().len(),
+                codewords * 8 + remainder_bits,
+                "{}",
+                version
+            );
+        }
+    }
+
+    #[test]
+    fn rmqr_patterns_draw_the_corners() {
+        let mut qr = QrCode::new(Version::Rmqr(11, 27), EcLevel::H, MaskPattern::RMQR).unwrap();
+        qr.all_functional_patterns();
+
+        // Sub-finder pattern centre and ring
+        assert!(matches!(qr.get(24, 8), Some(One(true))));
+        assert!(matches!(qr.get(23, 8), Some(Zero(true))));
+        // Corner finder patterns
+        assert!(matches!(qr.get(25, 0), Some(One(true))));
+        assert!(matches!(qr.get(25, 1), Some(Zero(true))));
+        assert!(matches!(qr.get(1, 9), Some(Zero(true))));
+        assert!(matches!(qr.get(2, 10), Some(One(true))));
+
+        let result = QrCode::new(Version::Rmqr(11, 27), EcLevel::Q, MaskPattern::RMQR);
+        assert!(matches!(result, Err(QrError::Unsupported(_))));
+        let result = QrCode::new(Version::Rmqr(11, 27), EcLevel::M, MaskPattern::Diagonal);
+        assert!(matches!(result, Err(QrError::Unsupported(_))));
+    }
+
+    #[test]
+    fn apply_best_mask_keeps_lowest_score() {
+        let mut qr =
+            QrCode::new(Version::Normal(2), EcLevel::M, MaskPattern::Checkerboard).unwrap();
+        qr.all_functional_patterns();
+        qr.fill(&Bit::from(0x1234_5678, 32, false, true).repeat(12));
+
+        let scores = qr.apply_best_mask();
+
+        let best = *scores.iter().min().unwrap();
+        assert_eq!(scores[qr.mask_pattern().ordinal() as usize], best);
+        assert_eq!(qr.penalty(), best);
+        assert_eq!(qr.mask_scores(), Some(scores));
+    }
+
+    #[test]
+    fn apply_best_mask_writes_matching_format_information() {
+        let mut qr =
+            QrCode::new(Version::Normal(1), EcLevel::L, MaskPattern::Checkerboard).unwrap();
+        qr.all_functional_patterns();
+        qr.fill(&[Bit::One(false); 208]);
+        qr.apply_best_mask();
+
+        let mut expected = qr.clone();
+        expected.data.fill(Bit::Zero(false));
+        expected.all_functional_patterns();
+
+        for x in 0..9 {
+            assert_eq!(qr.get(x, 8), expected.get(x, 8));
+        }
+    }
+
+    /// "HELLO WORLD" in version 7 at ec level M with mask 4, as drawn by the qrcodegen crate,
+    /// `#` being a dark module.
+    const REFERENCE_SYMBOL: [&str; 45] = [
+        "#######.#.#.##....#..####...#.#.##..#.#######",
+        "#.....#..##..#....##..#.#########..#..#.....#",
+        "#.###.#..##..####..##.......#.##...#..#.###.#",
+        "#.###.#.#.#...##.#.#.###.#.####....##.#.###.#",
+        "#.###.#.##.##...############.#..#.###.#.###.#",
+        "#.....#.##.#....##.##...#.#...###.....#.....#",
+        "#######.#.#.#.#.#.#.#.#.#.#.#.#.#.#.#.#######",
+        "........##..##.######...#..###......#........",
+        "#...#.###..####.##.######.#####...########..#",
+        "..#..#..#.##.#....###..####.#.##.##.#..#..#..",
+        "###.###...#...###.##..##.#.....####.........#",
+        "..##....##....#####..##..####.##.###...###...",
+        "#...#.####.##.#.##..##..##.##..###..#.#######",
+        ".#.#....####.#....#####.#...##..#..#.##.##.##",
+        "#..##.###..####.##...#..#....#....####...####",
+        "######.#.#####.#...##..##...#.#.....###.#.#.#",
+        "##..###....####...##...#..#.......#.##....#..",
+        ".....#.##..#.#.####..#######.#.#.##.#..#..#.#",
+        "###...###.#....##.##.#.#.#.###.#####...##....",
+        ".####..####..#...##......#####...##....###.#.",
+        "#.#.#####..#.##..#..######.####..#.##########",
+        "..###...#.###..#..###...#...#.##....#...#####",
+        "..###.#.#..####..#..#.#.#......##.###.#.#.###",
+        "##..#...####.##.....#...###.#.##.####...###.#",
+        "....#####.........########.....###.######.#..",
+        "....##.....#.#######....#..#.#..#...#.####...",
+        "..#.###.###....##..#.###...###....##...#.##.#",
+        "#####..#..#...#..#.##.#.#...##.....###....##.",
+        "#..####.#.###...#...#.....#..##...###.#.#.#.#",
+        ".##.#....#.#..#....#.#.#.###..##.###.##...#.#",
+        "..##.###.#.#.##.####...#.#.##..####.###......",
+        "##..#...########...###..######.#.###.#..##.#.",
+        "..##.##..##....##....##..#.#######.....#.####",
+        "..#.##....##.###...##..#....#.#.#...##...#.##",
+        "....#.#.###..#..##..###...........#####.#####",
+        ".####..#.##...##.#....##....#.##.......##.#.#",
+        "#..##.#...##.#..#..######.#....##.#.#####.#..",
+        "........#.##..##.#.##...####.#..###.#...##..#",
+        "#######.##.#....###.#.#.##.###...####.#.#....",
+        "#.....#..#####.##..##...#..###......#...##.#.",
+        "#.###.#.###.##.##...#####.#####...#.#########",
+        "#.###.#...#..###...#...####.#.##.##.###...###",
+        "#.###.#......##.#..##.##.#.....####.##.......",
+        "#.....#..#.#...#...#.###.####.##.####..#.#..#",
+        "#######.##.#...#...###.#.#.##..###.#..#######",
+    ];
+
+    #[test]
+    fn encoding_matches_a_reference_symbol() {
+        let segments = [Segment::new(Encoding::Alphanumeric, "HELLO WORLD").unwrap()];
+        let qrcode = Preprocessor::from_segments(
+            &segments,
+            Version::Normal(7),
+            EcLevel::M,
+            MaskSelection::Fixed(MaskPattern::LargeCheckerboard),
+        )
+        .and_then(|preprocessor| preprocessor.generate_qrcode())
+        .unwrap();
+        for (y, expected) in REFERENCE_SYMBOL.iter().enumerate() {
+            let row: String = (0..45)
+                .map(|x| match qrcode.data[x + 45 * y].value() {
+                    true => '#',
+                    false => '.',
+                })
+                .collect();
+            assert_eq!(&row, expected, "row {}", y);
+        }
+    }
+
+    #[test]
+    fn decode_reads_a_reference_symbol() {
+        let modules: Vec<bool> = REFERENCE_SYMBOL
+            .iter()
+            .flat_map(|row| row.chars().map(|module| module == '#'))
+            .collect();
+        let decoded = decode_modules(&modules, 45).unwrap();
+        assert_eq!(decoded.version, Version::Normal(7));
+        assert_eq!(decoded.text().unwrap(), "HELLO WORLD");
+    }
+
+    #[test]
+    fn version_information_is_placed_least_significant_bit_first() {
+        let mut qrcode =
+            QrCode::new(Version::Normal(7), EcLevel::M, MaskPattern::Checkerboard).unwrap();
+        qrcode.all_functional_patterns();
+        let dark = |x: u32, y: u32| qrcode.get(x, y).unwrap().value();
+        // Version 7 is 000111 110010 010100
+        for i in 0..18 {
+            let bit = (0x07C94 >> i) & 1 == 1;
+            assert_eq!(dark(i / 3, 34 + i % 3), bit, "bottom left bit {}", i);
+            assert_eq!(dark(34 + i % 3, i / 3), bit, "top right bit {}", i);
+        }
+    }
+
+    #[test]
+    fn mask_4_groups_two_rows_and_three_columns() {
+        let mask = MaskPattern::LargeCheckerboard.get_mask();
+        assert!(mask(0, 0) && mask(2, 1) && mask(3, 2));
+        assert!(!mask(3, 0) && !mask(0, 2) && !mask(2, 3));
+    }
 }
@@ -0,0 +1,483 @@
+use crate::bit::Bit;
+use crate::eci::Charset;
+use crate::encoding::Encoding;
+use crate::error::QrError;
+use crate::fnc1::{escape_alphanumeric, GS};
+use crate::version::Version;
+
+const MODES: [Encoding; 4] = [
+    Encoding::Numeric,
+    Encoding::Alphanumeric,
+    Encoding::Byte,
+    Encoding::Kanji,
+];
+
+/// A run of characters encoded in a single mode, with its own mode indicator and count.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Segment {
+    pub encoding: Encoding,
+    /// Payload bytes: ASCII digits or characters for the numeric and alphanumeric modes, the
+    /// bytes of byte mode and the Shift JIS double bytes of kanji mode.
+    pub data: Vec<u8>,
+    /// Character set of byte mode data, ISO-8859-1 for the other modes.
+    pub charset: Charset,
+}
+
+impl Segment {
+    pub fn new(encoding: Encoding, data: &str) -> Result<Segment, QrError> {
+        Self::with_charset(encoding, data, Charset::Iso8859_1)
+    }
+
+    /// Segment of `data`, transcoded to `charset` in byte mode.
+    pub fn with_charset(
+        encoding: Encoding,
+        data: &str,
+        charset: Charset,
+    ) -> Result<Segment, QrError> {
+        Ok(match encoding {
+            Encoding::Byte => Segment {
+                encoding,
+                data: charset.encode(data)?,
+                charset,
+            },
+            _ => Segment {
+                encoding,
+                data: encoding.to_bytes(data)?,
+                charset: Charset::Iso8859_1,
+            },
+        })
+    }
+
+    /// Byte mode segment holding `data` as is.
+    pub fn binary(data: &[u8]) -> Segment {
+        Segment {
+            encoding: Encoding::Byte,
+            data: data.to_vec(),
+            charset: Charset::Iso8859_1,
+        }
+    }
+
+    /// Payload decoded as text, or `None` if byte mode data is not valid in its charset.
+    pub fn text(&self) -> Option<String> {
+        match self.encoding {
+            Encoding::Numeric | Encoding::Alphanumeric => String::from_utf8(self.data.clone()).ok(),
+            Encoding::Byte => self.charset.decode(&self.data),
+            Encoding::Kanji => Charset::ShiftJis.decode(&self.data),
+        }
+    }
+
+    /// Mode indicator, character count indicator and encoded data for the given version.
+    pub fn bits(&self, version: Version) -> Result<Vec<Bit>, QrError> {
+        let char_count = self.encoding.byte_char_count(&self.data);
+        let char_count_bits = self.encoding.char_count_bits(version)?;
+
+        let mut bits = self.encoding.mode_indicator(version)?;
+        bits.append(&mut Bit::from(
+            char_count as u32,
+            char_count_bits,
+            false,
+            true,
+        ));
+        bits.append(&mut self.encoding.encode_bytes(&self.data)?);
+
+        Ok(bits)
+    }
+
+    /// Bit stream of a list of segments in the given version.
+    ///
+    /// An ECI header is inserted before each byte segment whose charset is not the one in
+    /// effect, starting from the default ISO-8859-1. Micro QR codes have no ECI.
+    pub fn stream(segments: &[Segment], version: Version) -> Result<Vec<Bit>, QrError> {
+        let mut charset = Charset::Iso8859_1;
+        let mut bits = vec![];
+
+        for segment in segments {
+            if segment.encoding == Encoding::Byte && segment.charset != charset {
+                if version.is_micro() {
+                    return Err(QrError::Unsupported(format!(
+                        "{} data needs an ECI header, not available in version {}",
+                        segment.charset.name(),
+                        version
+                    )));
+                }
+                charset = segment.charset;
+                bits.append(&mut charset.header(version));
+            }
+            bits.append(&mut segment.bits(version)?);
+        }
+
+        Ok(bits)
+    }
+
+    /// Total number of bits taken by a list of segments in the given version, ECI headers
+    /// included.
+    pub fn total_bits(segments: &[Segment], version: Version) -> Result<usize, QrError> {
+        Ok(Self::stream(segments, version)?.len())
+    }
+}
+
+/// Cost of one character in sixths of a bit, so that the fractional sizes of the numeric
+/// (10 bits for 3 digits) and alphanumeric (11 bits for 2 characters) modes stay exact.
+///
+/// `None` if the mode cannot encode the character.
+fn char_cost(encoding: Encoding, c: char, charset: Charset, fnc1: bool) -> Option<usize> {
+    let unit = match encoding {
+        Encoding::Numeric => 20,
+        Encoding::Alphanumeric => 33,
+        Encoding::Byte => 48,
+        Encoding::Kanji => 39,
+    };
+    char_bytes(encoding, c, charset, fnc1).map(|bytes| bytes.len() * unit)
+}
+
+/// Payload bytes of a character. With FNC1, alphanumeric mode writes the separator as `%` and
+/// doubles `%`.
+fn char_bytes(encoding: Encoding, c: char, charset: Charset, fnc1: bool) -> Option<Vec<u8>> {
+    match encoding {
+        Encoding::Alphanumeric if fnc1 && (c == GS || c == '%') => {
+            Some(escape_alphanumeric(&c.to_string()).into_bytes())
+        }
+        Encoding::Byte => charset.encode_char(c),
+        _ => encoding.char_bytes(c),
+    }
+}
+
+/// Splits `data` into the segments giving the shortest bit stream for the given version.
+///
+/// The split only depends on the width of the mode and character count indicators, so every
+/// version of the same range (1 - 9, 10 - 26, 27 - 40) yields the same segments. Modes that are
+/// not available in a Micro QR version are left out.
+pub fn optimal_segments(data: &str, version: Version) -> Result<Vec<Segment>, QrError> {
+    optimal_segments_in(data, version, Charset::Iso8859_1)
+}
+
+/// Same as [`optimal_segments`], with the byte segments transcoded to `charset`.
+pub fn optimal_segments_in(
+    data: &str,
+    version: Version,
+    charset: Charset,
+) -> Result<Vec<Segment>, QrError> {
+    optimal_segments_with(data, version, charset, false)
+}
+
+/// Same as [`optimal_segments_in`] for FNC1 data, whose [`GS`] separators take a byte in byte
+/// mode or a `%` in alphanumeric mode, where a literal `%` is doubled.
+pub fn optimal_fnc1_segments_in(
+    data: &str,
+    version: Version,
+    charset: Charset,
+) -> Result<Vec<Segment>, QrError> {
+    optimal_segments_with(data, version, charset, true)
+}
+
+fn optimal_segments_with(
+    data: &str,
+    version: Version,
+    charset: Charset,
+    fnc1: bool,
+) -> Result<Vec<Segment>, QrError> {
+    let mode_bits = version.mode_bits()?;
+    let chars: Vec<char> = data.chars().collect();
+    if chars.is_empty() {
+        return Ok(vec![]);
+    }
+
+    // Unavailable modes cost usize::MAX
+    let head_costs = MODES.map(|mode| {
+        mode.char_count_bits(version)
+            .map_or(usize::MAX, |bits| (mode_bits + bits as usize) * 6)
+    });
+
+    // char_modes[i][m]: mode of the i-th character when the stream up to it ends in mode m
+    let mut char_modes: Vec<[Option<Encoding>; 4]> = Vec::with_capacity(chars.len());
+    let mut prev_costs = head_costs;
+
+    for (position, &c) in chars.iter().enumerate() {
+        let mut modes = [None; 4];
+        let mut costs = [usize::MAX; 4];
+
+        // Extend the current segment of each mode
+        for (m, mode) in MODES.iter().enumerate() {
+            if prev_costs[m] == usize::MAX {
+                continue;
+            }
+            if let Some(cost) = char_cost(*mode, c, charset, fnc1) {
+                costs[m] = prev_costs[m] + cost;
+                modes[m] = Some(*mode);
+            }
+        }
+
+        if modes.iter().all(|mode| mode.is_none()) {
+            // Byte mode is the most permissive one, then alphanumeric mode in Micro QR codes
+            let mode = [Encoding::Byte, Encoding::Alphanumeric, Encoding::Numeric]
+                .into_iter()
+                .find(|mode| mode.char_count_bits(version).is_ok())
+                .unwrap();
+            return Err(QrError::InvalidCharacter {
+                ch: c,
+                position,
+                mode,
+            });
+        }
+
+        // Close the segment and start a new one in another mode
+        let extended = costs;
+        for to in 0..MODES.len() {
+            if head_costs[to] == usize::MAX {
+                continue;
+            }
+            for from in 0..MODES.len() {
+                if modes[from].is_none() || extended[from] == usize::MAX {
+                    continue;
+                }
+                let cost = extended[from].div_ceil(6) * 6 + head_costs[to];
+                if cost < costs[to] {
+                    costs[to] = cost;
+                    modes[to] = Some(MODES[from]);
+                }
+            }
+        }
+
+        char_modes.push(modes);
+        prev_costs = costs;
+    }
+
+    let (best, _) = prev_costs
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, &cost)| cost)
+        .unwrap();
+
+    // Walk back from the cheapest final mode to recover the mode of each character
+    let mut current = MODES[best];
+    let mut modes = vec![current; chars.len()];
+    for i in (0..chars.len()).rev() {
+        let index = MODES.iter().position(|mode| *mode == current).unwrap();
+        current = char_modes[i][index].unwrap();
+        modes[i] = current;
+    }
+
+    let mut segments: Vec<Segment> = vec![];
+    for (c, mode) in chars.iter().zip(modes) {
+        // Every character was checked to be encodable in its mode
+        let bytes = char_bytes(mode, *c, charset, fnc1).unwrap();
+        match segments.last_mut() {
+            Some(segment) if segment.encoding == mode => segment.data.extend_from_slice(&bytes),
+            _ => segments.push(Segment {
+                encoding: mode,
+                data: bytes,
+                charset: match mode {
+                    Encoding::Byte => charset,
+                    _ => Charset::Iso8859_1,
+                },
+            }),
+        }
+    }
+
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_versions_are_errors() {
+        let segment = Segment::new(Encoding::Byte, "data").unwrap();
+        for version in [
+            Version::Normal(0),
+            Version::Normal(41),
+            Version::Micro(0),
+            Version::Rmqr(7, 27),
+        ] {
+            assert_eq!(segment.bits(version), Err(QrError::InvalidVersion(version)));
+            assert_eq!(
+                optimal_segments(" data", version),
+                Err(QrError::InvalidVersion(version))
+            );
+        }
+        assert!(matches!(
+            Encoding::Byte.char_count_bits(Version::Micro(1)),
+            Err(QrError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn optimal_segments_keeps_single_mode_input() {
+        let segments = optimal_segments("0123456789", Version::Normal(1)).unwrap();
+        assert_eq!(
+            segments,
+            vec![Segment::new(Encoding::Numeric, "0123456789").unwrap()]
+        );
+    }
+
+    #[test]
+    fn optimal_segments_handles_empty_input() {
+        assert_eq!(optimal_segments("", Version::Normal(1)).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn optimal_segments_splits_long_digit_runs() {
+        let segments = optimal_segments("ORDER 000123456789 - café", Version::Normal(1)).unwrap();
+        assert_eq!(
+            segments,
+            vec![
+                Segment::new(Encoding::Alphanumeric, "ORDER ").unwrap(),
+                Segment::new(Encoding::Numeric, "000123456789").unwrap(),
+                Segment::new(Encoding::Byte, " - café").unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn optimal_segments_does_not_split_short_digit_runs() {
+        let segments = optimal_segments("a1b", Version::Normal(1)).unwrap();
+        assert_eq!(segments, vec![Segment::new(Encoding::Byte, "a1b").unwrap()]);
+    }
+
+    #[test]
+    fn optimal_segments_uses_kanji_mode() {
+        let segments = optimal_segments("点茗点茗", Version::Normal(1)).unwrap();
+        assert_eq!(
+            segments,
+            vec![Segment::new(Encoding::Kanji, "点茗点茗").unwrap()]
+        );
+    }
+
+    #[test]
+    fn optimal_segments_is_never_longer_than_a_single_segment() {
+        let data = "HELLO WORLD 1234567890 hello";
+        for version in [1, 10, 27].map(Version::Normal) {
+            let mixed = Segment::total_bits(&optimal_segments(data, version).unwrap(), version);
+            let single =
+                Segment::total_bits(&[Segment::new(Encoding::Byte, data).unwrap()], version);
+            assert!(mixed.unwrap() <= single.unwrap());
+        }
+    }
+
+    #[test]
+    fn optimal_segments_rejects_unencodable_characters() {
+        assert_eq!(
+            optimal_segments("A😀", Version::Normal(1)),
+            Err(QrError::InvalidCharacter {
+                ch: '😀',
+                position: 1,
+                mode: Encoding::Byte
+            })
+        );
+    }
+
+    #[test]
+    fn optimal_segments_in_transcodes_byte_segments() {
+        let segments = optimal_segments_in("Ünïcödé ✓", Version::Normal(1), Charset::Utf8).unwrap();
+        assert_eq!(
+            segments,
+            vec![Segment::with_charset(Encoding::Byte, "Ünïcödé ✓", Charset::Utf8).unwrap()]
+        );
+    }
+
+    #[test]
+    fn total_bits_counts_eci_headers_and_transcoded_bytes() {
+        // 12 bits of ECI header, then 2 UTF-8 bytes for é
+        let segments = [Segment::with_charset(Encoding::Byte, "é", Charset::Utf8).unwrap()];
+        assert_eq!(
+            Segment::total_bits(&segments, Version::Normal(1)),
+            Ok(12 + 4 + 8 + 16)
+        );
+
+        // The header is only written when the charset changes
+        let segments = [
+            Segment::with_charset(Encoding::Byte, "é", Charset::Utf8).unwrap(),
+            Segment::new(Encoding::Numeric, "123").unwrap(),
+            Segment::with_charset(Encoding::Byte, "é", Charset::Utf8).unwrap(),
+        ];
+        assert_eq!(
+            Segment::total_bits(&segments, Version::Normal(1)),
+            Ok(12 + 2 * (4 + 8 + 16) + 4 + 10 + 10)
+        );
+    }
+
+    #[test]
+    fn micro_segments_use_short_headers() {
+        // No mode indicator and a 3 bit count in M1, 1 + 4 bits in M2
+        let numeric = Segment::new(Encoding::Numeric, "0123456").unwrap();
+        assert_eq!(numeric.bits(Version::Micro(1)).unwrap().len(), 3 + 24);
+        assert_eq!(numeric.bits(Version::Micro(2)).unwrap().len(), 1 + 4 + 24);
+
+        let byte = Segment::new(Encoding::Byte, "a").unwrap();
+        assert!(matches!(
+            byte.bits(Version::Micro(2)),
+            Err(QrError::Unsupported(_))
+        ));
+        assert_eq!(byte.bits(Version::Micro(3)).unwrap().len(), 2 + 4 + 8);
+    }
+
+    #[test]
+    fn micro_segments_have_no_eci() {
+        let segments = [Segment::with_charset(Encoding::Byte, "é", Charset::Utf8).unwrap()];
+        assert!(matches!(
+            Segment::stream(&segments, Version::Micro(4)),
+            Err(QrError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn optimal_segments_leaves_out_unavailable_modes() {
+        assert_eq!(
+            optimal_segments("AB12", Version::Micro(2)).unwrap(),
+            vec![Segment::new(Encoding::Alphanumeric, "AB12").unwrap()]
+        );
+        assert_eq!(
+            optimal_segments("Ab", Version::Micro(2)),
+            Err(QrError::InvalidCharacter {
+                ch: 'b',
+                position: 1,
+                mode: Encoding::Alphanumeric
+            })
+        );
+    }
+
+    #[test]
+    fn binary_segment_keeps_bytes() {
+        let segment = Segment::binary(&[0x00, 0xFF, 0x80]);
+        assert_eq!(segment.bits(Version::Normal(1)).unwrap().len(), 4 + 8 + 24);
+        assert_eq!(segment.text().as_deref(), Some("\0ÿ\u{80}"));
+    }
+
+    #[test]
+    fn kanji_segment_holds_shift_jis_bytes() {
+        let segment = Segment::new(Encoding::Kanji, "点茗").unwrap();
+        assert_eq!(segment.data, vec![0x93, 0x5F, 0xE4, 0xAA]);
+        assert_eq!(segment.bits(Version::Normal(1)).unwrap().len(), 4 + 8 + 26);
+        assert_eq!(segment.text().as_deref(), Some("点茗"));
+    }
+
+    #[test]
+    fn bits_includes_mode_and_char_count_indicator() {
+        let segment = Segment::new(Encoding::Numeric, "01234567").unwrap();
+        assert_eq!(segment.bits(Version::Normal(1)).unwrap().len(), 4 + 10 + 27);
+        assert_eq!(
+            segment.bits(Version::Normal(10)).unwrap().len(),
+            4 + 12 + 27
+        );
+        assert_eq!(
+            segment.bits(Version::Normal(27)).unwrap().len(),
+            4 + 14 + 27
+        );
+    }
+
+    #[test]
+    fn optimal_fnc1_segments_escape_separators_in_alphanumeric_mode() {
+        let data = "10ABC\u{1D}DEF%";
+        let segments =
+            optimal_fnc1_segments_in(data, Version::Normal(1), Charset::Iso8859_1).unwrap();
+        assert_eq!(
+            segments,
+            vec![Segment::new(Encoding::Alphanumeric, "10ABC%DEF%%").unwrap()]
+        );
+
+        // Without FNC1, the separator only fits in byte mode
+        let segments = optimal_segments(data, Version::Normal(1)).unwrap();
+        assert_eq!(segments, vec![Segment::new(Encoding::Byte, data).unwrap()]);
+    }
+}
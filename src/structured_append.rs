@@ -0,0 +1,105 @@
+use crate::bit::Bit;
+use crate::fnc1::payload_bytes;
+use crate::segment::Segment;
+
+/// Structured Append header of a symbol: its position in a sequence of up to 16 symbols
+/// holding a single message, and the parity of that message.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StructuredAppend {
+    /// Position of the symbol in the sequence, from 0.
+    pub index: u8,
+    /// Number of symbols in the sequence, from 2 to 16.
+    pub total: u8,
+    /// XOR of the payload bytes of the whole message.
+    pub parity: u8,
+}
+
+impl StructuredAppend {
+    /// Largest number of symbols in a sequence.
+    pub const MAX_SYMBOLS: usize = 16;
+
+    /// Length of the header: mode indicator, symbol sequence indicator and parity.
+    pub const HEADER_BITS: usize = 20;
+
+    /// Mode indicator 0011, then the index and the total minus 1 on 4 bits each and the parity
+    /// byte.
+    pub fn header(&self) -> Vec<Bit> {
+        let mut bits = Bit::from(0b0011, 4, false, true);
+        bits.extend(Bit::from(self.index as u32, 4, false, true));
+        bits.extend(Bit::from(self.total as u32 - 1, 4, false, true));
+        bits.extend(Bit::from(self.parity as u32, 8, false, true));
+        bits
+    }
+}
+
+/// XOR of the payload bytes of the segments, kanji being Shift JIS double bytes. With `fnc1`,
+/// the `%` escapes of alphanumeric segments count as the bytes they stand for.
+pub fn parity<'a>(segments: impl IntoIterator<Item = &'a Segment>, fnc1: bool) -> u8 {
+    segments
+        .into_iter()
+        .flat_map(|segment| payload_bytes(segment, fnc1))
+        .fold(0, |parity, byte| parity ^ byte)
+}
+
+/// Splits `items` into `count` runs whose lengths differ by at most 1, the longer ones first.
+pub(crate) fn balanced_split<T>(items: &[T], count: usize) -> Vec<&[T]> {
+    let (length, longer) = (items.len() / count, items.len() % count);
+    let mut rest = items;
+
+    (0..count)
+        .map(|index| {
+            let (run, tail) = rest.split_at(length + usize::from(index < longer));
+            rest = tail;
+            run
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::Encoding;
+
+    #[test]
+    fn header_packs_the_sequence_indicator() {
+        let header = StructuredAppend {
+            index: 2,
+            total: 4,
+            parity: 0xA5,
+        };
+        let bits: String = header
+            .header()
+            .iter()
+            .map(|bit| if bit.value() { '1' } else { '0' })
+            .collect();
+        assert_eq!(bits, "0011".to_string() + "0010" + "0011" + "10100101");
+        assert_eq!(bits.len(), StructuredAppend::HEADER_BITS);
+    }
+
+    #[test]
+    fn parity_covers_every_segment() {
+        let segments = [
+            Segment::new(Encoding::Numeric, "12").unwrap(),
+            Segment::binary(&[0xFF]),
+        ];
+        assert_eq!(parity(&segments, false), b'1' ^ b'2' ^ 0xFF);
+        assert_eq!(parity(&[], false), 0);
+
+        // FNC1 separators count as GS, and a doubled `%` as one
+        let segments = [Segment::new(Encoding::Alphanumeric, "1%2%%").unwrap()];
+        assert_eq!(parity(&segments, false), b'1' ^ b'2' ^ b'%');
+        assert_eq!(parity(&segments, true), b'1' ^ 0x1D ^ b'2' ^ b'%');
+    }
+
+    #[test]
+    fn balanced_split_keeps_lengths_within_one() {
+        let items: Vec<u32> = (0..10).collect();
+        let lengths: Vec<usize> = balanced_split(&items, 4)
+            .iter()
+            .map(|run| run.len())
+            .collect();
+        assert_eq!(lengths, [3, 3, 2, 2]);
+        assert_eq!(balanced_split(&items, 4).concat(), items);
+        assert_eq!(balanced_split(&items, 1), [&items[..]]);
+    }
+}
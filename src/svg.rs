@@ -0,0 +1,286 @@
+use crate::color::Color;
+use crate::qr_bill::swiss_cross;
+use crate::qrcode::QrCode;
+use std::fmt::Write;
+
+/// Renders a [`QrCode`] as an SVG document.
+///
+/// The dark modules of each row are merged into horizontal runs and the runs into a single
+/// path, which keeps large versions small.
+pub struct SvgRenderer<'a> {
+    qrcode: &'a QrCode,
+    module_size: u32,
+    quiet_zone: u32,
+    foreground: Color,
+    background: Color,
+    title: Option<String>,
+    description: Option<String>,
+    swiss_cross: bool,
+}
+
+impl QrCode {
+    pub fn svg(&self) -> SvgRenderer<'_> {
+        SvgRenderer::new(self)
+    }
+}
+
+impl<'a> SvgRenderer<'a> {
+    pub fn new(qrcode: &'a QrCode) -> SvgRenderer<'a> {
+        SvgRenderer {
+            qrcode,
+            module_size: 8,
+            quiet_zone: qrcode.version().quiet_zone(),
+            foreground: Color::BLACK,
+            background: Color::WHITE,
+            title: None,
+            description: None,
+            swiss_cross: false,
+        }
+    }
+
+    /// Size of a module in user units (pixels by default).
+    pub fn module_size(mut self, module_size: u32) -> Self {
+        self.module_size = module_size;
+        self
+    }
+
+    /// Width of the quiet zone in modules. By default 4 for regular QR codes, and 2 for Micro
+    /// QR and rMQR codes.
+    pub fn quiet_zone(mut self, quiet_zone: u32) -> Self {
+        self.quiet_zone = quiet_zone;
+        self
+    }
+
+    pub fn foreground(mut self, foreground: Color) -> Self {
+        self.foreground = foreground;
+        self
+    }
+
+    pub fn background(mut self, background: Color) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// Draws the Swiss cross of QR-bills in the centre, 7 mm wide for a symbol of 46 mm. Its
+    /// light parts use the background colour, or white if the background is not opaque.
+    pub fn swiss_cross(mut self, swiss_cross: bool) -> Self {
+        self.swiss_cross = swiss_cross;
+        self
+    }
+
+    pub fn render(&self) -> String {
+        let total_x = self.qrcode.width() + 2 * self.quiet_zone;
+        let total_y = self.qrcode.height() + 2 * self.quiet_zone;
+        let (width, height) = (total_x * self.module_size, total_y * self.module_size);
+
+        let mut svg = String::new();
+        svg.push_str(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+        svg.push('\n');
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" version="1.1" width="{width}" height="{height}" viewBox="0 0 {total_x} {total_y}" shape-rendering="crispEdges">"#
+        );
+
+        if let Some(title) = &self.title {
+            let _ = writeln!(svg, "<title>{}</title>", escape(title));
+        }
+        if let Some(description) = &self.description {
+            let _ = writeln!(svg, "<desc>{}</desc>", escape(description));
+        }
+
+        if !self.background.is_transparent() {
+            let _ = writeln!(
+                svg,
+                r#"<rect width="{total_x}" height="{total_y}"{}/>"#,
+                fill(self.background)
+            );
+        }
+
+        if !self.foreground.is_transparent() {
+            let _ = writeln!(
+                svg,
+                r#"<path d="{}"{}/>"#,
+                self.path(),
+                fill(self.foreground)
+            );
+        }
+
+        if self.swiss_cross {
+            self.write_swiss_cross(&mut svg);
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    fn write_swiss_cross(&self, svg: &mut String) {
+        let light = match self.background.a {
+            255 => self.background,
+            _ => Color::WHITE,
+        };
+        // Coordinates are rounded to a thousandth of a module
+        let round = |value: f64| (value * 1000.0).round() / 1000.0;
+        let quiet_zone = self.quiet_zone as f64;
+
+        for rect in swiss_cross(self.qrcode.width(), self.qrcode.height()) {
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="{}" height="{}"{}/>"#,
+                round(rect.x + quiet_zone),
+                round(rect.y + quiet_zone),
+                round(rect.width),
+                round(rect.height),
+                fill(if rect.dark { self.foreground } else { light })
+            );
+        }
+    }
+
+    /// One `M x,y h w v1 h-w z` rectangle for each horizontal run of dark modules.
+    fn path(&self) -> String {
+        let width = self.qrcode.width() as usize;
+        let mut path = String::new();
+
+        for (y, row) in self.qrcode.data.chunks(width).enumerate() {
+            let mut x = 0;
+            for run in row.chunk_by(|a, b| a.value() == b.value()) {
+                if run[0].value() {
+                    let _ = write!(
+                        path,
+                        "M{},{}h{}v1h-{}z",
+                        x + self.quiet_zone as usize,
+                        y + self.quiet_zone as usize,
+                        run.len(),
+                        run.len()
+                    );
+                }
+                x += run.len();
+            }
+        }
+
+        path
+    }
+}
+
+fn fill(color: Color) -> String {
+    if color.a == 255 {
+        format!(r#" fill="{}""#, color.hex())
+    } else {
+        format!(
+            r#" fill="{}" fill-opacity="{:.3}""#,
+            color.hex(),
+            color.a as f32 / 255.0
+        )
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ec::EcLevel;
+    use crate::encoding::Encoding;
+    use crate::mask::MaskSelection;
+    use crate::preprocessor::Preprocessor;
+
+    fn qrcode() -> QrCode {
+        Preprocessor::new(
+            "HELLO WORLD",
+            Encoding::Alphanumeric,
+            EcLevel::M,
+            MaskSelection::Auto,
+        )
+        .unwrap()
+        .generate_qrcode()
+        .unwrap()
+    }
+
+    #[test]
+    fn render_uses_module_size_and_quiet_zone() {
+        let svg = qrcode().svg().module_size(10).quiet_zone(2).render();
+        assert!(svg.contains(r#"width="250" height="250" viewBox="0 0 25 25""#));
+    }
+
+    #[test]
+    fn render_merges_runs_of_dark_modules() {
+        let svg = qrcode().svg().render();
+        // The top row starts with the 7 modules of the finder pattern
+        assert!(svg.contains("M4,4h7v1h-7z"));
+        assert_eq!(svg.matches("<path").count(), 1);
+    }
+
+    #[test]
+    fn render_omits_transparent_background() {
+        let svg = qrcode().svg().background(Color::TRANSPARENT).render();
+        assert!(!svg.contains("<rect"));
+    }
+
+    #[test]
+    fn render_writes_colors() {
+        let svg = qrcode()
+            .svg()
+            .foreground(Color::rgba(255, 0, 0, 128))
+            .background(Color::rgb(0, 0, 255))
+            .render();
+        assert!(svg.contains(r##"fill="#ff0000" fill-opacity="0.502""##));
+        assert!(svg.contains(r##"fill="#0000ff""##));
+    }
+
+    #[test]
+    fn render_escapes_title_and_description() {
+        let svg = qrcode()
+            .svg()
+            .title("Tom & Jerry")
+            .description("<b>")
+            .render();
+        assert!(svg.contains("<title>Tom &amp; Jerry</title>"));
+        assert!(svg.contains("<desc>&lt;b&gt;</desc>"));
+    }
+
+    #[test]
+    fn render_draws_the_swiss_cross() {
+        let svg = qrcode()
+            .svg()
+            .background(Color::TRANSPARENT)
+            .swiss_cross(true)
+            .render();
+        // A square of 21 * 7 / 46 modules in the centre of the symbol, then the black square
+        assert!(svg.contains(
+            r##"<rect x="12.902" y="12.902" width="3.196" height="3.196" fill="#ffffff"/>"##
+        ));
+        assert!(svg.contains(
+            r##"<rect x="13.13" y="13.13" width="2.739" height="2.739" fill="#000000"/>"##
+        ));
+        assert_eq!(svg.matches("<rect").count(), 4);
+
+        // Centred on each axis of a rectangular symbol, with a quiet zone of 2
+        let qrcode = crate::builder::QrCodeBuilder::new("HELLO")
+            .rmqr_version(11, 43)
+            .build()
+            .unwrap();
+        let svg = qrcode
+            .svg()
+            .background(Color::TRANSPARENT)
+            .swiss_cross(true)
+            .render();
+        assert!(svg.contains(
+            r##"<rect x="22.663" y="6.663" width="1.674" height="1.674" fill="#ffffff"/>"##
+        ));
+    }
+}
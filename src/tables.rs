@@ -10,34 +10,172 @@ pub(crate) const BYTE_CHAR_COUNT: [u8; 3] = [8, 16, 16];
 /// 0: version 1 - 9, 1: version 10 - 26, 2: version 27 - 40
 pub(crate) const KANJI_CHAR_COUNT: [u8; 3] = [8, 10, 12];
 
+/// Micro QR versions M1 to M4, 0 when the mode is not available
+pub(crate) const NUMERIC_MICRO_CHAR_COUNT: [u8; 4] = [3, 4, 5, 6];
+
+/// Micro QR versions M1 to M4, 0 when the mode is not available
+pub(crate) const ALPHANUMERIC_MICRO_CHAR_COUNT: [u8; 4] = [0, 3, 4, 5];
+
+/// Micro QR versions M1 to M4, 0 when the mode is not available
+pub(crate) const BYTE_MICRO_CHAR_COUNT: [u8; 4] = [0, 0, 4, 5];
+
+/// Micro QR versions M1 to M4, 0 when the mode is not available
+pub(crate) const KANJI_MICRO_CHAR_COUNT: [u8; 4] = [0, 0, 3, 4];
+
+/// Number of data bits of the Micro QR versions M1 to M4 per ec level ordinal (L, M, Q), 0 when
+/// the level is not available. The last data codeword of M1 and M3 only has 4 bits.
+pub(crate) const MICRO_DATA_BITS: [[usize; 3]; 4] =
+    [[20, 0, 0], [40, 32, 0], [84, 68, 0], [128, 112, 80]];
+
+/// Number of ec codewords of the Micro QR versions M1 to M4 per ec level ordinal (L, M, Q), all
+/// in a single block.
+pub(crate) const MICRO_EC_BYTES: [[usize; 3]; 4] = [[2, 0, 0], [5, 6, 0], [6, 8, 0], [8, 10, 14]];
+
+/// Format information (ec level and mask pattern with BCH error correction, already masked).
+/// Index: 8 * ec level ordinal + mask pattern ordinal
+#[rustfmt::skip]
+pub(crate) const FORMAT_BITS: [u32; 32] = [
+    0x77C4, 0x72F3, 0x7DAA, 0x789D, 0x662F, 0x6318, 0x6C41, 0x6976, 0x5412, 0x5125, 0x5E7C,
+    0x5B4B, 0x45F9, 0x40CE, 0x4F97, 0x4AA0, 0x355F, 0x3068, 0x3F31, 0x3A06, 0x24B4, 0x2183,
+    0x2EDA, 0x2BED, 0x1689, 0x13BE, 0x1CE7, 0x19D0, 0x762, 0x255, 0xD0C, 0x83B,
+];
+
+/// Micro QR format information (symbol number and Micro QR mask with BCH error correction,
+/// already masked).
+/// Index: 4 * symbol number + Micro QR mask ordinal
+#[rustfmt::skip]
+pub(crate) const MICRO_FORMAT_BITS: [u32; 32] = [
+    0x4445, 0x4172, 0x4E2B, 0x4B1C, 0x55AE, 0x5099, 0x5FC0, 0x5AF7, 0x6793, 0x62A4, 0x6DFD,
+    0x68CA, 0x7678, 0x734F, 0x7C16, 0x7921, 0x06DE, 0x03E9, 0x0CB0, 0x0987, 0x1735, 0x1202,
+    0x1D5B, 0x186C, 0x2508, 0x203F, 0x2F66, 0x2A51, 0x34E3, 0x31D4, 0x3E8D, 0x3BBA,
+];
+
+/// Height and width of the rMQR versions R7x43 to R17x139, indexed by version indicator.
+#[rustfmt::skip]
+pub(crate) const RMQR_SIZES: [(u8, u8); 32] = [
+    (7, 43), (7, 59), (7, 77), (7, 99), (7, 139),
+    (9, 43), (9, 59), (9, 77), (9, 99), (9, 139),
+    (11, 27), (11, 43), (11, 59), (11, 77), (11, 99), (11, 139),
+    (13, 27), (13, 43), (13, 59), (13, 77), (13, 99), (13, 139),
+    (15, 43), (15, 59), (15, 77), (15, 99), (15, 139),
+    (17, 43), (17, 59), (17, 77), (17, 99), (17, 139),
+];
+
+/// Columns of the rMQR alignment patterns, drawn on the top and bottom edges, by symbol width.
+pub(crate) const RMQR_ALIGNMENT_COLUMNS: [(u8, &[u32]); 6] = [
+    (27, &[]),
+    (43, &[21]),
+    (59, &[19, 39]),
+    (77, &[25, 51]),
+    (99, &[23, 49, 75]),
+    (139, &[27, 55, 83, 111]),
+];
+
+/// rMQR versions, indexed by version indicator
+#[rustfmt::skip]
+pub(crate) const NUMERIC_RMQR_CHAR_COUNT: [u8; 32] = [
+    4, 5, 6, 7, 7, 5, 6, 7, 7, 8, 4, 6, 7, 7, 8, 8, 5, 6, 7, 7, 8, 8, 7, 7, 8, 8, 9, 7, 8, 8, 8, 9,
+];
+
+/// rMQR versions, indexed by version indicator
+#[rustfmt::skip]
+pub(crate) const ALPHANUMERIC_RMQR_CHAR_COUNT: [u8; 32] = [
+    3, 5, 5, 6, 6, 5, 5, 6, 6, 7, 4, 5, 6, 6, 7, 7, 5, 6, 6, 7, 7, 8, 6, 7, 7, 7, 8, 6, 7, 7, 8, 8,
+];
+
+/// rMQR versions, indexed by version indicator
 #[rustfmt::skip]
-pub(crate) static SIZE_EC_L: [u32; 40] = [
-    19, 34, 55, 80, 108, 136, 156, 194, 232, 274, 324, 370, 428, 461, 523, 589, 647, 721, 795, 861,
-    932, 1006, 1094, 1174, 1276, 1370, 1468, 1531, 1631, 1735, 1843, 1955, 2071, 2191, 2306, 2434,
-    2566, 2702, 2812, 2956,
+pub(crate) const BYTE_RMQR_CHAR_COUNT: [u8; 32] = [
+    3, 4, 5, 5, 6, 4, 5, 5, 6, 6, 3, 5, 5, 6, 6, 7, 4, 5, 6, 6, 7, 7, 6, 6, 7, 7, 7, 6, 6, 7, 7, 8,
 ];
 
+/// rMQR versions, indexed by version indicator
 #[rustfmt::skip]
-pub(crate) static SIZE_EC_M: [u32; 40] = [
-    16, 28, 44, 64, 86, 108, 124, 154, 182, 216, 254, 290, 334, 365, 415, 453, 507, 563, 627, 669,
-    714, 782, 860, 914, 1000, 1062, 1128, 1193, 1267, 1373, 1455, 1541, 1631, 1725, 1812, 1914,
-    1992, 2102, 2216, 2334,
+pub(crate) const KANJI_RMQR_CHAR_COUNT: [u8; 32] = [
+    2, 3, 4, 5, 5, 3, 4, 5, 5, 6, 2, 4, 5, 5, 6, 6, 3, 5, 5, 6, 6, 7, 5, 5, 6, 6, 7, 5, 6, 6, 6, 7,
 ];
 
+/// Number of ec codewords per block of the rMQR versions per ec level (M, H).
+/// Index: version indicator
 #[rustfmt::skip]
-pub(crate) static SIZE_EC_Q: [u32; 40] = [
-    13, 22, 34, 48, 62, 76, 88, 110, 132, 154, 180, 206, 244, 261, 295, 325, 367, 397, 445, 485,
-    512, 568, 614, 664, 718, 754, 808, 871, 911, 985, 1033, 1115, 1171, 1231, 1286, 1354, 1426,
-    1502, 1582, 1666,
+pub(crate) const RMQR_EC_BYTES_PER_BLOCK: [[usize; 2]; 32] = [
+    [7, 10], [9, 14], [12, 22], [16, 30], [24, 22],
+    [9, 14], [12, 22], [18, 16], [24, 22], [18, 22],
+    [8, 10], [12, 20], [16, 16], [24, 22], [16, 30], [24, 30],
+    [9, 14], [14, 28], [22, 20], [16, 28], [20, 26], [20, 28],
+    [18, 18], [26, 24], [18, 24], [24, 22], [24, 26],
+    [22, 20], [16, 30], [22, 28], [20, 26], [20, 26],
 ];
 
+/// Data codewords per block of the rMQR versions per ec level (M, H): (size of group 1 blocks,
+/// group 1 block count, size of group 2 blocks, group 2 block count).
+/// Index: version indicator
+#[rustfmt::skip]
+pub(crate) static RMQR_DATA_BYTES_PER_BLOCK: [[(usize, usize, usize, usize); 2]; 32] = [
+    [(6, 1, 0, 0), (3, 1, 0, 0)], // R7x43
+    [(12, 1, 0, 0), (7, 1, 0, 0)], // R7x59
+    [(20, 1, 0, 0), (10, 1, 0, 0)], // R7x77
+    [(28, 1, 0, 0), (14, 1, 0, 0)], // R7x99
+    [(44, 1, 0, 0), (12, 2, 0, 0)], // R7x139
+    [(12, 1, 0, 0), (7, 1, 0, 0)], // R9x43
+    [(21, 1, 0, 0), (11, 1, 0, 0)], // R9x59
+    [(31, 1, 0, 0), (8, 1, 9, 1)], // R9x77
+    [(42, 1, 0, 0), (11, 2, 0, 0)], // R9x99
+    [(31, 1, 32, 1), (11, 3, 0, 0)], // R9x139
+    [(7, 1, 0, 0), (5, 1, 0, 0)], // R11x27
+    [(19, 1, 0, 0), (11, 1, 0, 0)], // R11x43
+    [(31, 1, 0, 0), (7, 1, 8, 1)], // R11x59
+    [(43, 1, 0, 0), (11, 1, 12, 1)], // R11x77
+    [(28, 1, 29, 1), (14, 1, 15, 1)], // R11x99
+    [(42, 2, 0, 0), (14, 3, 0, 0)], // R11x139
+    [(12, 1, 0, 0), (7, 1, 0, 0)], // R13x27
+    [(27, 1, 0, 0), (13, 1, 0, 0)], // R13x43
+    [(38, 1, 0, 0), (10, 2, 0, 0)], // R13x59
+    [(26, 1, 27, 1), (14, 1, 15, 1)], // R13x77
+    [(36, 1, 37, 1), (11, 1, 12, 2)], // R13x99
+    [(35, 2, 36, 1), (13, 2, 14, 2)], // R13x139
+    [(33, 1, 0, 0), (7, 1, 8, 1)], // R15x43
+    [(48, 1, 0, 0), (13, 2, 0, 0)], // R15x59
+    [(33, 1, 34, 1), (10, 2, 11, 1)], // R15x77
+    [(44, 2, 0, 0), (12, 4, 0, 0)], // R15x99
+    [(42, 2, 43, 1), (13, 1, 14, 4)], // R15x139
+    [(39, 1, 0, 0), (10, 1, 11, 1)], // R17x43
+    [(28, 2, 0, 0), (14, 2, 0, 0)], // R17x59
+    [(39, 2, 0, 0), (12, 1, 13, 2)], // R17x77
+    [(33, 2, 34, 1), (14, 4, 0, 0)], // R17x99
+    [(38, 4, 0, 0), (12, 2, 13, 4)], // R17x139
+];
+
+/// rMQR format information (ec level bit and version indicator with BCH error correction),
+/// before masking. The sides of the finder pattern and of the sub-finder pattern use different
+/// masks. Index: 32 * ec level bit (0: M, 1: H) + version indicator
+#[rustfmt::skip]
+pub(crate) const RMQR_FORMAT_BITS: [u32; 64] = [
+    0x00000, 0x01F25, 0x0216F, 0x03E4A, 0x042DE, 0x05DFB, 0x063B1, 0x07C94, 0x085BC, 0x09A99,
+    0x0A4D3, 0x0BBF6, 0x0C762, 0x0D847, 0x0E60D, 0x0F928, 0x10B78, 0x1145D, 0x12A17, 0x13532,
+    0x149A6, 0x15683, 0x168C9, 0x177EC, 0x18EC4, 0x191E1, 0x1AFAB, 0x1B08E, 0x1CC1A, 0x1D33F,
+    0x1ED75, 0x1F250, 0x209D5, 0x216F0, 0x228BA, 0x2379F, 0x24B0B, 0x2542E, 0x26A64, 0x27541,
+    0x28C69, 0x2934C, 0x2AD06, 0x2B223, 0x2CEB7, 0x2D192, 0x2EFD8, 0x2F0FD, 0x302AD, 0x31D88,
+    0x323C2, 0x33CE7, 0x34073, 0x35F56, 0x3611C, 0x37E39, 0x38711, 0x39834, 0x3A67E, 0x3B95B,
+    0x3C5CF, 0x3DAEA, 0x3E4A0, 0x3FB85,
+];
+
+/// Masks of the rMQR format information next to the finder pattern and next to the sub-finder
+/// pattern.
+pub(crate) const RMQR_FORMAT_MASKS: [u32; 2] = [0x1FAB2, 0x20A7B];
+
+/// Version information with BCH error correction, for versions 7 to 40.
 #[rustfmt::skip]
-pub(crate) static SIZE_EC_H: [u32; 40] = [
-    9, 16, 26, 36, 46, 60, 66, 86, 100, 122, 140, 158, 180, 197, 223, 253, 283, 313, 341, 385, 406,
-    442, 464, 514, 538, 596, 628, 661, 701, 745, 793, 845, 901, 961, 986, 1054, 1096, 1142, 1222,
-    1276,
+pub(crate) const VERSION_BITS: [u32; 34] = [
+    0x07c94, 0x085bc, 0x09a99, 0x0a4d3, 0x0bbf6, 0x0c762, 0x0d847, 0x0e60d, 0x0f928,
+    0x10b78, 0x1145d, 0x12a17, 0x13532, 0x149a6, 0x15683, 0x168c9, 0x177ec, 0x18ec4,
+    0x191e1, 0x1afab, 0x1b08e, 0x1cc1a, 0x1d33f, 0x1ed75, 0x1f250, 0x209d5, 0x216f0,
+    0x228ba, 0x2379f, 0x24b0b, 0x2542e, 0x26a64, 0x27541, 0x28c69,
 ];
 
+/// Number of characters that fit in each version and ec level, for tests.
+/// Index: 4 * (version - 1) + ec level ordinal
+#[cfg(test)]
 #[rustfmt::skip]
 pub(crate) static NUMERIC_SIZE: [u32; 160] = [
     41, 34, 27, 17, 77, 63, 48, 34, 127, 101, 77, 58, 187, 149, 111, 82, 255, 202, 144, 106, 322,
@@ -52,6 +190,9 @@ pub(crate) static NUMERIC_SIZE: [u32; 160] = [
     2927, 7089, 5596, 3993, 3057,
 ];
 
+/// Number of characters that fit in each version and ec level, for tests.
+/// Index: 4 * (version - 1) + ec level ordinal
+#[cfg(test)]
 #[rustfmt::skip]
 pub(crate) static ALPHANUMERIC_SIZE: [u32; 160] = [
     25, 20, 16, 10, 47, 38, 29, 20, 77, 61, 47, 35, 114, 90, 67, 50, 154, 122, 87, 64, 195, 154,
@@ -66,6 +207,9 @@ pub(crate) static ALPHANUMERIC_SIZE: [u32; 160] = [
     1852,
 ];
 
+/// Number of characters that fit in each version and ec level, for tests.
+/// Index: 4 * (version - 1) + ec level ordinal
+#[cfg(test)]
 #[rustfmt::skip]
 pub(crate) static BYTE_SIZE: [u32; 160] = [
     17, 14, 11, 7, 32, 26, 20, 14, 53, 42, 32, 24, 78, 62, 46, 34, 106, 84, 60, 44, 134, 106, 74,
@@ -79,6 +223,9 @@ pub(crate) static BYTE_SIZE: [u32; 160] = [
     2699, 2099, 1499, 1139, 2809, 2213, 1579, 1219, 2953, 2331, 1663, 1273,
 ];
 
+/// Number of characters that fit in each version and ec level, for tests.
+/// Index: 4 * (version - 1) + ec level ordinal
+#[cfg(test)]
 #[rustfmt::skip]
 pub(crate) static KANJI_SIZE: [u32; 160] = [
     10, 8, 7, 4, 20, 16, 12, 8, 32, 26, 20, 15, 48, 38, 28, 21, 65, 52, 37, 27, 82, 65, 45, 36, 95,
@@ -0,0 +1,211 @@
+use crate::color::Color;
+use crate::qrcode::QrCode;
+use std::fmt::Write;
+
+/// Which modules are printed with the terminal's foreground colour.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Polarity {
+    /// Dark modules are printed, for terminals with a light background.
+    Normal,
+    /// Light modules are printed, for terminals with a dark background.
+    Inverted,
+}
+
+/// Renders a [`QrCode`] as text with two rows of modules per line, using `▀`, `▄` and `█`.
+///
+/// A module is one character wide and half a character high, which is close to square in
+/// most terminal fonts.
+pub struct TerminalRenderer<'a> {
+    qrcode: &'a QrCode,
+    quiet_zone: u32,
+    polarity: Polarity,
+    colors: Option<(Color, Color)>,
+    caption: bool,
+}
+
+impl QrCode {
+    pub fn terminal(&self) -> TerminalRenderer<'_> {
+        TerminalRenderer::new(self)
+    }
+}
+
+impl<'a> TerminalRenderer<'a> {
+    pub fn new(qrcode: &'a QrCode) -> TerminalRenderer<'a> {
+        TerminalRenderer {
+            qrcode,
+            quiet_zone: qrcode.version().quiet_zone(),
+            polarity: Polarity::Inverted,
+            colors: None,
+            caption: true,
+        }
+    }
+
+    /// Width of the quiet zone in modules. By default 4 for regular QR codes, and 2 for Micro
+    /// QR and rMQR codes.
+    pub fn quiet_zone(mut self, quiet_zone: u32) -> Self {
+        self.quiet_zone = quiet_zone;
+        self
+    }
+
+    pub fn polarity(mut self, polarity: Polarity) -> Self {
+        self.polarity = polarity;
+        self
+    }
+
+    /// Draws with 24-bit ANSI colours instead of the terminal's own colours. Dark modules
+    /// use `foreground` and light modules `background`, whatever the polarity.
+    pub fn colors(mut self, foreground: Color, background: Color) -> Self {
+        self.colors = Some((foreground, background));
+        self
+    }
+
+    /// Whether to print the "Version: N" caption below the symbol.
+    pub fn caption(mut self, caption: bool) -> Self {
+        self.caption = caption;
+        self
+    }
+
+    pub fn render(&self) -> String {
+        let total_x = self.qrcode.width() + 2 * self.quiet_zone;
+        let total_y = self.qrcode.height() + 2 * self.quiet_zone;
+        let mut output = String::new();
+
+        for y in (0..total_y).step_by(2) {
+            if let Some((foreground, background)) = self.colors {
+                let _ = write!(
+                    output,
+                    "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m",
+                    foreground.r,
+                    foreground.g,
+                    foreground.b,
+                    background.r,
+                    background.g,
+                    background.b
+                );
+            }
+
+            for x in 0..total_x {
+                // The line below the last row is not part of the symbol
+                let bottom = y + 1 < total_y && self.is_printed(x, y + 1);
+                output.push(match (self.is_printed(x, y), bottom) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                });
+            }
+
+            if self.colors.is_some() {
+                output.push_str("\x1b[0m");
+            }
+            output.push('\n');
+        }
+
+        if self.caption {
+            let caption = format!("Version: {}", self.qrcode.version());
+            let padding = (total_x as usize).saturating_sub(caption.len()) / 2;
+            let _ = writeln!(output, "{}{}", " ".repeat(padding), caption);
+        }
+
+        output
+    }
+
+    fn is_dark(&self, x: u32, y: u32) -> bool {
+        let (width, height) = (self.qrcode.width(), self.qrcode.height());
+        let (Some(x), Some(y)) = (
+            x.checked_sub(self.quiet_zone),
+            y.checked_sub(self.quiet_zone),
+        ) else {
+            return false;
+        };
+
+        x < width && y < height && self.qrcode.data[(x + width * y) as usize].value()
+    }
+
+    fn is_printed(&self, x: u32, y: u32) -> bool {
+        match (self.colors, self.polarity) {
+            (Some(_), _) | (None, Polarity::Normal) => self.is_dark(x, y),
+            (None, Polarity::Inverted) => !self.is_dark(x, y),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ec::EcLevel;
+    use crate::encoding::Encoding;
+    use crate::mask::MaskSelection;
+    use crate::preprocessor::Preprocessor;
+
+    fn qrcode() -> QrCode {
+        Preprocessor::new(
+            "HELLO WORLD",
+            Encoding::Alphanumeric,
+            EcLevel::M,
+            MaskSelection::Auto,
+        )
+        .unwrap()
+        .generate_qrcode()
+        .unwrap()
+    }
+
+    #[test]
+    fn render_packs_two_rows_per_line() {
+        let text = qrcode().terminal().caption(false).render();
+        let lines: Vec<&str> = text.lines().collect();
+
+        // 29 rows with the quiet zone, rounded up to 15 lines
+        assert_eq!(lines.len(), 15);
+        assert!(lines.iter().all(|line| line.chars().count() == 29));
+    }
+
+    #[test]
+    fn render_normal_polarity_prints_dark_modules() {
+        let text = qrcode()
+            .terminal()
+            .quiet_zone(1)
+            .polarity(Polarity::Normal)
+            .caption(false)
+            .render();
+        let lines: Vec<&str> = text.lines().collect();
+
+        // Quiet zone above the top edge of the finder pattern, then its two dark rows
+        assert!(lines[0].starts_with(" ▄▄▄▄▄▄▄ "));
+        assert!(lines[1].starts_with(" █ ▄▄▄ █ "));
+    }
+
+    #[test]
+    fn render_inverted_polarity_prints_light_modules() {
+        let text = qrcode().terminal().quiet_zone(1).caption(false).render();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert!(lines[0].starts_with("█▀▀▀▀▀▀▀█"));
+        assert!(lines[1].starts_with("█ █▀▀▀█ █"));
+    }
+
+    #[test]
+    fn render_wraps_lines_in_ansi_colors() {
+        let text = qrcode()
+            .terminal()
+            .colors(Color::BLACK, Color::rgb(255, 255, 0))
+            .caption(false)
+            .render();
+
+        for line in text.lines() {
+            assert!(line.starts_with("\x1b[38;2;0;0;0m\x1b[48;2;255;255;0m"));
+            assert!(line.ends_with("\x1b[0m"));
+        }
+    }
+
+    #[test]
+    fn render_caption_is_optional() {
+        let qrcode = qrcode();
+        assert!(qrcode.terminal().render().ends_with("Version: 1\n"));
+        assert!(!qrcode
+            .terminal()
+            .caption(false)
+            .render()
+            .contains("Version"));
+    }
+}
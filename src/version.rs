@@ -0,0 +1,266 @@
+use crate::ec::EcLevel;
+use crate::error::QrError;
+use crate::tables::{
+    DATA_BYTES_PER_BLOCK, EC_BYTES_PER_BLOCK, MICRO_DATA_BITS, MICRO_EC_BYTES,
+    RMQR_DATA_BYTES_PER_BLOCK, RMQR_EC_BYTES_PER_BLOCK, RMQR_SIZES,
+};
+use std::fmt;
+use std::fmt::Formatter;
+
+/// Symbol type and size: a Micro QR code M1 to M4, a regular QR code version 1 to 40 or a
+/// rectangular Micro QR code (rMQR) R7x43 to R17x139, given by its height and width.
+///
+/// Square versions are ordered by size, every Micro QR version coming before version 1. rMQR
+/// versions come last, by height then width like their version indicator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Version {
+    Micro(u8),
+    Normal(u8),
+    Rmqr(u8, u8),
+}
+
+impl Version {
+    /// Every valid version: the square ones from the smallest to the largest, then the rMQR
+    /// ones.
+    pub fn all() -> impl Iterator<Item = Version> {
+        (1..=4)
+            .map(Version::Micro)
+            .chain((1..=40).map(Version::Normal))
+            .chain(RMQR_SIZES.map(|(height, width)| Version::Rmqr(height, width)))
+    }
+
+    /// Version number, or the version indicator plus one for rMQR.
+    pub fn number(&self) -> u8 {
+        match self {
+            Version::Micro(number) | Version::Normal(number) => *number,
+            Version::Rmqr(..) => self.rmqr_indicator().map_or(0, |indicator| indicator + 1),
+        }
+    }
+
+    pub fn is_micro(&self) -> bool {
+        matches!(self, Version::Micro(_))
+    }
+
+    pub fn is_rmqr(&self) -> bool {
+        matches!(self, Version::Rmqr(..))
+    }
+
+    /// Index of an rMQR version in the tables, also written in its format information.
+    pub fn rmqr_indicator(&self) -> Option<u8> {
+        match self {
+            Version::Rmqr(height, width) => RMQR_SIZES
+                .iter()
+                .position(|size| *size == (*height, *width))
+                .map(|indicator| indicator as u8),
+            _ => None,
+        }
+    }
+
+    pub fn check(&self) -> Result<(), QrError> {
+        let valid = match self {
+            Version::Micro(number) => (1..=4).contains(number),
+            Version::Normal(number) => (1..=40).contains(number),
+            Version::Rmqr(..) => self.rmqr_indicator().is_some(),
+        };
+        match valid {
+            true => Ok(()),
+            false => Err(QrError::InvalidVersion(*self)),
+        }
+    }
+
+    /// Number of modules per row.
+    pub fn width(&self) -> u32 {
+        match self {
+            Version::Micro(number) => 9 + 2 * *number as u32,
+            Version::Normal(number) => 17 + 4 * *number as u32,
+            Version::Rmqr(_, width) => *width as u32,
+        }
+    }
+
+    /// Number of modules per column, the width except for rMQR.
+    pub fn height(&self) -> u32 {
+        match self {
+            Version::Rmqr(height, _) => *height as u32,
+            _ => self.width(),
+        }
+    }
+
+    /// Recommended width of the quiet zone in modules.
+    pub fn quiet_zone(&self) -> u32 {
+        match self {
+            Version::Micro(_) | Version::Rmqr(..) => 2,
+            Version::Normal(_) => 4,
+        }
+    }
+
+    /// Width of the mode indicator: 4 bits, 0 to 3 bits for M1 to M4 or 3 bits for rMQR.
+    pub fn mode_bits(&self) -> Result<usize, QrError> {
+        self.check()?;
+        Ok(match self {
+            Version::Micro(number) => *number as usize - 1,
+            Version::Normal(_) => 4,
+            Version::Rmqr(..) => 3,
+        })
+    }
+
+    /// Width of the terminator: 4 bits, 3 to 9 bits for M1 to M4 or 3 bits for rMQR.
+    pub fn terminator_bits(&self) -> Result<usize, QrError> {
+        self.check()?;
+        Ok(match self {
+            Version::Micro(number) => 2 * *number as usize + 1,
+            Version::Normal(_) => 4,
+            Version::Rmqr(..) => 3,
+        })
+    }
+
+    /// Data codewords per block (size of group 1 blocks, group 1 block count, size of group 2
+    /// blocks, group 2 block count), or `None` for Micro QR codes, invalid versions or if the
+    /// ec level is not available. rMQR only has the levels M and H.
+    pub(crate) fn blocks(&self, ec_level: EcLevel) -> Option<(usize, usize, usize, usize)> {
+        self.check().ok()?;
+        match self {
+            Version::Micro(_) => None,
+            Version::Normal(number) => {
+                Some(DATA_BYTES_PER_BLOCK[*number as usize - 1][ec_level.ordinal() as usize])
+            }
+            Version::Rmqr(..) => {
+                let indicator = self.rmqr_indicator()? as usize;
+                Some(RMQR_DATA_BYTES_PER_BLOCK[indicator][Self::rmqr_ec_index(ec_level)?])
+            }
+        }
+    }
+
+    /// Index of the rMQR ec levels M and H in the tables, which is also their format bit.
+    pub(crate) fn rmqr_ec_index(ec_level: EcLevel) -> Option<usize> {
+        match ec_level {
+            EcLevel::M => Some(0),
+            EcLevel::H => Some(1),
+            EcLevel::L | EcLevel::Q => None,
+        }
+    }
+
+    /// Number of data bits (without ec codewords), or `None` if the version is not valid or
+    /// the ec level is not available. M1 only has error detection, given as ec level L.
+    pub fn data_capacity(&self, ec_level: EcLevel) -> Option<usize> {
+        self.check().ok()?;
+        match self {
+            Version::Micro(number) => MICRO_DATA_BITS[*number as usize - 1]
+                .get(ec_level.ordinal() as usize)
+                .copied()
+                .filter(|bits| *bits > 0),
+            Version::Normal(_) | Version::Rmqr(..) => {
+                let (block_1_size, block_1_count, block_2_size, block_2_count) =
+                    self.blocks(ec_level)?;
+                Some((block_1_size * block_1_count + block_2_size * block_2_count) * 8)
+            }
+        }
+    }
+
+    /// Number of ec codewords per block, or `None` if the version is not valid or the ec level
+    /// is not available.
+    pub fn ec_codewords(&self, ec_level: EcLevel) -> Option<usize> {
+        self.data_capacity(ec_level)?;
+        match self {
+            Version::Micro(number) => MICRO_EC_BYTES[*number as usize - 1]
+                .get(ec_level.ordinal() as usize)
+                .copied(),
+            Version::Normal(number) => {
+                Some(EC_BYTES_PER_BLOCK[*number as usize - 1][ec_level.ordinal() as usize])
+            }
+            Version::Rmqr(..) => Some(
+                RMQR_EC_BYTES_PER_BLOCK[self.rmqr_indicator()? as usize]
+                    [Self::rmqr_ec_index(ec_level)?],
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Version::Micro(number) => write!(f, "M{}", number),
+            Version::Normal(number) => write!(f, "{}", number),
+            Version::Rmqr(height, width) => write!(f, "R{}x{}", height, width),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_are_ordered_by_size() {
+        let versions: Vec<Version> = Version::all().collect();
+        assert_eq!(versions.len(), 44 + 32);
+        assert!(versions.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(versions[..44]
+            .windows(2)
+            .all(|pair| pair[0].width() < pair[1].width()));
+        assert_eq!(Version::Micro(4).width(), 17);
+        assert_eq!(Version::Normal(1).width(), 21);
+    }
+
+    #[test]
+    fn micro_capacity_depends_on_ec_level() {
+        assert_eq!(Version::Micro(1).data_capacity(EcLevel::L), Some(20));
+        assert_eq!(Version::Micro(1).data_capacity(EcLevel::M), None);
+        assert_eq!(Version::Micro(3).data_capacity(EcLevel::M), Some(68));
+        assert_eq!(Version::Micro(4).data_capacity(EcLevel::Q), Some(80));
+        assert_eq!(Version::Micro(4).data_capacity(EcLevel::H), None);
+        assert_eq!(Version::Normal(1).data_capacity(EcLevel::H), Some(72));
+    }
+
+    #[test]
+    fn rmqr_versions_have_a_height_and_a_width() {
+        let version = Version::Rmqr(11, 27);
+        assert_eq!((version.height(), version.width()), (11, 27));
+        assert_eq!(version.rmqr_indicator(), Some(10));
+        assert_eq!(version.number(), 11);
+        assert_eq!(version.to_string(), "R11x27");
+        assert_eq!(Version::Normal(2).height(), 25);
+    }
+
+    #[test]
+    fn rmqr_capacity_has_levels_m_and_h() {
+        assert_eq!(Version::Rmqr(7, 43).data_capacity(EcLevel::M), Some(48));
+        assert_eq!(Version::Rmqr(7, 43).data_capacity(EcLevel::H), Some(24));
+        assert_eq!(Version::Rmqr(7, 43).data_capacity(EcLevel::L), None);
+        assert_eq!(Version::Rmqr(17, 139).data_capacity(EcLevel::M), Some(1216));
+        assert_eq!(Version::Rmqr(17, 139).ec_codewords(EcLevel::H), Some(26));
+        assert_eq!(Version::Rmqr(17, 139).ec_codewords(EcLevel::Q), None);
+    }
+
+    #[test]
+    fn tables_reject_unknown_versions() {
+        for version in [Version::Micro(0), Version::Normal(0), Version::Normal(41)] {
+            assert_eq!(version.data_capacity(EcLevel::L), None);
+            assert_eq!(version.ec_codewords(EcLevel::L), None);
+            assert_eq!(version.mode_bits(), Err(QrError::InvalidVersion(version)));
+            assert_eq!(
+                version.terminator_bits(),
+                Err(QrError::InvalidVersion(version))
+            );
+        }
+        assert_eq!(Version::Rmqr(7, 27).data_capacity(EcLevel::M), None);
+        assert_eq!(Version::Micro(1).mode_bits(), Ok(0));
+    }
+
+    #[test]
+    fn check_rejects_unknown_versions() {
+        assert!(Version::Micro(4).check().is_ok());
+        assert_eq!(
+            Version::Micro(5).check(),
+            Err(QrError::InvalidVersion(Version::Micro(5)))
+        );
+        assert_eq!(
+            Version::Normal(0).check(),
+            Err(QrError::InvalidVersion(Version::Normal(0)))
+        );
+        assert!(Version::Rmqr(13, 27).check().is_ok());
+        assert_eq!(
+            Version::Rmqr(7, 27).check(),
+            Err(QrError::InvalidVersion(Version::Rmqr(7, 27)))
+        );
+    }
+}
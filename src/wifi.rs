@@ -0,0 +1,426 @@
+use crate::builder::QrCodeBuilder;
+use crate::error::QrError;
+
+/// Authentication type of a Wi-Fi network, the `T` field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Security {
+    /// No password, `nopass`.
+    Open,
+    Wep,
+    /// WPA, WPA2 or WPA3 personal in transition mode, `WPA`.
+    Wpa,
+    /// WPA3 personal only, `SAE`.
+    Wpa3,
+    /// WPA2 or WPA3 enterprise, `WPA2-EAP`, with the EAP fields.
+    Eap,
+}
+
+impl Security {
+    fn name(self) -> &'static str {
+        match self {
+            Security::Open => "nopass",
+            Security::Wep => "WEP",
+            Security::Wpa => "WPA",
+            Security::Wpa3 => "SAE",
+            Security::Eap => "WPA2-EAP",
+        }
+    }
+}
+
+/// "Join this network" payload, `WIFI:T:WPA;S:<ssid>;P:<password>;;`, as read by phone cameras.
+///
+/// Special characters are escaped with a backslash, and an SSID or passphrase that could be
+/// read as hexadecimal is quoted.
+///
+/// ```
+/// use qrcode::wifi::{Security, Wifi};
+///
+/// let wifi = Wifi::new("Office; 2nd floor")
+///     .security(Security::Wpa)
+///     .password("p@ss:word")
+///     .hidden(true);
+/// let data = wifi.data().unwrap();
+/// assert_eq!(data, r"WIFI:T:WPA;S:Office\; 2nd floor;P:p@ss\:word;H:true;;");
+/// assert_eq!(Wifi::parse(&data).unwrap(), wifi);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Wifi {
+    ssid: String,
+    security: Security,
+    password: Option<String>,
+    hidden: bool,
+    eap_method: Option<String>,
+    phase_2: Option<String>,
+    identity: Option<String>,
+    anonymous_identity: Option<String>,
+}
+
+impl Wifi {
+    /// Open network named `ssid`.
+    pub fn new(ssid: &str) -> Wifi {
+        Wifi {
+            ssid: ssid.to_string(),
+            security: Security::Open,
+            password: None,
+            hidden: false,
+            eap_method: None,
+            phase_2: None,
+            identity: None,
+            anonymous_identity: None,
+        }
+    }
+
+    pub fn security(mut self, security: Security) -> Self {
+        self.security = security;
+        self
+    }
+
+    /// WEP key, WPA passphrase or EAP password.
+    pub fn password(mut self, password: &str) -> Self {
+        self.password = Some(password.to_string());
+        self
+    }
+
+    /// The network does not broadcast its SSID.
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    /// EAP method, such as `PEAP`, `TTLS` or `TLS`.
+    pub fn eap_method(mut self, method: &str) -> Self {
+        self.eap_method = Some(method.to_string());
+        self
+    }
+
+    /// Phase 2 authentication of tunnelled EAP methods, such as `MSCHAPV2`.
+    pub fn phase_2(mut self, method: &str) -> Self {
+        self.phase_2 = Some(method.to_string());
+        self
+    }
+
+    /// EAP identity.
+    pub fn identity(mut self, identity: &str) -> Self {
+        self.identity = Some(identity.to_string());
+        self
+    }
+
+    /// EAP outer identity sent before the tunnel is set up.
+    pub fn anonymous_identity(mut self, identity: &str) -> Self {
+        self.anonymous_identity = Some(identity.to_string());
+        self
+    }
+
+    /// Payload to encode, after checking the password against the security type.
+    pub fn data(&self) -> Result<String, QrError> {
+        self.check()?;
+
+        let mut data = format!(
+            "WIFI:T:{};S:{};",
+            self.security.name(),
+            quote(&self.ssid, 0)
+        );
+        let eap_fields = [
+            ("E", &self.eap_method),
+            ("PH2", &self.phase_2),
+            ("A", &self.anonymous_identity),
+            ("I", &self.identity),
+        ];
+        for (key, value) in eap_fields {
+            if let Some(value) = value {
+                data.push_str(&format!("{}:{};", key, escape(value)));
+            }
+        }
+        if let Some(password) = &self.password {
+            data.push_str(&format!("P:{};", quote(password, self.raw_key_length())));
+        }
+        if self.hidden {
+            data.push_str("H:true;");
+        }
+        data.push(';');
+
+        Ok(data)
+    }
+
+    /// Builder of a QR code holding the payload.
+    pub fn builder(&self) -> Result<QrCodeBuilder, QrError> {
+        Ok(QrCodeBuilder::new(&self.data()?))
+    }
+
+    /// Reads back a `WIFI:` payload, such as the text of a decoded symbol. Unknown fields are
+    /// skipped.
+    pub fn parse(data: &str) -> Result<Wifi, QrError> {
+        let invalid = |reason: &str| QrError::InvalidPayload(reason.to_string());
+        let fields = data
+            .strip_prefix("WIFI:")
+            .ok_or_else(|| invalid("a Wi-Fi payload starts with WIFI:"))?;
+
+        let mut wifi = Wifi::new("");
+        let mut ssid = None;
+        let mut chars = fields.chars();
+        // The fields end with an empty one
+        while !chars.as_str().starts_with(';') {
+            let key: String = chars.by_ref().take_while(|c| *c != ':').collect();
+            let value = read_value(&mut chars).ok_or_else(|| invalid("unterminated field"))?;
+
+            match key.as_str() {
+                "T" => {
+                    wifi.security = match value.as_str() {
+                        "nopass" | "" => Security::Open,
+                        "WEP" => Security::Wep,
+                        "WPA" | "WPA2" => Security::Wpa,
+                        "SAE" => Security::Wpa3,
+                        "WPA2-EAP" => Security::Eap,
+                        _ => return Err(invalid(&format!("unknown security type {}", value))),
+                    }
+                }
+                "S" => ssid = Some(value),
+                "P" => wifi.password = Some(value),
+                "H" => wifi.hidden = value == "true",
+                "E" => wifi.eap_method = Some(value),
+                "PH2" => wifi.phase_2 = Some(value),
+                "A" => wifi.anonymous_identity = Some(value),
+                "I" => wifi.identity = Some(value),
+                _ => {}
+            }
+        }
+
+        wifi.ssid = ssid.ok_or_else(|| invalid("the SSID is missing"))?;
+        Ok(wifi)
+    }
+
+    fn check(&self) -> Result<(), QrError> {
+        let invalid = |reason: &str| Err(QrError::InvalidPayload(reason.to_string()));
+
+        if self.ssid.is_empty() || self.ssid.len() > 32 {
+            return invalid("the SSID takes 1 to 32 bytes");
+        }
+
+        let eap = [
+            &self.eap_method,
+            &self.phase_2,
+            &self.identity,
+            &self.anonymous_identity,
+        ];
+        let has_eap_fields = eap.iter().any(|field| field.is_some());
+        let hex = |password: &str| password.chars().all(|c| c.is_ascii_hexdigit());
+        let ascii = |password: &str| password.chars().all(|c| c.is_ascii() && !c.is_control());
+
+        match (self.security, self.password.as_deref()) {
+            (Security::Eap, _) if self.eap_method.is_none() => invalid("EAP needs a method"),
+            (Security::Eap, _) => Ok(()),
+            _ if has_eap_fields => invalid("EAP fields need the EAP security type"),
+            (Security::Open, None) => Ok(()),
+            (Security::Open, Some(_)) => invalid("an open network has no password"),
+            (_, None) => invalid("a protected network needs a password"),
+            (Security::Wep, Some(key)) => match (key.len(), hex(key)) {
+                (10 | 26, true) | (5 | 13, _) if ascii(key) => Ok(()),
+                _ => invalid("a WEP key takes 5 or 13 characters, or 10 or 26 hex digits"),
+            },
+            (_, Some(passphrase)) => match (passphrase.len(), hex(passphrase)) {
+                (64, true) => Ok(()),
+                (8..=63, _) if ascii(passphrase) => Ok(()),
+                _ => invalid("a WPA passphrase takes 8 to 63 ASCII characters, or 64 hex digits"),
+            },
+        }
+    }
+
+    /// Length of the password when it is a raw hexadecimal key instead of a passphrase.
+    fn raw_key_length(&self) -> usize {
+        match (self.security, self.password.as_ref().map(String::len)) {
+            (Security::Wep, Some(length @ (10 | 26))) => length,
+            (Security::Wpa | Security::Wpa3, Some(64)) => 64,
+            _ => 0,
+        }
+    }
+}
+
+/// Backslash before each special character of the payload.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | ';' | ',' | ':' | '"') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Escaped value, in double quotes if it could be read as hexadecimal, unless it is a raw key
+/// of `raw_key_length` digits.
+fn quote(value: &str, raw_key_length: usize) -> String {
+    let hex = !value.is_empty() && value.chars().all(|c| c.is_ascii_hexdigit());
+    match hex && value.len() != raw_key_length {
+        true => format!("\"{}\"", value),
+        false => escape(value),
+    }
+}
+
+/// Unescaped value up to the next unescaped `;`, without the double quotes around it, or `None`
+/// if there is no `;`.
+fn read_value(chars: &mut std::str::Chars) -> Option<String> {
+    let mut value = String::new();
+    // Positions of the unescaped double quotes
+    let mut quotes = vec![];
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => value.push(chars.next()?),
+            ';' => {
+                let last = value.chars().count().saturating_sub(1);
+                if value.len() >= 2 && quotes.first() == Some(&0) && quotes.last() == Some(&last) {
+                    value = value[1..value.len() - 1].to_string();
+                }
+                return Some(value);
+            }
+            c => {
+                if c == '"' {
+                    quotes.push(value.chars().count());
+                }
+                value.push(c);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::decode;
+
+    #[test]
+    fn data_escapes_special_characters() {
+        let wifi = Wifi::new(r#"a;b,c:d\e"f"#)
+            .security(Security::Wpa3)
+            .password("12345678;");
+        assert_eq!(
+            wifi.data().unwrap(),
+            r#"WIFI:T:SAE;S:a\;b\,c\:d\\e\"f;P:12345678\;;;"#
+        );
+        assert_eq!(
+            Wifi::new("Lobby").data().unwrap(),
+            "WIFI:T:nopass;S:Lobby;;"
+        );
+    }
+
+    #[test]
+    fn data_quotes_values_that_look_hexadecimal() {
+        let wifi = Wifi::new("CAFE")
+            .security(Security::Wpa)
+            .password("12345678");
+        assert_eq!(
+            wifi.data().unwrap(),
+            r#"WIFI:T:WPA;S:"CAFE";P:"12345678";;"#
+        );
+
+        // Raw keys are hexadecimal
+        let wifi = Wifi::new("Lobby")
+            .security(Security::Wep)
+            .password("0123456789");
+        assert_eq!(wifi.data().unwrap(), "WIFI:T:WEP;S:Lobby;P:0123456789;;");
+    }
+
+    #[test]
+    fn data_writes_eap_fields() {
+        let wifi = Wifi::new("Corp")
+            .security(Security::Eap)
+            .eap_method("PEAP")
+            .phase_2("MSCHAPV2")
+            .anonymous_identity("anonymous")
+            .identity("jdoe")
+            .password("secret");
+        assert_eq!(
+            wifi.data().unwrap(),
+            "WIFI:T:WPA2-EAP;S:Corp;E:PEAP;PH2:MSCHAPV2;A:anonymous;I:jdoe;P:secret;;"
+        );
+    }
+
+    #[test]
+    fn data_checks_the_password() {
+        let reason = |wifi: Wifi| match wifi.data() {
+            Err(QrError::InvalidPayload(reason)) => reason,
+            result => panic!("{:?}", result),
+        };
+
+        assert_eq!(reason(Wifi::new("")), "the SSID takes 1 to 32 bytes");
+        assert_eq!(
+            reason(Wifi::new("Lobby").password("secret")),
+            "an open network has no password"
+        );
+        assert_eq!(
+            reason(Wifi::new("Lobby").security(Security::Wpa)),
+            "a protected network needs a password"
+        );
+        assert_eq!(
+            reason(Wifi::new("Lobby").security(Security::Wpa).password("short")),
+            "a WPA passphrase takes 8 to 63 ASCII characters, or 64 hex digits"
+        );
+        assert_eq!(
+            reason(
+                Wifi::new("Lobby")
+                    .security(Security::Wep)
+                    .password("123456")
+            ),
+            "a WEP key takes 5 or 13 characters, or 10 or 26 hex digits"
+        );
+        assert_eq!(
+            reason(Wifi::new("Lobby").security(Security::Eap)),
+            "EAP needs a method"
+        );
+        assert_eq!(
+            reason(
+                Wifi::new("Lobby")
+                    .security(Security::Wpa)
+                    .password("12345678")
+                    .identity("jdoe")
+            ),
+            "EAP fields need the EAP security type"
+        );
+    }
+
+    #[test]
+    fn parse_reads_back_the_payload() {
+        let networks = [
+            Wifi::new(r#"a;b,c:d\e"f"#)
+                .security(Security::Wpa)
+                .password(r#""quoted""#)
+                .hidden(true),
+            Wifi::new("CAFE")
+                .security(Security::Wep)
+                .password("0123456789"),
+            Wifi::new("CAFE").security(Security::Wep).password("CAFE0"),
+            Wifi::new("Corp")
+                .security(Security::Eap)
+                .eap_method("TTLS")
+                .identity("jdoe"),
+            Wifi::new("Lobby"),
+        ];
+        for wifi in networks {
+            assert_eq!(Wifi::parse(&wifi.data().unwrap()).unwrap(), wifi);
+        }
+
+        // Fields in any order, unknown ones skipped
+        let wifi = Wifi::parse("WIFI:S:Home;R:1;P:password;T:WPA;;").unwrap();
+        assert_eq!(
+            wifi,
+            Wifi::new("Home")
+                .security(Security::Wpa)
+                .password("password")
+        );
+
+        assert!(Wifi::parse("WIFI:T:WPA;P:password;;").is_err());
+        assert!(Wifi::parse("WIFI:S:Home").is_err());
+        assert!(Wifi::parse("MECARD:N:Doe;;").is_err());
+    }
+
+    #[test]
+    fn builder_encodes_the_payload() {
+        let wifi = Wifi::new("Café")
+            .security(Security::Wpa)
+            .password("correct horse");
+        let qrcode = wifi.builder().unwrap().build().unwrap();
+        let text = decode(&qrcode).unwrap().text().unwrap();
+        assert_eq!(Wifi::parse(&text).unwrap(), wifi);
+    }
+}